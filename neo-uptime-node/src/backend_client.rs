@@ -1,10 +1,21 @@
 use anyhow::{Context, Result};
-use reqwest::Client;
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Deserializer, Serialize};
-use std::collections::HashMap;
-use std::time::Duration;
+use sha2::Sha256;
+use futures::{stream, StreamExt as _};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
+use crate::db::{operations::ProbeIdentityOperations, CertStatus, Db, ProbeErrorKind};
+use crate::node_load::NodeLoad;
+use crate::probe_backend::ProbeBackend;
+use crate::sla::NodeSla;
+
 /// Custom deserializer that handles both string timestamps and empty objects
 fn deserialize_optional_timestamp<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
 where
@@ -22,11 +33,152 @@ where
     }
 }
 
+/// Maximum number of in-flight per-node heartbeat requests when the batch endpoint is unavailable
+const HEARTBEAT_FALLBACK_CONCURRENCY: usize = 8;
+/// Maximum number of in-flight per-node private info requests when the list endpoint is unavailable
+const PRIVATE_INFO_FETCH_CONCURRENCY: usize = 8;
+
+/// How long an optional endpoint that answered 404 is skipped before it is tried again
+const UNSUPPORTED_ENDPOINT_RETRY: Duration = Duration::from_secs(3600);
+
+const PROBE_ID_HEADER: &str = "x-neo-uptime-probe-id";
+const TIMESTAMP_HEADER: &str = "x-neo-uptime-timestamp";
+const NONCE_HEADER: &str = "x-neo-uptime-nonce";
+const SIGNATURE_HEADER: &str = "x-neo-uptime-signature";
+
 /// Backend API client for distributed probe mode
 pub struct BackendClient {
     client: Client,
     base_url: String,
     api_key: Option<String>,
+    /// Skipped for a while after the backend answers 404 on the batch heartbeat endpoint
+    batch_heartbeat_supported: EndpointSupport,
+    /// Skipped for a while after the backend answers 404 on the private info list endpoint
+    private_info_list_supported: EndpointSupport,
+    /// Last private info list with its validators, revalidated on every fetch
+    private_info_list_cache: Mutex<Option<Cached<Vec<NodePrivateInfo>>>>,
+    /// Last private info of each node with its validators, revalidated on every fetch
+    private_info_cache: Mutex<HashMap<i32, Cached<NodePrivateInfo>>>,
+    /// Skipped for a while after the backend answers 404 on the probe list endpoint
+    probe_list_supported: EndpointSupport,
+    /// Skipped for a while after the backend answers 404 on the SLA report endpoint
+    sla_report_supported: EndpointSupport,
+    /// Where the probe identity is persisted and what is sent when registering
+    registration: Option<(Db, ProbeRegistration)>,
+    /// Identity assigned by the backend, reports are signed once it is set
+    identity: Mutex<Option<ProbeIdentity>>,
+    /// Held while registering, so concurrent rejected requests register only once
+    registering: tokio::sync::Mutex<()>,
+}
+
+/// Name and region the probe registers with
+#[derive(Debug, Clone, Default)]
+pub struct ProbeRegistration {
+    /// Human readable probe name, generated once and persisted when not set
+    pub name: Option<String>,
+    pub region: Option<String>,
+}
+
+/// Probe ID and signing secret of a registered probe
+#[derive(Debug, Clone)]
+struct ProbeIdentity {
+    probe_id: String,
+    secret: String,
+}
+
+impl ProbeIdentity {
+    /// Add the probe ID and signature headers
+    fn sign(&self, request: RequestBuilder, body: &[u8]) -> RequestBuilder {
+        let timestamp = chrono::Utc::now().timestamp();
+        let nonce = uuid::Uuid::new_v4().simple().to_string();
+        request
+            .header(PROBE_ID_HEADER, &self.probe_id)
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(
+                SIGNATURE_HEADER,
+                sign_report(&self.secret, timestamp, &nonce, body),
+            )
+            .header(NONCE_HEADER, nonce)
+    }
+}
+
+/// Whether an optional backend endpoint is requested
+///
+/// A 404 marks the endpoint unsupported; it is tried again once `retry_after` has passed,
+/// so a backend upgraded while the probe is running is picked up.
+#[derive(Debug)]
+struct EndpointSupport {
+    retry_after: Duration,
+    unsupported_since: Mutex<Option<Instant>>,
+}
+
+impl EndpointSupport {
+    fn new(retry_after: Duration) -> Self {
+        Self {
+            retry_after,
+            unsupported_since: Mutex::new(None),
+        }
+    }
+
+    fn is_supported(&self) -> bool {
+        self.unsupported_since
+            .lock()
+            .unwrap()
+            .is_none_or(|since| since.elapsed() >= self.retry_after)
+    }
+
+    fn mark_unsupported(&self) {
+        *self.unsupported_since.lock().unwrap() = Some(Instant::now());
+    }
+
+    fn mark_supported(&self) {
+        *self.unsupported_since.lock().unwrap() = None;
+    }
+}
+
+/// Response body cached together with the validators the backend sent for it
+#[derive(Debug, Clone)]
+struct Cached<T> {
+    value: T,
+    validators: CacheValidators,
+}
+
+/// `ETag` / `Last-Modified` of a cached response, sent back as conditional request headers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct CacheValidators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl CacheValidators {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let get = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            etag: get(header::ETAG),
+            last_modified: get(header::LAST_MODIFIED),
+        }
+    }
+
+    fn apply(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(etag) = &self.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &self.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+        request
+    }
+}
+
+/// Result of a conditional GET
+enum Conditional<T> {
+    Modified(T, CacheValidators),
+    NotModified,
 }
 
 /// Peer node information from backend
@@ -50,6 +202,7 @@ pub struct BackendPeer {
     pub network_name: Option<String>,
     #[serde(default)]
     pub network_secret: Option<String>,
+    #[serde(default)]
     pub status: String,
     #[serde(default)]
     pub latency_ms: Option<i32>,
@@ -57,6 +210,24 @@ pub struct BackendPeer {
     pub peer: Option<i32>,
     #[serde(default)]
     pub last_heartbeat: Option<String>,
+    /// Per-node health check interval in seconds, overrides the probe default
+    #[serde(default)]
+    pub check_interval: Option<u64>,
+    /// Every listener URL of the node (e.g. `udp://host:11010`), probed one by one
+    #[serde(default)]
+    pub listeners: Vec<String>,
+    /// Tags attached to the node in the backend, used for selective monitoring
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl BackendPeer {
+    /// Health check interval requested by the backend for this peer
+    pub fn requested_check_interval(&self) -> Option<Duration> {
+        self.check_interval
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
 }
 
 /// Response from GET /node-status endpoint (for getting node IDs)
@@ -73,7 +244,7 @@ pub struct NodeStatus {
 }
 
 /// Private node information from GET /nodes/{node_id}/private-info
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct NodePrivateInfo {
     pub id: i32,
     #[serde(default)]
@@ -98,6 +269,46 @@ pub struct NodePrivateInfo {
     pub network_name: Option<String>,
     #[serde(default)]
     pub network_secret: Option<String>,
+    /// Per-node health check interval in seconds
+    #[serde(default)]
+    pub check_interval: Option<u64>,
+    /// Every listener URL of the node
+    #[serde(default)]
+    pub listeners: Vec<String>,
+    /// Tags attached to the node
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl NodePrivateInfo {
+    /// Combine with the public node status into a peer to monitor
+    fn into_backend_peer(self, node_status: &NodeStatus) -> BackendPeer {
+        // Generate a default name if not provided
+        let name = self
+            .name
+            .or_else(|| self.network_name.clone())
+            .unwrap_or_else(|| format!("node-{}", self.id));
+
+        BackendPeer {
+            id: self.id,
+            name,
+            description: self.description,
+            sponsor: self.sponsor,
+            location: self.location,
+            allow_relay: self.allow_relay,
+            public_ip: self.public_ip,
+            protocol: self.protocol,
+            network_name: self.network_name,
+            network_secret: self.network_secret,
+            status: node_status.status.clone(),
+            latency_ms: node_status.latency_ms,
+            peer: node_status.peer,
+            last_heartbeat: node_status.last_heartbeat.clone(),
+            check_interval: self.check_interval,
+            listeners: self.listeners,
+            tags: self.tags,
+        }
+    }
 }
 
 /// Response from GET /peers endpoint (deprecated, keeping for compatibility)
@@ -115,12 +326,118 @@ pub struct PeersData {
     pub next_batch_available: bool,
 }
 
+/// Request body for POST /probes/register endpoint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProbeRegisterRequest {
+    /// ID assigned by an earlier registration, absent on first start
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probe_id: Option<String>,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    pub version: String,
+    /// HMAC key the probe signs its reports with
+    pub secret: String,
+}
+
+/// Response from POST /probes/register endpoint
+#[derive(Debug, Deserialize)]
+pub struct ProbeRegisterResponse {
+    pub probe_id: String,
+}
+
+/// Request body for POST /probes/offline endpoint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProbeOfflineRequest {
+    pub probe_id: String,
+    pub reason: String,
+}
+
+/// Entry of GET /probes, a probe of the same region
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeInfo {
+    pub probe_id: String,
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Last request the backend received from the probe
+    #[serde(default)]
+    pub last_seen_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Request body for POST /nodes/sla endpoint
+#[derive(Debug, Serialize)]
+pub struct SlaReportRequest<'a> {
+    pub nodes: &'a [NodeSla],
+}
+
 /// Request body for POST /nodes/:node_id/heartbeat endpoint
 #[derive(Debug, Serialize)]
 pub struct HeartbeatRequest {
     pub status: String,
     pub peer: i32,
     pub latency_ms: i32,
+    /// Time the measurement was taken, set when replaying delayed heartbeats
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checked_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Derived node state (up/degraded/down) behind `status`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<HeartbeatQuality>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<ProbeErrorKind>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub listeners: Vec<ListenerHeartbeat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load: Option<NodeLoad>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub certificates: Vec<CertificateHeartbeat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub version_outdated: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<String>,
+}
+
+/// Result of the last check of a single listener, attached to heartbeats
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListenerHeartbeat {
+    pub url: String,
+    pub protocol: String,
+    pub status: String,
+    #[serde(default)]
+    pub latency_ms: Option<i32>,
+    #[serde(default)]
+    pub error_kind: Option<ProbeErrorKind>,
+}
+
+/// Last TLS certificate check of a wss or quic address, attached to heartbeats
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CertificateHeartbeat {
+    pub url: String,
+    pub status: CertStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname_match: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trusted: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Link quality over the probe's sliding window, attached to heartbeats
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeartbeatQuality {
+    pub loss_rate: Option<f64>,
+    pub jitter_ms: Option<f64>,
+    pub p50_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
 }
 
 /// Response from POST /nodes/:node_id/heartbeat endpoint
@@ -143,6 +460,87 @@ pub struct HeartbeatData {
     pub timestamp: Option<String>,
 }
 
+/// Single peer entry of POST /nodes/heartbeats
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatItem {
+    pub node_id: i32,
+    pub status: String,
+    pub peer: i32,
+    pub latency_ms: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checked_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Derived node state (up/degraded/down) behind `status`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    /// Loss rate, jitter and latency percentiles behind `latency_ms`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<HeartbeatQuality>,
+    /// Classified reason of the last failed check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<ProbeErrorKind>,
+    /// Per-protocol status of every checked listener
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub listeners: Vec<ListenerHeartbeat>,
+    /// Measured foreign networks and peers hosted by the node, `peer` is taken from it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load: Option<NodeLoad>,
+    /// TLS certificates presented by the node's wss and quic addresses
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub certificates: Vec<CertificateHeartbeat>,
+    /// EasyTier version last reported by the node
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Set when `version` is older than the configured minimum version
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub version_outdated: bool,
+    /// Feature flags the node announced in its last successful handshake
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<String>,
+}
+
+/// Request body for POST /nodes/heartbeats endpoint
+#[derive(Debug, Serialize)]
+pub struct BatchHeartbeatRequest<'a> {
+    pub heartbeats: &'a [HeartbeatItem],
+}
+
+/// Response from POST /nodes/heartbeats endpoint
+#[derive(Debug, Deserialize)]
+pub struct BatchHeartbeatResponse {
+    pub success: bool,
+    #[serde(default)]
+    pub results: Vec<BatchHeartbeatItemResult>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchHeartbeatItemResult {
+    pub node_id: i32,
+    pub success: bool,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Delivery outcome of a single peer heartbeat
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeartbeatOutcome {
+    pub node_id: i32,
+    pub error: Option<String>,
+    /// The backend refused this heartbeat itself (e.g. unknown node), so sending it again
+    /// cannot succeed
+    pub rejected: bool,
+}
+
+impl HeartbeatOutcome {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Failed for good rather than because the backend could not be reached
+    pub fn is_rejected(&self) -> bool {
+        self.error.is_some() && self.rejected
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct NodeStatusData {
     pub node_id: i32,
@@ -168,12 +566,307 @@ impl BackendClient {
             client,
             base_url,
             api_key,
+            batch_heartbeat_supported: EndpointSupport::new(UNSUPPORTED_ENDPOINT_RETRY),
+            private_info_list_supported: EndpointSupport::new(UNSUPPORTED_ENDPOINT_RETRY),
+            private_info_list_cache: Mutex::new(None),
+            private_info_cache: Mutex::new(HashMap::new()),
+            probe_list_supported: EndpointSupport::new(UNSUPPORTED_ENDPOINT_RETRY),
+            sla_report_supported: EndpointSupport::new(UNSUPPORTED_ENDPOINT_RETRY),
+            registration: None,
+            identity: Mutex::new(None),
+            registering: tokio::sync::Mutex::new(()),
         })
     }
 
+    /// Register with the backend on startup, persisting the probe identity in `db`
+    pub fn with_registration(mut self, db: Db, registration: ProbeRegistration) -> Self {
+        self.registration = Some((db, registration));
+        self
+    }
+
+    /// Probe ID assigned by the backend, if registered
+    pub fn probe_id(&self) -> Option<String> {
+        self.identity
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|identity| identity.probe_id.clone())
+    }
+
+    /// Register via POST /probes/register, reusing the identity stored for this backend
+    ///
+    /// Re-registering a stored identity is signed with its current secret. When the backend
+    /// rejects it (401), the probe registers again as a new probe with a new secret.
+    /// Falls back to a plain connection test with unsigned reports when the backend does
+    /// not provide the endpoint (404) or registration is not configured.
+    pub async fn register(&self) -> Result<()> {
+        let Some((db, registration)) = &self.registration else {
+            return self.test_connection().await;
+        };
+
+        let _registering = self.registering.lock().await;
+        let stored = ProbeIdentityOperations::get_by_backend(db, &self.base_url)
+            .await
+            .context("Failed to load probe identity")?;
+        let name = stored.as_ref().map(|identity| identity.name.clone());
+        let previous = stored.map(|identity| ProbeIdentity {
+            probe_id: identity.probe_id,
+            secret: identity.secret,
+        });
+
+        match self
+            .register_as(db, registration, previous.clone(), name.clone())
+            .await
+        {
+            Err(e) if HttpStatusError::status_of(&e) == Some(StatusCode::UNAUTHORIZED) => {
+                let Some(previous) = previous else {
+                    return Err(e);
+                };
+                warn!(
+                    "Backend rejected stored probe {}, registering as a new probe: {:#}",
+                    previous.probe_id, e
+                );
+                self.register_as(db, registration, None, name).await
+            }
+            result => result,
+        }
+    }
+
+    /// Register as a new probe after the backend rejected the signature of `rejected`
+    ///
+    /// Does nothing when another request already registered again in the meantime.
+    async fn reregister(&self, rejected: &ProbeIdentity) -> Result<()> {
+        let Some((db, registration)) = &self.registration else {
+            return Ok(());
+        };
+
+        let _registering = self.registering.lock().await;
+        let current = self.identity.lock().unwrap().clone();
+        if current.is_none_or(|current| current.secret != rejected.secret) {
+            return Ok(());
+        }
+
+        warn!(
+            "Backend rejected the signature of probe {}, registering as a new probe",
+            rejected.probe_id
+        );
+        let name = ProbeIdentityOperations::get_by_backend(db, &self.base_url)
+            .await
+            .context("Failed to load probe identity")?
+            .map(|identity| identity.name);
+        self.register_as(db, registration, None, name).await
+    }
+
+    /// POST /probes/register, signed with `previous` when re-registering an existing probe
+    async fn register_as(
+        &self,
+        db: &Db,
+        registration: &ProbeRegistration,
+        previous: Option<ProbeIdentity>,
+        stored_name: Option<String>,
+    ) -> Result<()> {
+        let secret = previous
+            .as_ref()
+            .map(|identity| identity.secret.clone())
+            .unwrap_or_else(generate_probe_secret);
+        let name = registration
+            .name
+            .clone()
+            .or(stored_name)
+            .unwrap_or_else(|| {
+                format!("probe-{}", &uuid::Uuid::new_v4().simple().to_string()[..8])
+            });
+        let body = ProbeRegisterRequest {
+            probe_id: previous.as_ref().map(|identity| identity.probe_id.clone()),
+            name: name.clone(),
+            region: registration.region.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            secret: secret.clone(),
+        };
+
+        let url = format!("{}/probes/register", self.base_url);
+        debug!("Registering probe {} with backend: {}", name, url);
+        let response = self
+            .post_json_signed(&url, &body, previous.as_ref())?
+            .send()
+            .await
+            .context("Failed to send probe registration to backend")?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            warn!("Backend does not support probe registration, reports will not be signed");
+            return self.test_connection().await;
+        }
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(HttpStatusError { status, error_text })
+                .context("Failed to register probe with backend");
+        }
+
+        let registered: ProbeRegisterResponse = response
+            .json()
+            .await
+            .context("Failed to parse probe registration response")?;
+        ProbeIdentityOperations::save(
+            db,
+            &self.base_url,
+            registered.probe_id.clone(),
+            name,
+            registration.region.clone(),
+            secret.clone(),
+        )
+        .await
+        .context("Failed to store probe identity")?;
+
+        info!("Registered with backend as probe {}", registered.probe_id);
+        *self.identity.lock().unwrap() = Some(ProbeIdentity {
+            probe_id: registered.probe_id,
+            secret,
+        });
+        Ok(())
+    }
+
+    /// POST a JSON body with the API key, signed with the probe secret once registered
+    fn post_json<T: Serialize + ?Sized>(&self, url: &str, body: &T) -> Result<RequestBuilder> {
+        let identity = self.identity.lock().unwrap().clone();
+        self.post_json_signed(url, body, identity.as_ref())
+    }
+
+    /// POST a JSON body with the API key, signed with `identity` when given
+    fn post_json_signed<T: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        body: &T,
+        identity: Option<&ProbeIdentity>,
+    ) -> Result<RequestBuilder> {
+        let body = serde_json::to_vec(body).context("Failed to serialize request body")?;
+        let mut request = self
+            .client
+            .post(url)
+            .header("user-agent", "easytier-uptime")
+            .header(header::CONTENT_TYPE, "application/json");
+
+        // Add API key authentication using Bearer token
+        if let Some(api_key) = &self.api_key {
+            request = request.header("authorization", format!("Bearer {}", api_key));
+        }
+
+        if let Some(identity) = identity {
+            request = identity.sign(request, &body);
+        }
+        Ok(request.body(body))
+    }
+
+    /// Add the probe ID and signature headers once registered
+    fn sign(&self, request: RequestBuilder, body: &[u8]) -> RequestBuilder {
+        match self.identity.lock().unwrap().as_ref() {
+            Some(identity) => identity.sign(request, body),
+            None => request,
+        }
+    }
+
+    /// Send a request built by `build`, registering again once when the backend rejects
+    /// the probe signature (401)
+    ///
+    /// `build` is called again for the retry, so the request is signed with the new identity.
+    async fn send_signed(&self, build: impl Fn() -> Result<RequestBuilder>) -> Result<Response> {
+        let identity = self.identity.lock().unwrap().clone();
+        let response = build()?.send().await?;
+        let Some(identity) = identity else {
+            return Ok(response);
+        };
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        self.reregister(&identity)
+            .await
+            .context("Failed to register again after the backend rejected the probe signature")?;
+        Ok(build()?.send().await?)
+    }
+
+    /// GET /probes, the probes of a region that coordinate node assignment
+    ///
+    /// The request is signed, so the backend also records it as a sign of life of this probe.
+    /// Returns `None` when this probe is not registered or the backend has no probe list.
+    pub async fn fetch_probes(&self, region: Option<&str>) -> Result<Option<Vec<ProbeInfo>>> {
+        if self.identity.lock().unwrap().is_none()
+            || !self.probe_list_supported.is_supported()
+        {
+            return Ok(None);
+        }
+
+        let url = format!("{}/probes", self.base_url);
+        let response = self
+            .send_signed(|| {
+                let mut request = self.authorized_get(&url);
+                if let Some(region) = region {
+                    request = request.query(&[("region", region)]);
+                }
+                Ok(self.sign(request, &[]))
+            })
+            .await
+            .context("Failed to fetch probe list from backend")?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            info!("Backend has no probe list, monitoring every node");
+            self.probe_list_supported.mark_unsupported();
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(HttpStatusError { status, error_text })
+                .context("Failed to fetch probe list from backend");
+        }
+
+        let probes = response
+            .json()
+            .await
+            .context("Failed to parse probe list response")?;
+        self.probe_list_supported.mark_supported();
+        Ok(Some(probes))
+    }
+
+    /// GET request with the API key as Bearer token
+    fn authorized_get(&self, url: &str) -> RequestBuilder {
+        let mut request = self.client.get(url).header("user-agent", "easytier-uptime");
+        if let Some(api_key) = &self.api_key {
+            request = request.header("authorization", format!("Bearer {}", api_key));
+        }
+        request
+    }
+
+    /// Send a conditional GET and parse the body when it changed
+    async fn conditional_get<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        validators: Option<&CacheValidators>,
+    ) -> Result<Conditional<T>> {
+        let mut request = self.authorized_get(url);
+        if let Some(validators) = validators {
+            request = validators.apply(request);
+        }
+
+        let response = request.send().await.context("Failed to send request")?;
+        let status = response.status();
+        if status == StatusCode::NOT_MODIFIED {
+            return Ok(Conditional::NotModified);
+        }
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(HttpStatusError { status, error_text }.into());
+        }
+
+        let validators = CacheValidators::from_headers(response.headers());
+        let value = response.json().await.context("Failed to parse response")?;
+        Ok(Conditional::Modified(value, validators))
+    }
+
     /// Fetch peers from backend using two-step process:
     /// 1. GET /node-status to get all node IDs (no auth)
-    /// 2. GET /nodes/{node_id}/private-info to get connection details (with auth)
+    /// 2. GET /nodes/private-info, or GET /nodes/{node_id}/private-info per node when the
+    ///    list endpoint is unavailable, to get connection details (with auth, conditional)
     pub async fn fetch_peers(&self, region: Option<&str>) -> Result<Vec<BackendPeer>> {
         // Step 1: Get all node statuses (no authentication)
         let node_status_url = format!("{}/node-status", self.base_url);
@@ -204,72 +897,141 @@ impl BackendClient {
 
         info!("Fetched {} node statuses from backend", node_statuses.len());
 
-        // Step 2: Fetch private info for each node (with authentication)
-        let mut peers = Vec::new();
-        
-        for node_status in node_statuses {
-            let private_info_url = format!("{}/nodes/{}/private-info", self.base_url, node_status.node_id);
-            debug!("Fetching private info for node {}: {}", node_status.node_id, private_info_url);
-
-            let mut request = self.client.get(&private_info_url)
-                .header("user-agent", "easytier-uptime");
-
-            // Add API key authentication using Bearer token
-            if let Some(api_key) = &self.api_key {
-                request = request.header("authorization", format!("Bearer {}", api_key));
-            }
+        // Step 2: Fetch private info (with authentication), unchanged nodes come from the cache
+        let mut private_infos = self.fetch_private_infos(&node_statuses).await;
+        let peers = node_statuses
+            .iter()
+            .filter_map(|node_status| {
+                let private_info = private_infos.remove(&node_status.node_id);
+                if private_info.is_none() {
+                    debug!("No private info for node {}, skipping", node_status.node_id);
+                }
+                private_info.map(|info| info.into_backend_peer(node_status))
+            })
+            .collect::<Vec<_>>();
 
-            match request.send().await {
-                Ok(response) => {
-                    let status_code = response.status();
-                    if !status_code.is_success() {
-                        let error_text = response.text().await.unwrap_or_default();
-                        warn!(
-                            "Failed to fetch private info for node {}: status={}, error={}",
-                            node_status.node_id, status_code, error_text
-                        );
-                        continue;
-                    }
+        info!("Successfully fetched detailed info for {} peers from backend", peers.len());
+        Ok(peers)
+    }
 
-                    match response.json::<NodePrivateInfo>().await {
-                        Ok(private_info) => {
-                            // Generate a default name if not provided
-                            let name = private_info.name
-                                .or_else(|| private_info.network_name.clone())
-                                .unwrap_or_else(|| format!("node-{}", private_info.id));
-                            
-                            // Combine node status and private info into BackendPeer
-                            let peer = BackendPeer {
-                                id: private_info.id,
-                                name,
-                                description: private_info.description,
-                                sponsor: private_info.sponsor,
-                                location: private_info.location,
-                                allow_relay: private_info.allow_relay,
-                                public_ip: private_info.public_ip,
-                                protocol: private_info.protocol,
-                                network_name: private_info.network_name,
-                                network_secret: private_info.network_secret,
-                                status: node_status.status.clone(),
-                                latency_ms: node_status.latency_ms,
-                                peer: node_status.peer,
-                                last_heartbeat: node_status.last_heartbeat.clone(),
-                            };
-                            peers.push(peer);
-                        }
-                        Err(e) => {
-                            warn!("Failed to parse private info for node {}: {}", node_status.node_id, e);
-                        }
-                    }
+    /// Private info of the given nodes, keyed by node ID
+    ///
+    /// Uses the list endpoint when the backend provides it, otherwise fetches each node
+    /// with bounded concurrency. Both are conditional requests against the local cache.
+    async fn fetch_private_infos(
+        &self,
+        node_statuses: &[NodeStatus],
+    ) -> HashMap<i32, NodePrivateInfo> {
+        if self.private_info_list_supported.is_supported() {
+            match self.fetch_private_info_list().await {
+                Ok(list) => {
+                    self.private_info_list_supported.mark_supported();
+                    return list.into_iter().map(|info| (info.id, info)).collect();
+                }
+                Err(e) if HttpStatusError::status_of(&e) == Some(StatusCode::NOT_FOUND) => {
+                    info!("Backend has no private info list endpoint, fetching nodes one by one");
+                    self.private_info_list_supported.mark_unsupported();
                 }
                 Err(e) => {
-                    warn!("Failed to fetch private info for node {}: {}", node_status.node_id, e);
+                    warn!(
+                        "Failed to fetch private info list, fetching nodes one by one: {:#}",
+                        e
+                    );
                 }
             }
         }
 
-        info!("Successfully fetched detailed info for {} peers from backend", peers.len());
-        Ok(peers)
+        // Forget nodes the backend no longer lists
+        self.private_info_cache
+            .lock()
+            .unwrap()
+            .retain(|node_id, _| node_statuses.iter().any(|s| s.node_id == *node_id));
+
+        let requests = node_statuses
+            .iter()
+            .map(|node_status| self.fetch_private_info(node_status.node_id))
+            .collect::<Vec<_>>();
+        stream::iter(requests)
+            .buffer_unordered(PRIVATE_INFO_FETCH_CONCURRENCY)
+            .filter_map(|info| async move { info })
+            .map(|info| (info.id, info))
+            .collect()
+            .await
+    }
+
+    /// GET /nodes/private-info, the private info of every node in one request
+    async fn fetch_private_info_list(&self) -> Result<Vec<NodePrivateInfo>> {
+        let url = format!("{}/nodes/private-info", self.base_url);
+        let validators = self
+            .private_info_list_cache
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|cached| cached.validators.clone());
+
+        match self
+            .conditional_get::<Vec<NodePrivateInfo>>(&url, validators.as_ref())
+            .await?
+        {
+            Conditional::Modified(list, validators) => {
+                debug!("Fetched private info list from backend");
+                *self.private_info_list_cache.lock().unwrap() = Some(Cached {
+                    value: list.clone(),
+                    validators,
+                });
+                Ok(list)
+            }
+            Conditional::NotModified => {
+                debug!("Private info list not modified, using cached copy");
+                self.private_info_list_cache
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .map(|cached| cached.value.clone())
+                    .context("Backend answered 304 for a private info list that is not cached")
+            }
+        }
+    }
+
+    /// GET /nodes/{node_id}/private-info, revalidating the cached copy
+    ///
+    /// The cached copy is kept when the request fails in transit, so a flaky backend does not
+    /// make nodes disappear; it is dropped when the backend refuses the node.
+    async fn fetch_private_info(&self, node_id: i32) -> Option<NodePrivateInfo> {
+        let url = format!("{}/nodes/{}/private-info", self.base_url, node_id);
+        let cached = self
+            .private_info_cache
+            .lock()
+            .unwrap()
+            .get(&node_id)
+            .cloned();
+        debug!("Fetching private info for node {}: {}", node_id, url);
+
+        match self
+            .conditional_get::<NodePrivateInfo>(&url, cached.as_ref().map(|c| &c.validators))
+            .await
+        {
+            Ok(Conditional::Modified(info, validators)) => {
+                self.private_info_cache.lock().unwrap().insert(
+                    node_id,
+                    Cached {
+                        value: info.clone(),
+                        validators,
+                    },
+                );
+                Some(info)
+            }
+            Ok(Conditional::NotModified) => cached.map(|c| c.value),
+            Err(e) if HttpStatusError::status_of(&e).is_some() => {
+                warn!("Failed to fetch private info for node {}: {:#}", node_id, e);
+                self.private_info_cache.lock().unwrap().remove(&node_id);
+                None
+            }
+            Err(e) => {
+                warn!("Failed to fetch private info for node {}: {:#}", node_id, e);
+                cached.map(|c| c.value)
+            }
+        }
     }
 
     /// Report node status to backend via heartbeat endpoint
@@ -280,38 +1042,73 @@ impl BackendClient {
         latency_ms: i32,
         peer: i32,
     ) -> Result<()> {
+        self.send_heartbeat(&HeartbeatItem {
+            node_id,
+            status: status.to_string(),
+            peer,
+            latency_ms,
+            checked_at: None,
+            state: None,
+            quality: None,
+            error_kind: None,
+            listeners: Vec::new(),
+            load: None,
+            certificates: Vec::new(),
+            version: None,
+            version_outdated: false,
+            features: Vec::new(),
+        })
+        .await
+    }
+
+    async fn send_heartbeat_outcome(&self, item: &HeartbeatItem) -> HeartbeatOutcome {
+        match self.send_heartbeat(item).await {
+            Ok(()) => HeartbeatOutcome {
+                node_id: item.node_id,
+                error: None,
+                rejected: false,
+            },
+            Err(e) => HeartbeatOutcome {
+                node_id: item.node_id,
+                rejected: HttpStatusError::status_of(&e).is_some_and(is_permanent_rejection),
+                error: Some(format!("{:#}", e)),
+            },
+        }
+    }
+
+    /// POST a single heartbeat to /nodes/:node_id/heartbeat
+    async fn send_heartbeat(&self, item: &HeartbeatItem) -> Result<()> {
+        let node_id = item.node_id;
         let url = format!("{}/nodes/{}/heartbeat", self.base_url, node_id);
 
         debug!("Reporting heartbeat to backend: {} for node id={}", url, node_id);
 
         let request_body = HeartbeatRequest {
-            status: status.to_string(),
-            peer,
-            latency_ms,
+            status: item.status.clone(),
+            peer: item.peer,
+            latency_ms: item.latency_ms,
+            checked_at: item.checked_at,
+            state: item.state.clone(),
+            quality: item.quality.clone(),
+            error_kind: item.error_kind,
+            listeners: item.listeners.clone(),
+            load: item.load.clone(),
+            certificates: item.certificates.clone(),
+            version: item.version.clone(),
+            version_outdated: item.version_outdated,
+            features: item.features.clone(),
         };
 
-        let mut request = self.client.post(&url).json(&request_body);
-        
-        request = request.header("user-agent", "easytier-uptime");
-
-        // Add API key authentication using Bearer token
-        if let Some(api_key) = &self.api_key {
-            request = request.header("authorization", format!("Bearer {}", api_key));
-        }
-
-        let response = request
-            .send()
+        let response = self
+            .send_signed(|| self.post_json(&url, &request_body))
             .await
             .context("Failed to send heartbeat to backend")?;
 
-        let status_code = response.status();
-        if !status_code.is_success() {
+        let status = response.status();
+        if !status.is_success() {
             let error_text = response.text().await.unwrap_or_default();
-            anyhow::bail!(
-                "Failed to report heartbeat to backend: status={}, error={}",
-                status_code,
-                error_text
-            );
+            return Err(HttpStatusError { status, error_text })
+                .context("Failed to report heartbeat to backend");
         }
 
         let heartbeat_response: HeartbeatResponse = response
@@ -327,6 +1124,124 @@ impl BackendClient {
         Ok(())
     }
 
+    /// Report many peers at once via POST /nodes/heartbeats.
+    ///
+    /// Returns one outcome per item, in item order, so a single rejected peer does not
+    /// fail the whole cycle. Falls back to concurrent per-node heartbeats when the backend does not
+    /// provide the batch endpoint (404). Transport or authentication failures of the
+    /// batch request itself are returned as an error.
+    pub async fn report_heartbeats(&self, items: &[HeartbeatItem]) -> Result<Vec<HeartbeatOutcome>> {
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if self.batch_heartbeat_supported.is_supported() {
+            let url = format!("{}/nodes/heartbeats", self.base_url);
+            debug!("Reporting {} heartbeats to backend: {}", items.len(), url);
+
+            let response = self
+                .send_signed(|| self.post_json(&url, &BatchHeartbeatRequest { heartbeats: items }))
+                .await
+                .context("Failed to send heartbeat batch to backend")?;
+
+            let status_code = response.status();
+            if status_code == reqwest::StatusCode::NOT_FOUND {
+                warn!("Backend does not support batch heartbeats, falling back to per-node reporting");
+                self.batch_heartbeat_supported.mark_unsupported();
+            } else if !status_code.is_success() {
+                let error_text = response.text().await.unwrap_or_default();
+                anyhow::bail!(
+                    "Failed to report heartbeat batch to backend: status={}, error={}",
+                    status_code,
+                    error_text
+                );
+            } else {
+                self.batch_heartbeat_supported.mark_supported();
+                let batch_response: BatchHeartbeatResponse = response
+                    .json()
+                    .await
+                    .context("Failed to parse heartbeat batch response")?;
+
+                return Ok(merge_batch_results(items, batch_response));
+            }
+        }
+
+        let requests = items
+            .iter()
+            .map(|item| self.send_heartbeat_outcome(item))
+            .collect::<Vec<_>>();
+        let outcomes = stream::iter(requests)
+            .buffered(HEARTBEAT_FALLBACK_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+
+        Ok(outcomes)
+    }
+
+    /// Report per-node SLA via POST /nodes/sla, `node_id` being the backend peer ID
+    ///
+    /// Skipped for an hour after the backend answers 404, then tried again.
+    pub async fn report_sla(&self, items: &[NodeSla]) -> Result<()> {
+        if items.is_empty() || !self.sla_report_supported.is_supported() {
+            return Ok(());
+        }
+
+        let url = format!("{}/nodes/sla", self.base_url);
+        debug!("Reporting SLA of {} nodes to backend: {}", items.len(), url);
+        let response = self
+            .send_signed(|| self.post_json(&url, &SlaReportRequest { nodes: items }))
+            .await
+            .context("Failed to send SLA report to backend")?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            info!("Backend does not accept SLA reports, pausing them");
+            self.sla_report_supported.mark_unsupported();
+            return Ok(());
+        }
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(HttpStatusError { status, error_text })
+                .context("Failed to report SLA to backend");
+        }
+        self.sla_report_supported.mark_supported();
+        Ok(())
+    }
+
+    /// Tell the backend via POST /probes/offline that this probe is going away, so its
+    /// nodes can be taken over without waiting for it to go stale
+    ///
+    /// Does nothing when the probe is not registered; a 404 is ignored.
+    pub async fn report_offline(&self, reason: &str) -> Result<()> {
+        let Some(probe_id) = self.probe_id() else {
+            return Ok(());
+        };
+
+        let url = format!("{}/probes/offline", self.base_url);
+        debug!("Reporting probe {} offline to backend: {}", probe_id, url);
+        let body = ProbeOfflineRequest {
+            probe_id,
+            reason: reason.to_string(),
+        };
+        let response = self
+            .post_json(&url, &body)?
+            .send()
+            .await
+            .context("Failed to send probe offline report to backend")?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            debug!("Backend does not accept probe offline reports");
+            return Ok(());
+        }
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(HttpStatusError { status, error_text })
+                .context("Failed to report probe offline to backend");
+        }
+        Ok(())
+    }
+
     /// Test backend connection
     pub async fn test_connection(&self) -> Result<()> {
         let url = format!("{}/node-status", self.base_url);
@@ -349,8 +1264,140 @@ impl BackendClient {
     }
 }
 
+#[async_trait]
+impl ProbeBackend for BackendClient {
+    async fn register_probe(&self) -> Result<()> {
+        self.register().await
+    }
+
+    async fn test_connection(&self) -> Result<()> {
+        BackendClient::test_connection(self).await
+    }
+
+    async fn fetch_peers(&self, region: Option<&str>) -> Result<Vec<BackendPeer>> {
+        BackendClient::fetch_peers(self, region).await
+    }
+
+    async fn report_heartbeats(&self, items: &[HeartbeatItem]) -> Result<Vec<HeartbeatOutcome>> {
+        BackendClient::report_heartbeats(self, items).await
+    }
+
+    fn probe_id(&self) -> Option<String> {
+        BackendClient::probe_id(self)
+    }
+
+    async fn fetch_probes(&self, region: Option<&str>) -> Result<Option<Vec<ProbeInfo>>> {
+        BackendClient::fetch_probes(self, region).await
+    }
+
+    async fn report_sla(&self, items: &[NodeSla]) -> Result<()> {
+        BackendClient::report_sla(self, items).await
+    }
+
+    async fn report_offline(&self, reason: &str) -> Result<()> {
+        BackendClient::report_offline(self, reason).await
+    }
+}
+
+/// Non-success HTTP status returned by the backend
+#[derive(Debug)]
+struct HttpStatusError {
+    status: StatusCode,
+    error_text: String,
+}
+
+impl std::fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "status={}, error={}", self.status, self.error_text)
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+impl HttpStatusError {
+    fn status_of(err: &anyhow::Error) -> Option<StatusCode> {
+        err.downcast_ref::<Self>().map(|e| e.status)
+    }
+}
+
+/// Random secret a new probe signs its reports with
+fn generate_probe_secret() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// Report signature: HMAC-SHA256(secret, "{timestamp}.{nonce}.{body}")
+///
+/// The backend rejects stale timestamps and nonces it has already seen, so a captured
+/// report cannot be replayed.
+pub fn sign_report(secret: &str, timestamp: i64, nonce: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(nonce.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Match per-item batch results back to the submitted heartbeats.
+///
+/// A bare `success` without per-item results applies to every item. Otherwise items the
+/// backend did not mention are treated as failed so they are not silently lost.
+fn merge_batch_results(
+    items: &[HeartbeatItem],
+    response: BatchHeartbeatResponse,
+) -> Vec<HeartbeatOutcome> {
+    let batch_success = response.success;
+    let has_item_results = !response.results.is_empty();
+    // The same peer may appear several times when replaying delayed heartbeats,
+    // results for one peer are consumed in submission order.
+    let mut results: HashMap<i32, VecDeque<BatchHeartbeatItemResult>> = HashMap::new();
+    for r in response.results {
+        results.entry(r.node_id).or_default().push_back(r);
+    }
+
+    items
+        .iter()
+        .map(|item| {
+            let result = results.get_mut(&item.node_id).and_then(|q| q.pop_front());
+            // An explicit per-item failure is the backend refusing that heartbeat
+            let rejected = result.as_ref().is_some_and(|r| !r.success);
+            let error = match result {
+                Some(r) if r.success => None,
+                Some(r) => Some(r.error.unwrap_or_else(|| "rejected by backend".to_string())),
+                None if !has_item_results && batch_success => None,
+                None if !has_item_results => Some("backend returned success=false for heartbeat batch".to_string()),
+                None => Some("missing from batch heartbeat response".to_string()),
+            };
+            HeartbeatOutcome {
+                node_id: item.node_id,
+                error,
+                rejected,
+            }
+        })
+        .collect()
+}
+
+/// Client errors other than authentication, timeouts and rate limiting mean the backend
+/// will refuse the same heartbeat again
+fn is_permanent_rejection(status: StatusCode) -> bool {
+    status.is_client_error()
+        && !matches!(
+            status,
+            StatusCode::UNAUTHORIZED
+                | StatusCode::FORBIDDEN
+                | StatusCode::REQUEST_TIMEOUT
+                | StatusCode::TOO_MANY_REQUESTS
+        )
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
     #[test]
@@ -432,7 +1479,9 @@ mod tests {
             "updated_at": "2025-11-17T13:05:31.321Z",
             "public_ip": "string",
             "network_name": "string",
-            "network_secret": "string"
+            "network_secret": "string",
+            "check_interval": 30,
+            "listeners": ["tcp://example.com:11010", "wss://example.com:11012"]
         }"#;
         
         let private_info: Result<NodePrivateInfo, _> = serde_json::from_str(json);
@@ -441,6 +1490,11 @@ mod tests {
         assert_eq!(private_info.id, 0);
         assert_eq!(private_info.name, Some("string".to_string()));
         assert_eq!(private_info.network_secret, Some("string".to_string()));
+        assert_eq!(private_info.check_interval, Some(30));
+        assert_eq!(
+            private_info.listeners,
+            vec!["tcp://example.com:11010", "wss://example.com:11012"]
+        );
     }
 
     #[test]
@@ -459,6 +1513,8 @@ mod tests {
         let private_info = private_info.unwrap();
         assert_eq!(private_info.id, 2);
         assert_eq!(private_info.name, None);
+        assert_eq!(private_info.check_interval, None);
+        assert!(private_info.listeners.is_empty());
         assert_eq!(private_info.protocol, Some("tcp".to_string()));
         assert_eq!(private_info.public_ip, Some("bj.et-hub.top".to_string()));
         assert_eq!(private_info.network_name, Some("et-hub".to_string()));
@@ -533,4 +1589,354 @@ mod tests {
         assert_eq!(response.node_status.peer, 0);
         assert_eq!(response.node_status.last_heartbeat, None);
     }
+
+    pub(crate) fn heartbeat_item(node_id: i32) -> HeartbeatItem {
+        HeartbeatItem {
+            node_id,
+            status: "online".to_string(),
+            peer: 0,
+            latency_ms: 10,
+            checked_at: None,
+            state: None,
+            quality: None,
+            error_kind: None,
+            listeners: Vec::new(),
+            load: None,
+            certificates: Vec::new(),
+            version: None,
+            version_outdated: false,
+            features: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_batch_heartbeat_response_per_item_results() {
+        let json = r#"{
+            "success": true,
+            "results": [
+                { "node_id": 1, "success": true },
+                { "node_id": 2, "success": false, "error": "node not found" }
+            ]
+        }"#;
+
+        let response: BatchHeartbeatResponse = serde_json::from_str(json).unwrap();
+        let items = vec![heartbeat_item(1), heartbeat_item(2), heartbeat_item(3)];
+        let outcomes = merge_batch_results(&items, response);
+
+        assert_eq!(outcomes.len(), 3);
+        assert!(outcomes[0].is_success());
+        assert_eq!(outcomes[1].error, Some("node not found".to_string()));
+        assert!(outcomes[1].is_rejected());
+        assert_eq!(
+            outcomes[2].error,
+            Some("missing from batch heartbeat response".to_string())
+        );
+        assert!(!outcomes[2].is_rejected());
+    }
+
+    #[test]
+    fn test_batch_heartbeat_response_without_results() {
+        let items = vec![heartbeat_item(1), heartbeat_item(2)];
+
+        let response: BatchHeartbeatResponse =
+            serde_json::from_str(r#"{ "success": true }"#).unwrap();
+        assert!(merge_batch_results(&items, response)
+            .iter()
+            .all(|o| o.is_success()));
+
+        let response: BatchHeartbeatResponse =
+            serde_json::from_str(r#"{ "success": false }"#).unwrap();
+        assert!(merge_batch_results(&items, response)
+            .iter()
+            .all(|o| !o.is_success()));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_peers_revalidates_private_info() {
+        use axum::{extract::Path, response::IntoResponse, routing::get, Json, Router};
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        // Only the per-node endpoint exists; node 1 sends an ETag and answers 304,
+        // node 2 has no validators
+        let full_responses = Arc::new(AtomicUsize::new(0));
+        let counter = full_responses.clone();
+        let private_info = move |Path(id): Path<i32>, headers: HeaderMap| {
+            let counter = counter.clone();
+            async move {
+                let etag = format!("\"node-{}\"", id);
+                let if_none_match = headers.get(header::IF_NONE_MATCH);
+                if id == 1 && if_none_match.is_some_and(|v| v == &etag) {
+                    return StatusCode::NOT_MODIFIED.into_response();
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                let body = Json(serde_json::json!({
+                    "id": id,
+                    "public_ip": format!("node{}.example.com:11010", id),
+                }));
+                match id {
+                    1 => ([(header::ETAG, etag)], body).into_response(),
+                    _ => body.into_response(),
+                }
+            }
+        };
+        let app = Router::new()
+            .route(
+                "/node-status",
+                get(|| async {
+                    Json(serde_json::json!([
+                        { "node_id": 1, "status": "online" },
+                        { "node_id": 2, "status": "offline" }
+                    ]))
+                }),
+            )
+            .route(
+                "/nodes/private-info",
+                get(|| async { StatusCode::NOT_FOUND }),
+            )
+            .route("/nodes/:id/private-info", get(private_info));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = BackendClient::new(base_url, Some("key".to_string())).unwrap();
+        for _ in 0..2 {
+            let mut peers = client.fetch_peers(None).await.unwrap();
+            peers.sort_by_key(|p| p.id);
+            assert_eq!(peers.len(), 2);
+            for (peer, status) in peers.iter().zip(["online", "offline"]) {
+                assert_eq!(peer.status, status);
+                let public_ip = format!("node{}.example.com:11010", peer.id);
+                assert_eq!(peer.public_ip, Some(public_ip));
+            }
+        }
+
+        // The list endpoint is not requested again after its 404; in the second round node 1
+        // comes from the cache and node 2 is fetched again
+        assert!(!client.private_info_list_supported.is_supported());
+        assert_eq!(full_responses.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_unsupported_endpoint_is_retried() {
+        let support = EndpointSupport::new(Duration::from_millis(50));
+        assert!(support.is_supported());
+
+        support.mark_unsupported();
+        assert!(!support.is_supported());
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(support.is_supported());
+
+        support.mark_unsupported();
+        support.mark_supported();
+        assert!(support.is_supported());
+    }
+
+    /// Whether the request carries a valid signature made with `secret`
+    fn signed_by(headers: &HeaderMap, body: &[u8], secret: &str) -> bool {
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+        let (Some(timestamp), Some(nonce), Some(signature)) = (
+            header(TIMESTAMP_HEADER).and_then(|t| t.parse().ok()),
+            header(NONCE_HEADER),
+            header(SIGNATURE_HEADER),
+        ) else {
+            return false;
+        };
+        signature == sign_report(secret, timestamp, nonce, body)
+    }
+
+    #[tokio::test]
+    async fn test_register_and_sign_reports() {
+        use axum::{extract::State, routing::post, Json, Router};
+        use std::sync::Arc;
+
+        #[derive(Default)]
+        struct Backend {
+            registrations: Vec<ProbeRegisterRequest>,
+            // (probe ID, signature valid) of every heartbeat batch
+            reports: Vec<(String, bool)>,
+            offline: Vec<ProbeOfflineRequest>,
+        }
+        type Shared = Arc<Mutex<Backend>>;
+
+        async fn register(
+            State(state): State<Shared>,
+            headers: HeaderMap,
+            body: axum::body::Bytes,
+        ) -> Result<Json<serde_json::Value>, StatusCode> {
+            let request: ProbeRegisterRequest = serde_json::from_slice(&body).unwrap();
+            let mut state = state.lock().unwrap();
+            let probe_id = match &request.probe_id {
+                // Re-registration must be signed with the current secret of the probe
+                Some(probe_id) => {
+                    let current = state
+                        .registrations
+                        .iter()
+                        .rev()
+                        .find(|r| r.probe_id.is_none() || r.probe_id.as_ref() == Some(probe_id))
+                        .unwrap();
+                    if !signed_by(&headers, &body, &current.secret) {
+                        return Err(StatusCode::UNAUTHORIZED);
+                    }
+                    probe_id.clone()
+                }
+                None => format!("probe-{}", state.registrations.len() + 1),
+            };
+            state.registrations.push(request);
+            Ok(Json(serde_json::json!({ "probe_id": probe_id })))
+        }
+
+        async fn heartbeats(
+            State(state): State<Shared>,
+            headers: HeaderMap,
+            body: axum::body::Bytes,
+        ) -> Json<serde_json::Value> {
+            let mut state = state.lock().unwrap();
+            let secret = state.registrations.last().unwrap().secret.clone();
+            let valid = signed_by(&headers, &body, &secret);
+            let probe_id = headers.get(PROBE_ID_HEADER).unwrap().to_str().unwrap();
+            state.reports.push((probe_id.to_string(), valid));
+            Json(serde_json::json!({ "success": true }))
+        }
+
+        async fn offline(
+            State(state): State<Shared>,
+            headers: HeaderMap,
+            Json(request): Json<ProbeOfflineRequest>,
+        ) -> StatusCode {
+            assert_eq!(headers.get(PROBE_ID_HEADER).unwrap(), &request.probe_id);
+            state.lock().unwrap().offline.push(request);
+            StatusCode::NO_CONTENT
+        }
+
+        let state = Shared::default();
+        let app = Router::new()
+            .route("/probes/register", post(register))
+            .route("/probes/offline", post(offline))
+            .route("/nodes/heartbeats", post(heartbeats))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let db = Db::memory_db().await;
+        let registration = ProbeRegistration {
+            name: None,
+            region: Some("cn-hz".to_string()),
+        };
+        let item = heartbeat_item(1);
+
+        // After a restart the identity and secret stored in the database are reused
+        for _ in 0..2 {
+            let client = BackendClient::new(base_url.clone(), Some("key".to_string()))
+                .unwrap()
+                .with_registration(db.clone(), registration.clone());
+            client.register().await.unwrap();
+            assert_eq!(client.probe_id().as_deref(), Some("probe-1"));
+            let outcomes = client
+                .report_heartbeats(std::slice::from_ref(&item))
+                .await
+                .unwrap();
+            assert!(outcomes.iter().all(|o| o.is_success()));
+            client.report_offline("shutdown").await.unwrap();
+        }
+
+        let state = state.lock().unwrap();
+        let (first, second) = (&state.registrations[0], &state.registrations[1]);
+        assert_eq!(first.probe_id, None);
+        assert_eq!(second.probe_id.as_deref(), Some("probe-1"));
+        assert_eq!(first.secret, second.secret);
+        assert_eq!(first.name, second.name);
+        assert_eq!(second.region.as_deref(), Some("cn-hz"));
+        assert_eq!(state.reports, vec![("probe-1".to_string(), true); 2]);
+        assert_eq!(state.offline.len(), 2);
+        assert_eq!(state.offline[0].probe_id, "probe-1");
+        assert_eq!(state.offline[0].reason, "shutdown");
+    }
+
+    #[tokio::test]
+    async fn test_register_again_when_signature_rejected() {
+        use axum::{extract::State, routing::post, Json, Router};
+        use std::sync::Arc;
+
+        // Secret of every probe the backend knows, and the accepted heartbeat batches
+        #[derive(Default)]
+        struct Backend {
+            secrets: HashMap<String, String>,
+            registered: usize,
+            accepted: Vec<String>,
+        }
+        type Shared = Arc<Mutex<Backend>>;
+
+        async fn register(
+            State(state): State<Shared>,
+            Json(request): Json<ProbeRegisterRequest>,
+        ) -> Result<Json<serde_json::Value>, StatusCode> {
+            let mut state = state.lock().unwrap();
+            if request.probe_id.is_some() {
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            state.registered += 1;
+            let probe_id = format!("probe-{}", state.registered);
+            state.secrets.insert(probe_id.clone(), request.secret);
+            Ok(Json(serde_json::json!({ "probe_id": probe_id })))
+        }
+
+        async fn heartbeats(
+            State(state): State<Shared>,
+            headers: HeaderMap,
+            body: axum::body::Bytes,
+        ) -> Result<Json<serde_json::Value>, StatusCode> {
+            let mut state = state.lock().unwrap();
+            let probe_id = headers.get(PROBE_ID_HEADER).unwrap().to_str().unwrap();
+            match state.secrets.get(probe_id) {
+                Some(secret) if signed_by(&headers, &body, secret) => {
+                    state.accepted.push(probe_id.to_string());
+                    Ok(Json(serde_json::json!({ "success": true })))
+                }
+                _ => Err(StatusCode::UNAUTHORIZED),
+            }
+        }
+
+        let state = Shared::default();
+        let app = Router::new()
+            .route("/probes/register", post(register))
+            .route("/nodes/heartbeats", post(heartbeats))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let db = Db::memory_db().await;
+        let client = BackendClient::new(base_url.clone(), Some("key".to_string()))
+            .unwrap()
+            .with_registration(db.clone(), ProbeRegistration::default());
+        client.register().await.unwrap();
+        assert_eq!(client.probe_id().as_deref(), Some("probe-1"));
+
+        // The backend forgets the probe, the next report registers again and is retried
+        state.lock().unwrap().secrets.clear();
+        let item = heartbeat_item(1);
+        let outcomes = client
+            .report_heartbeats(std::slice::from_ref(&item))
+            .await
+            .unwrap();
+        assert!(outcomes.iter().all(|o| o.is_success()));
+        assert_eq!(client.probe_id().as_deref(), Some("probe-2"));
+        assert_eq!(state.lock().unwrap().accepted, vec!["probe-2".to_string()]);
+
+        // After a restart the stored identity is rejected, so the probe registers as new
+        let client = BackendClient::new(base_url, Some("key".to_string()))
+            .unwrap()
+            .with_registration(db.clone(), ProbeRegistration::default());
+        client.register().await.unwrap();
+        assert_eq!(client.probe_id().as_deref(), Some("probe-3"));
+        let stored = ProbeIdentityOperations::get_by_backend(&db, &client.base_url)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.probe_id, "probe-3");
+    }
 }
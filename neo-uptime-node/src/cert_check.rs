@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::db::CertStatus;
+
+/// 证书检查配置
+#[derive(Debug, Clone)]
+pub struct CertCheckConfig {
+    /// 检查间隔，为 0 时不检查证书
+    pub interval: Duration,
+    /// 证书在该时长内过期时告警
+    pub expiry_warning: Duration,
+}
+
+impl Default for CertCheckConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(6 * 3600),
+            expiry_warning: Duration::from_secs(14 * 86400),
+        }
+    }
+}
+
+/// 节点出示的证书链中终端证书的信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    /// 签发者与主体相同
+    pub self_signed: bool,
+    /// 证书对节点的域名或 IP 有效
+    pub hostname_match: bool,
+    /// 证书链可以校验到内置的受信任根证书，不考虑有效期
+    pub trusted: bool,
+}
+
+impl CertificateInfo {
+    /// 判定证书状态：先看有效期，再看是否自签名、域名是否匹配和证书链是否受信任
+    pub fn status(&self, now: DateTime<Utc>, expiry_warning: Duration) -> CertStatus {
+        if now < self.not_before || now >= self.not_after {
+            return CertStatus::Expired;
+        }
+        let warning = chrono::Duration::from_std(expiry_warning).unwrap_or(chrono::Duration::MAX);
+        if self.not_after - now <= warning {
+            return CertStatus::Expiring;
+        }
+        if self.self_signed && !self.trusted {
+            return CertStatus::SelfSigned;
+        }
+        if !self.hostname_match {
+            return CertStatus::HostnameMismatch;
+        }
+        if !self.trusted {
+            return CertStatus::Untrusted;
+        }
+        CertStatus::Valid
+    }
+}
+
+/// 从节点的地址中挑出需要检查证书的 wss / quic 地址，规范化后去重
+pub fn tls_urls(urls: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut urls = urls
+        .into_iter()
+        .filter_map(|url| url::Url::parse(url.trim()).ok())
+        .filter(|url| matches!(url.scheme(), "wss" | "quic") && url.host().is_some())
+        .map(|url| url.to_string())
+        .collect::<Vec<_>>();
+    urls.sort();
+    urls.dedup();
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cert(days_left: i64) -> CertificateInfo {
+        let now = Utc::now();
+        CertificateInfo {
+            subject: "CN=node.example.com".to_string(),
+            issuer: "CN=Example CA".to_string(),
+            not_before: now - chrono::Duration::days(30),
+            not_after: now + chrono::Duration::days(days_left),
+            self_signed: false,
+            hostname_match: true,
+            trusted: true,
+        }
+    }
+
+    #[test]
+    fn test_certificate_status() {
+        let now = Utc::now();
+        let warning = Duration::from_secs(14 * 86400);
+
+        assert_eq!(cert(60).status(now, warning), CertStatus::Valid);
+        assert_eq!(cert(7).status(now, warning), CertStatus::Expiring);
+        assert_eq!(cert(-1).status(now, warning), CertStatus::Expired);
+
+        let mismatch = CertificateInfo {
+            hostname_match: false,
+            ..cert(60)
+        };
+        assert_eq!(mismatch.status(now, warning), CertStatus::HostnameMismatch);
+
+        let untrusted = CertificateInfo {
+            trusted: false,
+            ..cert(60)
+        };
+        assert_eq!(untrusted.status(now, warning), CertStatus::Untrusted);
+
+        // EasyTier 自动生成的自签名证书不告警，但过期仍然告警
+        let self_signed = CertificateInfo {
+            issuer: "CN=localhost".to_string(),
+            self_signed: true,
+            hostname_match: false,
+            trusted: false,
+            ..cert(60)
+        };
+        assert_eq!(self_signed.status(now, warning), CertStatus::SelfSigned);
+        assert!(!CertStatus::SelfSigned.needs_attention());
+        let expired = CertificateInfo {
+            not_after: now - chrono::Duration::days(1),
+            ..self_signed
+        };
+        assert_eq!(expired.status(now, warning), CertStatus::Expired);
+    }
+
+    #[test]
+    fn test_tls_urls() {
+        let urls = tls_urls([
+            "wss://node.example.com:11012".to_string(),
+            "wss://node.example.com:11012/".to_string(),
+            "quic://1.2.3.4:11012".to_string(),
+            "tcp://node.example.com:11010".to_string(),
+            "not a url".to_string(),
+        ]);
+        assert_eq!(
+            urls,
+            vec![
+                "quic://1.2.3.4:11012".to_string(),
+                "wss://node.example.com:11012/".to_string(),
+            ]
+        );
+    }
+}
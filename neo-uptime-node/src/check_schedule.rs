@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::db::NodeState;
+
+// Upper bound on interval doublings for long-down nodes
+const MAX_BACKOFF_DOUBLINGS: u32 = 16;
+
+/// 单节点检查间隔配置
+#[derive(Debug, Clone)]
+pub struct CheckIntervalConfig {
+    /// 默认检查间隔，可被后端下发的单节点间隔覆盖
+    pub base: Duration,
+    /// 最大检查间隔
+    pub max: Duration,
+    /// 检查结果与当前状态不一致时用于快速确认的检查间隔
+    pub confirm: Duration,
+    /// 一次待确认的状态转换最多使用快速确认间隔的检查次数，避免结果反复变化的节点一直被加密检查
+    pub confirm_checks: u32,
+    /// 离线超过该时长后开始退避，之后离线时长每翻倍一次，间隔翻倍一次
+    pub backoff_after: chrono::Duration,
+}
+
+impl Default for CheckIntervalConfig {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_secs(5),
+            max: Duration::from_secs(300),
+            confirm: Duration::from_secs(1),
+            confirm_checks: 3,
+            backoff_after: chrono::Duration::minutes(10),
+        }
+    }
+}
+
+/// 检查结果与当前状态不一致、尚未发生的状态转换
+#[derive(Debug, Clone, Copy)]
+pub struct PendingTransition {
+    /// 当前状态的最短停留时间结束的时刻，此前状态不会变化
+    pub dwell_until: DateTime<Utc>,
+    /// 停留时间结束后已进行的快速确认检查次数
+    pub confirm_checks: u32,
+}
+
+impl CheckIntervalConfig {
+    /// 计算下一次检查前的等待时间
+    ///
+    /// `base` 为该节点的基础间隔，`pending` 为等待确认的状态转换。
+    pub fn next_interval(
+        &self,
+        base: Duration,
+        state: NodeState,
+        state_since: DateTime<Utc>,
+        pending: Option<PendingTransition>,
+        now: DateTime<Utc>,
+    ) -> Duration {
+        if let Some(pending) = pending {
+            // 停留时间结束前状态不会变化，不必加密检查，但在结束时立即再检查一次
+            if now < pending.dwell_until {
+                let dwell_left = (pending.dwell_until - now).to_std().unwrap_or_default();
+                return dwell_left.max(self.confirm).min(base);
+            }
+            // 之后加密检查，尽快确认
+            if pending.confirm_checks < self.confirm_checks {
+                return self.confirm.min(base);
+            }
+        }
+
+        if state != NodeState::Down || self.backoff_after <= chrono::Duration::zero() {
+            return base;
+        }
+
+        // 长时间离线的节点按离线时长指数退避
+        let down_secs = (now - state_since).num_seconds();
+        let periods = down_secs / self.backoff_after.num_seconds();
+        if periods < 1 {
+            return base;
+        }
+        let doublings = (u64::BITS - (periods as u64).leading_zeros()).min(MAX_BACKOFF_DOUBLINGS);
+        base.saturating_mul(1 << doublings).min(self.max.max(base))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node_state::{CheckOutcome, NodeStateMachine, StateMachineConfig};
+
+    /// 按检查循环的方式从 `start` 起连续检查，返回进入 `target` 状态距开始的秒数
+    fn reach_state_after(
+        mut sm: NodeStateMachine,
+        target: NodeState,
+        healthy: bool,
+        start: DateTime<Utc>,
+        confirm: bool,
+    ) -> i64 {
+        let outcome = if healthy {
+            CheckOutcome::Healthy
+        } else {
+            CheckOutcome::Unhealthy
+        };
+        let cfg = CheckIntervalConfig::default();
+        let sm_config = StateMachineConfig::default();
+        let base = Duration::from_secs(7);
+        let mut now = start;
+        let mut confirm_checks = 0;
+        loop {
+            if let Some(transition) = sm.observe(outcome, now, &sm_config) {
+                if transition.to == target {
+                    return (transition.at - start).num_seconds();
+                }
+            }
+            let dwell_until = sm.since() + sm_config.min_dwell;
+            if !sm.is_pending() || now < dwell_until {
+                confirm_checks = 0;
+            }
+            let pending = (confirm && sm.is_pending()).then_some(PendingTransition {
+                dwell_until,
+                confirm_checks,
+            });
+            if pending.is_some() && now >= dwell_until {
+                confirm_checks += 1;
+            }
+            let next = cfg.next_interval(base, sm.state(), sm.since(), pending, now);
+            now += chrono::Duration::from_std(next).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_confirmation_speeds_up_transitions() {
+        let start = Utc::now();
+        let long_ago = start - chrono::Duration::minutes(5);
+
+        // 离线已久的节点恢复：第一次成功后 1 秒即确认，而不是等一个基础间隔
+        let down = || NodeStateMachine::restore(NodeState::Down, long_ago);
+        assert_eq!(
+            reach_state_after(down(), NodeState::Up, true, start, true),
+            1
+        );
+        assert_eq!(
+            reach_state_after(down(), NodeState::Up, true, start, false),
+            7
+        );
+
+        // 在线节点开始持续失败：先降级，在最短停留时间（30 秒）结束时立即检查并转为离线
+        let up = || NodeStateMachine::restore(NodeState::Up, long_ago);
+        assert_eq!(
+            reach_state_after(up(), NodeState::Down, false, start, true),
+            30
+        );
+        assert_eq!(
+            reach_state_after(up(), NodeState::Down, false, start, false),
+            35
+        );
+    }
+
+    #[test]
+    fn test_next_interval() {
+        let cfg = CheckIntervalConfig::default();
+        let base = Duration::from_secs(5);
+        let now = Utc::now();
+        let minutes_ago = |m: i64| now - chrono::Duration::minutes(m);
+
+        let pending = |dwell_until, confirm_checks| {
+            Some(PendingTransition {
+                dwell_until,
+                confirm_checks,
+            })
+        };
+
+        // 停留时间结束后，待确认的状态转换使用快速确认间隔
+        assert_eq!(
+            cfg.next_interval(base, NodeState::Down, minutes_ago(1), pending(now, 0), now),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            cfg.next_interval(base, NodeState::Up, minutes_ago(1), pending(now, 3), now),
+            base
+        );
+        assert_eq!(
+            cfg.next_interval(base, NodeState::Up, minutes_ago(1), None, now),
+            base
+        );
+
+        // 停留时间结束前按基础间隔检查，并在结束时立即检查
+        let secs_later = |s: i64| now + chrono::Duration::seconds(s);
+        assert_eq!(
+            cfg.next_interval(base, NodeState::Up, now, pending(secs_later(30), 0), now),
+            base
+        );
+        assert_eq!(
+            cfg.next_interval(base, NodeState::Up, now, pending(secs_later(3), 0), now),
+            Duration::from_secs(3)
+        );
+
+        // 离线不久不退避
+        assert_eq!(
+            cfg.next_interval(base, NodeState::Down, minutes_ago(5), None, now),
+            base
+        );
+
+        // 离线时长每翻倍一次，间隔翻倍一次
+        assert_eq!(
+            cfg.next_interval(base, NodeState::Down, minutes_ago(10), None, now),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            cfg.next_interval(base, NodeState::Down, minutes_ago(25), None, now),
+            Duration::from_secs(20)
+        );
+        assert_eq!(
+            cfg.next_interval(base, NodeState::Down, minutes_ago(45), None, now),
+            Duration::from_secs(40)
+        );
+
+        // 不超过最大间隔
+        assert_eq!(
+            cfg.next_interval(base, NodeState::Down, minutes_ago(60 * 24 * 30), None, now),
+            Duration::from_secs(300)
+        );
+
+        // 在线节点不退避
+        assert_eq!(
+            cfg.next_interval(base, NodeState::Up, minutes_ago(60 * 24), None, now),
+            base
+        );
+    }
+}
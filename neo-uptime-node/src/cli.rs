@@ -0,0 +1,554 @@
+//! 一次性子命令：检查单个节点、导出健康记录、查看和维护本地数据库
+//!
+//! 子命令不启动后台任务，也不连接后端，执行完毕即退出。
+
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::Context as _;
+use chrono::{DateTime, Utc};
+use clap::{Subcommand, ValueEnum};
+use serde::Serialize;
+
+use crate::{
+    db::{
+        cleanup::CleanupManager,
+        entity::health_records,
+        operations::{HealthOperations, NodeOperations},
+        Db, ProbeErrorKind,
+    },
+    health_checker::{HealthChecker, ProbeMode},
+    models::CreateNodeRequest,
+    node_load::NodeLoad,
+    probe_error,
+};
+
+// Port assumed when the node URI has none, same as for backend peers
+const DEFAULT_NODE_PORT: i32 = 11010;
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Probe a single node once and print the result, exits with 1 when it is offline
+    Check(CheckArgs),
+    /// Dump the health history of a node from the local database
+    Export(ExportArgs),
+    /// Inspect or maintain the local database
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CheckArgs {
+    /// Node address, e.g. tcp://public.example.com:11010
+    uri: String,
+
+    /// Network name used to connect to the node
+    #[arg(long, default_value = "default")]
+    network_name: String,
+
+    /// Network secret used to connect to the node
+    #[arg(long, default_value = "")]
+    network_secret: String,
+
+    /// How the node is probed, only `instance` reports the node version
+    #[arg(long, value_enum, default_value = "instance")]
+    probe_mode: ProbeMode,
+
+    /// Seconds to keep retrying before the node is reported offline
+    #[arg(long, default_value = "10")]
+    timeout: u64,
+
+    #[arg(long, value_enum, default_value = "table")]
+    format: OutputFormat,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ExportArgs {
+    /// Local node ID (see GET /nodes)
+    #[arg(long)]
+    node: i32,
+
+    /// Start of the exported range: an RFC 3339 time, or a duration ago such as 30m, 24h or 7d
+    #[arg(long, default_value = "24h")]
+    since: Since,
+
+    #[arg(long, value_enum, default_value = "csv")]
+    format: ExportFormat,
+
+    /// Write to this file instead of stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DbCommand {
+    /// Print row counts of the local database
+    Stats {
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Run ANALYZE and VACUUM on the local database
+    Vacuum,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+/// 导出的起始时间，解析时相对时长按当前时间换算
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Since(DateTime<Utc>);
+
+impl FromStr for Since {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+            return Ok(Self(time.to_utc()));
+        }
+
+        let invalid = || {
+            format!(
+                "invalid time '{}', expected RFC 3339 or a duration like 30m, 24h, 7d",
+                s
+            )
+        };
+        let unit = s.chars().last().ok_or_else(invalid)?;
+        let value: i64 = s[..s.len() - unit.len_utf8()]
+            .parse()
+            .map_err(|_| invalid())?;
+        let ago = match unit {
+            's' => chrono::Duration::try_seconds(value),
+            'm' => chrono::Duration::try_minutes(value),
+            'h' => chrono::Duration::try_hours(value),
+            'd' => chrono::Duration::try_days(value),
+            _ => None,
+        }
+        .ok_or_else(invalid)?;
+        Ok(Self(Utc::now() - ago))
+    }
+}
+
+/// 执行子命令，返回进程退出码
+pub async fn run(command: Command, database_path: &str) -> anyhow::Result<ExitCode> {
+    match command {
+        Command::Check(args) => check(args).await,
+        Command::Export(args) => {
+            export(&open_db(database_path).await?, args).await?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Db {
+            command: DbCommand::Stats { format },
+        } => {
+            let stats = CleanupManager::get_database_stats(&open_db(database_path).await?).await?;
+            match format {
+                OutputFormat::Table => print_table(&[
+                    ("total_nodes", stats.total_nodes.to_string()),
+                    ("active_nodes", stats.active_nodes.to_string()),
+                    (
+                        "total_health_records",
+                        stats.total_health_records.to_string(),
+                    ),
+                    (
+                        "total_health_rollups",
+                        stats.total_health_rollups.to_string(),
+                    ),
+                ]),
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&stats)?),
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Db {
+            command: DbCommand::Vacuum,
+        } => {
+            let result =
+                CleanupManager::perform_database_maintenance(&open_db(database_path).await?)
+                    .await?;
+            print_table(&[
+                ("analyze", done(result.analyze_performed).to_string()),
+                ("vacuum", done(result.vacuum_performed).to_string()),
+            ]);
+            Ok(if result.vacuum_performed {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            })
+        }
+    }
+}
+
+/// 打开已有的本地数据库，不存在时报错而不是新建一个空库
+async fn open_db(database_path: &str) -> anyhow::Result<Db> {
+    if database_path != ":memory:" && !Path::new(database_path).exists() {
+        anyhow::bail!("Database {} not found", database_path);
+    }
+    Db::new(database_path)
+        .await
+        .with_context(|| format!("Failed to open database {}", database_path))
+}
+
+fn done(performed: bool) -> &'static str {
+    if performed {
+        "done"
+    } else {
+        "failed"
+    }
+}
+
+/// `check` 命令的输出
+#[derive(Debug, Serialize)]
+struct CheckReport {
+    uri: String,
+    status: &'static str,
+    latency_ms: Option<f64>,
+    version: Option<String>,
+    loss_rate: Option<f32>,
+    /// 节点承载的外部网络负载，握手探测无法获取
+    load: Option<NodeLoad>,
+    error_kind: Option<ProbeErrorKind>,
+    error: Option<String>,
+}
+
+/// 将 `protocol://host[:port]` 拆分为协议、主机和端口
+fn parse_node_uri(uri: &str) -> anyhow::Result<(String, String, i32)> {
+    let (protocol, rest) = uri
+        .split_once("://")
+        .filter(|(protocol, rest)| !protocol.is_empty() && !rest.is_empty())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid node URI '{}', expected protocol://host[:port]",
+                uri
+            )
+        })?;
+    let rest = rest.trim_end_matches('/');
+
+    // IPv6 地址的冒号在方括号内，不是端口分隔符
+    let (host, port) = match rest.rsplit_once(':') {
+        Some((host, port)) if !port.contains(']') => {
+            let port: u16 = port
+                .parse()
+                .with_context(|| format!("Invalid port in node URI '{}'", uri))?;
+            (host, port as i32)
+        }
+        _ => (rest, DEFAULT_NODE_PORT),
+    };
+    Ok((protocol.to_lowercase(), host.to_string(), port))
+}
+
+async fn check(args: CheckArgs) -> anyhow::Result<ExitCode> {
+    let (protocol, host, port) = parse_node_uri(&args.uri)?;
+
+    // 节点只写入内存数据库，不影响本地数据库
+    let db = Db::new(":memory:").await?;
+    let node = NodeOperations::create_node(
+        &db,
+        CreateNodeRequest {
+            name: args.uri.clone(),
+            host,
+            port,
+            protocol,
+            description: None,
+            max_connections: 100,
+            allow_relay: true,
+            network_name: args.network_name,
+            network_secret: Some(args.network_secret),
+            qq_number: None,
+            wechat: None,
+            mail: None,
+        },
+    )
+    .await?;
+
+    let health_checker = HealthChecker::new(db).with_probe_mode(args.probe_mode);
+    let report = match health_checker
+        .probe_once(&node, Duration::from_secs(args.timeout))
+        .await
+    {
+        Ok(result) => CheckReport {
+            uri: args.uri,
+            status: "online",
+            latency_ms: Some(result.response_time as f64 / 1000.0),
+            version: result.version.filter(|v| !v.is_empty()),
+            loss_rate: Some(result.loss_rate),
+            load: result.load,
+            error_kind: None,
+            error: None,
+        },
+        Err(e) => CheckReport {
+            uri: args.uri,
+            status: "offline",
+            latency_ms: None,
+            version: None,
+            loss_rate: None,
+            load: None,
+            error_kind: Some(probe_error::error_kind(&e)),
+            error: Some(format!("{:#}", e)),
+        },
+    };
+
+    match args.format {
+        OutputFormat::Table => {
+            let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+            print_table(&[
+                ("uri", report.uri.clone()),
+                ("status", report.status.to_string()),
+                (
+                    "latency_ms",
+                    or_dash(report.latency_ms.map(|ms| format!("{:.2}", ms))),
+                ),
+                ("version", or_dash(report.version.clone())),
+                (
+                    "loss_rate",
+                    or_dash(report.loss_rate.map(|rate| format!("{:.2}", rate))),
+                ),
+                (
+                    "foreign_networks",
+                    or_dash(
+                        report
+                            .load
+                            .as_ref()
+                            .map(|l| l.foreign_network_count.to_string()),
+                    ),
+                ),
+                (
+                    "foreign_peers",
+                    or_dash(
+                        report
+                            .load
+                            .as_ref()
+                            .map(|l| l.foreign_peer_count.to_string()),
+                    ),
+                ),
+                (
+                    "error_kind",
+                    or_dash(report.error_kind.map(|kind| kind.to_string())),
+                ),
+                ("error", or_dash(report.error.clone())),
+            ]);
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+
+    Ok(if report.error.is_none() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn print_table(rows: &[(&str, String)]) {
+    let width = rows.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    for (key, value) in rows {
+        println!("{:width$}  {}", key, value, width = width);
+    }
+}
+
+/// 导出的单条健康记录，延迟单位为毫秒
+#[derive(Debug, Serialize)]
+struct ExportRecord {
+    checked_at: DateTime<Utc>,
+    status: String,
+    latency_ms: Option<f64>,
+    loss_rate: Option<f64>,
+    jitter_ms: Option<f64>,
+    p50_latency_ms: Option<f64>,
+    p95_latency_ms: Option<f64>,
+    error_kind: Option<String>,
+    error_message: Option<String>,
+}
+
+impl From<health_records::Model> for ExportRecord {
+    fn from(record: health_records::Model) -> Self {
+        let us_to_ms = |us: f64| us / 1000.0;
+        Self {
+            checked_at: record.checked_at.to_utc(),
+            status: record.status,
+            latency_ms: (record.response_time > 0).then(|| us_to_ms(record.response_time as f64)),
+            loss_rate: record.loss_rate,
+            jitter_ms: record.jitter_us.map(us_to_ms),
+            p50_latency_ms: record.p50_latency.map(|us| us_to_ms(us as f64)),
+            p95_latency_ms: record.p95_latency.map(|us| us_to_ms(us as f64)),
+            error_kind: record.error_kind,
+            error_message: Some(record.error_message).filter(|msg| !msg.is_empty()),
+        }
+    }
+}
+
+const CSV_HEADER: &str = "checked_at,status,latency_ms,loss_rate,jitter_ms,p50_latency_ms,p95_latency_ms,error_kind,error_message";
+
+async fn export(db: &Db, args: ExportArgs) -> anyhow::Result<()> {
+    NodeOperations::get_node_by_id(db, args.node)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Node {} not found", args.node))?;
+    let records = HealthOperations::get_node_health_records_since(db, args.node, args.since.0)
+        .await?
+        .into_iter()
+        .map(ExportRecord::from)
+        .collect::<Vec<_>>();
+
+    match &args.output {
+        Some(path) => {
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+            let mut out = BufWriter::new(file);
+            write_export(&mut out, &records, args.format)?;
+            out.flush()?;
+            eprintln!(
+                "Exported {} records of node {} to {}",
+                records.len(),
+                args.node,
+                path.display()
+            );
+        }
+        None => write_export(&mut std::io::stdout().lock(), &records, args.format)?,
+    }
+    Ok(())
+}
+
+fn write_export(
+    out: &mut dyn Write,
+    records: &[ExportRecord],
+    format: ExportFormat,
+) -> anyhow::Result<()> {
+    match format {
+        ExportFormat::Csv => {
+            writeln!(out, "{}", CSV_HEADER)?;
+            let opt = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+            for record in records {
+                writeln!(
+                    out,
+                    "{},{},{},{},{},{},{},{},{}",
+                    record.checked_at.to_rfc3339(),
+                    csv_field(&record.status),
+                    opt(record.latency_ms),
+                    opt(record.loss_rate),
+                    opt(record.jitter_ms),
+                    opt(record.p50_latency_ms),
+                    opt(record.p95_latency_ms),
+                    csv_field(record.error_kind.as_deref().unwrap_or_default()),
+                    csv_field(record.error_message.as_deref().unwrap_or_default()),
+                )?;
+            }
+        }
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, records)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+/// 按 RFC 4180 给含有分隔符、引号或换行的字段加引号
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_node_uri() {
+        assert_eq!(
+            parse_node_uri("tcp://public.example.com:11010").unwrap(),
+            ("tcp".to_string(), "public.example.com".to_string(), 11010)
+        );
+        assert_eq!(
+            parse_node_uri("WSS://1.2.3.4").unwrap(),
+            ("wss".to_string(), "1.2.3.4".to_string(), DEFAULT_NODE_PORT)
+        );
+        assert_eq!(
+            parse_node_uri("udp://[::1]:11011").unwrap(),
+            ("udp".to_string(), "[::1]".to_string(), 11011)
+        );
+        assert_eq!(parse_node_uri("udp://[::1]").unwrap().2, DEFAULT_NODE_PORT);
+        assert!(parse_node_uri("public.example.com:11010").is_err());
+        assert!(parse_node_uri("tcp://host:port").is_err());
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let since: Since = "2025-01-01T08:00:00+08:00".parse().unwrap();
+        assert_eq!(since.0.to_rfc3339(), "2025-01-01T00:00:00+00:00");
+
+        let before = Utc::now();
+        let Since(since) = "24h".parse().unwrap();
+        let ago = before - since;
+        assert!(ago >= chrono::Duration::hours(24) - chrono::Duration::seconds(5));
+        assert!(ago <= chrono::Duration::hours(24));
+
+        for invalid in ["", "h", "24", "24w", "昨天"] {
+            assert!(invalid.parse::<Since>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_write_export() {
+        let records = vec![
+            ExportRecord {
+                checked_at: DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+                    .unwrap()
+                    .to_utc(),
+                status: "Healthy".to_string(),
+                latency_ms: Some(12.5),
+                loss_rate: Some(0.0),
+                jitter_ms: None,
+                p50_latency_ms: None,
+                p95_latency_ms: None,
+                error_kind: None,
+                error_message: None,
+            },
+            ExportRecord {
+                checked_at: DateTime::parse_from_rfc3339("2025-01-01T00:00:05Z")
+                    .unwrap()
+                    .to_utc(),
+                status: "Unhealthy".to_string(),
+                latency_ms: None,
+                loss_rate: None,
+                jitter_ms: None,
+                p50_latency_ms: None,
+                p95_latency_ms: None,
+                error_kind: Some("connect_refused".to_string()),
+                error_message: Some("connect \"1.2.3.4\", refused".to_string()),
+            },
+        ];
+
+        let mut csv = Vec::new();
+        write_export(&mut csv, &records, ExportFormat::Csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines[1], "2025-01-01T00:00:00+00:00,Healthy,12.5,0,,,,,");
+        assert_eq!(
+            lines[2],
+            "2025-01-01T00:00:05+00:00,Unhealthy,,,,,,connect_refused,\"connect \"\"1.2.3.4\"\", refused\""
+        );
+
+        let mut json = Vec::new();
+        write_export(&mut json, &records, ExportFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value[0]["latency_ms"], 12.5);
+        assert_eq!(value[1]["error_kind"], "connect_refused");
+    }
+}
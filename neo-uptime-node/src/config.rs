@@ -1,247 +1,405 @@
-use std::env;
-use std::net::{IpAddr, SocketAddr};
-use std::path::PathBuf;
-
-use easytier::common::config::{ConsoleLoggerConfig, FileLoggerConfig, LoggingConfig};
-
-#[derive(Debug, Clone)]
-pub struct AppConfig {
-    pub server: ServerConfig,
-    pub database: DatabaseConfig,
-    pub health_check: HealthCheckConfig,
-    pub logging: LoggingConfig,
-    pub cors: CorsConfig,
-    pub security: SecurityConfig,
-    pub distributed: DistributedConfig,
-}
-
-#[derive(Debug, Clone)]
-pub struct ServerConfig {
-    pub host: String,
-    pub port: u16,
-    pub addr: SocketAddr,
-}
-
-#[derive(Debug, Clone)]
-pub struct DatabaseConfig {
-    pub path: PathBuf,
-    pub max_connections: u32,
-}
-
-#[derive(Debug, Clone)]
-pub struct HealthCheckConfig {
-    pub interval_seconds: u64,
-    pub timeout_seconds: u64,
-    pub max_retries: u32,
-}
-
-#[derive(Debug, Clone)]
-pub struct CorsConfig {
-    pub allowed_origins: Vec<String>,
-    pub allowed_methods: Vec<String>,
-    pub allowed_headers: Vec<String>,
-    pub enabled: bool,
-}
-
-#[derive(Debug, Clone)]
-pub struct SecurityConfig {
-    pub enable_compression: bool,
-    pub secret_key: String,
-    pub jwt_secret: String,
-    pub admin_password: String,
-}
-
-#[derive(Debug, Clone)]
-pub struct DistributedConfig {
-    /// Enable distributed probe mode
-    pub enabled: bool,
-    /// Backend base URL (e.g., "http://backend.example.com")
-    pub backend_base_url: Option<String>,
-    /// API key for authentication
+//! 探测节点配置文件
+//!
+//! 配置文件为 TOML 格式，各项与命令行参数一一对应；命令行或环境变量中显式给出的参数优先。
+//! 运行中会定期检查配置文件，间隔、标签过滤条件和最低节点版本在修改后立即生效，其余设置需要重启。
+
+use std::{
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use tokio::{
+    sync::watch,
+    time::{interval, Interval},
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    health_checker::ProbeMode,
+    node_version::NodeVersion,
+    notifier::MuteWindow,
+    tag_filter::{TagExpr, TagFilter},
+};
+
+/// 配置文件内容，未填写的项使用命令行参数的默认值
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub backend: BackendSection,
+    pub server: ServerSection,
+    pub database: DatabaseSection,
+    pub logging: LoggingSection,
+    pub intervals: IntervalsSection,
+    pub health_check: HealthCheckSection,
+    pub state: StateSection,
+    pub sharding: ShardingSection,
+    pub retention: RetentionSection,
+    pub tags: TagsSection,
+    pub versions: VersionsSection,
+    pub notifier: NotifierSection,
+    pub shutdown: ShutdownSection,
+}
+
+/// 后端连接，`base_url` 与 `peers_file` 二选一
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackendSection {
+    pub base_url: Option<String>,
     pub api_key: Option<String>,
-    /// Region identifier (optional)
+    pub peers_file: Option<PathBuf>,
+    pub report_file: Option<PathBuf>,
     pub region: Option<String>,
-    /// Interval for fetching peers from backend (seconds)
-    pub peer_fetch_interval: u64,
-    /// Interval for reporting node status to backend (seconds)
-    pub status_report_interval: u64,
+    pub probe_name: Option<String>,
 }
 
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self::from_env().unwrap_or_else(|_| Self::default_config())
+/// 本地状态服务监听地址
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSection {
+    pub host: Option<IpAddr>,
+    pub port: Option<u16>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseSection {
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingSection {
+    pub level: Option<String>,
+    pub file: Option<String>,
+}
+
+/// 各周期任务的间隔（秒），修改后立即生效
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IntervalsSection {
+    pub peer_fetch: Option<u64>,
+    pub status_report: Option<u64>,
+    pub sla_report: Option<u64>,
+    pub node_monitor: Option<u64>,
+    pub cleanup: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthCheckSection {
+    pub interval: Option<u64>,
+    pub max_interval: Option<u64>,
+    pub backoff_after: Option<i64>,
+    pub confirm_interval: Option<u64>,
+    pub probe_mode: Option<ProbeMode>,
+    pub handshake_ping_duration: Option<u64>,
+    pub quality_window: Option<usize>,
+    pub cert_check_interval: Option<u64>,
+    pub cert_expiry_warning_days: Option<u64>,
+}
+
+/// 节点状态机
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateSection {
+    pub failure_threshold: Option<u32>,
+    pub recovery_threshold: Option<u32>,
+    pub min_dwell: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShardingSection {
+    pub replicas: Option<usize>,
+    pub stale_after: Option<i64>,
+}
+
+/// 历史数据、离线节点和待重放心跳的保留策略
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionSection {
+    pub health_record_days: Option<i64>,
+    pub max_health_records_per_node: Option<u64>,
+    pub rollup_days: Option<i64>,
+    pub missing_node_grace_period: Option<i64>,
+    pub deactivated_node_days: Option<i64>,
+    pub outbox_max_age_hours: Option<i64>,
+    pub outbox_max_entries: Option<u64>,
+}
+
+/// 标签过滤条件，修改后立即生效
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TagsSection {
+    pub include: Option<Vec<TagExpr>>,
+    pub exclude: Option<Vec<TagExpr>>,
+}
+
+/// 节点版本要求，修改后立即生效
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VersionsSection {
+    pub minimum: Option<NodeVersion>,
+}
+
+/// 状态变化通知
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifierSection {
+    pub webhook_urls: Option<Vec<String>>,
+    pub secret: Option<String>,
+    pub mute: Option<Vec<MuteWindow>>,
+    pub batch_window: Option<u64>,
+    pub digest_threshold: Option<usize>,
+    pub notify_degraded: Option<bool>,
+}
+
+/// 退出流程
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShutdownSection {
+    /// 上报最后一批心跳和清理的时限（秒）
+    pub timeout: Option<u64>,
+}
+
+impl ConfigFile {
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        Self::parse(&content)
+            .with_context(|| format!("failed to parse config file {}", path.display()))
     }
 }
 
-impl AppConfig {
-    pub fn from_env() -> Result<Self, env::VarError> {
-        let server_config = ServerConfig {
-            host: env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
-            port: env::var("SERVER_PORT")
-                .map(|s| s.parse().unwrap_or(8080))
-                .unwrap_or(8080),
-            addr: SocketAddr::from((
-                env::var("SERVER_HOST")
-                    .unwrap_or_else(|_| "127.0.0.1".to_string())
-                    .parse::<IpAddr>()
-                    .unwrap(),
-                env::var("SERVER_PORT")
-                    .map(|s| s.parse().unwrap_or(8080))
-                    .unwrap_or(8080),
-            )),
-        };
-
-        let database_config = DatabaseConfig {
-            path: PathBuf::from(
-                env::var("DATABASE_PATH").unwrap_or_else(|_| "uptime.db".to_string()),
-            ),
-            max_connections: env::var("DATABASE_MAX_CONNECTIONS")
-                .map(|s| s.parse().unwrap_or(10))
-                .unwrap_or(10),
-        };
-
-        let health_check_config = HealthCheckConfig {
-            interval_seconds: env::var("HEALTH_CHECK_INTERVAL")
-                .map(|s| s.parse().unwrap_or(30))
-                .unwrap_or(30),
-            timeout_seconds: env::var("HEALTH_CHECK_TIMEOUT")
-                .map(|s| s.parse().unwrap_or(10))
-                .unwrap_or(10),
-            max_retries: env::var("HEALTH_CHECK_RETRIES")
-                .map(|s| s.parse().unwrap_or(3))
-                .unwrap_or(3),
-        };
-
-        let logging_config = LoggingConfig {
-            file_logger: Some(FileLoggerConfig {
-                level: Some(env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string())),
-                file: Some("easytier-uptime.log".to_string()),
-                ..Default::default()
-            }),
-            console_logger: Some(ConsoleLoggerConfig {
-                level: Some(env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string())),
-            }),
-        };
-
-        let cors_config = CorsConfig {
-            allowed_origins: env::var("CORS_ALLOWED_ORIGINS")
-                .unwrap_or_else(|_| "http://localhost:3000,http://localhost:8080".to_string())
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .collect(),
-            allowed_methods: env::var("CORS_ALLOWED_METHODS")
-                .unwrap_or_else(|_| "GET,POST,PUT,DELETE,OPTIONS".to_string())
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .collect(),
-            allowed_headers: env::var("CORS_ALLOWED_HEADERS")
-                .unwrap_or_else(|_| "content-type,authorization".to_string())
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .collect(),
-            enabled: env::var("ENABLE_CORS")
-                .map(|s| s.parse().unwrap_or(true))
-                .unwrap_or(true),
-        };
-
-        let security_config = SecurityConfig {
-            enable_compression: env::var("ENABLE_COMPRESSION")
-                .map(|s| s.parse().unwrap_or(true))
-                .unwrap_or(true),
-            secret_key: env::var("SECRET_KEY").unwrap_or_else(|_| "default-secret-key".to_string()),
-            jwt_secret: env::var("JWT_SECRET").unwrap_or_else(|_| "default-jwt-secret".to_string()),
-            admin_password: env::var("ADMIN_PASSWORD").unwrap_or_else(|_| "admin123".to_string()),
-        };
-
-        let distributed_config = DistributedConfig {
-            enabled: env::var("DISTRIBUTED_MODE_ENABLED")
-                .map(|s| s.parse().unwrap_or(false))
-                .unwrap_or(false),
-            backend_base_url: env::var("BACKEND_BASE_URL").ok(),
-            api_key: env::var("API_KEY").ok(),
-            region: env::var("REGION").ok(),
-            peer_fetch_interval: env::var("PEER_FETCH_INTERVAL")
-                .map(|s| s.parse().unwrap_or(60))
-                .unwrap_or(60),
-            status_report_interval: env::var("STATUS_REPORT_INTERVAL")
-                .map(|s| s.parse().unwrap_or(30))
-                .unwrap_or(30),
-        };
-
-        Ok(AppConfig {
-            server: server_config,
-            database: database_config,
-            health_check: health_check_config,
-            logging: logging_config,
-            cors: cors_config,
-            security: security_config,
-            distributed: distributed_config,
-        })
+/// 运行中可以重新加载的设置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeSettings {
+    pub peer_fetch_interval: Duration,
+    pub status_report_interval: Duration,
+    /// 为 0 时不上报 SLA
+    pub sla_report_interval: Duration,
+    pub node_monitor_interval: Duration,
+    pub tag_filter: TagFilter,
+    /// 低于该版本的节点在上报和状态服务中标记为过旧
+    pub min_node_version: Option<NodeVersion>,
+}
+
+impl Default for RuntimeSettings {
+    fn default() -> Self {
+        Self {
+            peer_fetch_interval: Duration::from_secs(60),
+            status_report_interval: Duration::from_secs(30),
+            sla_report_interval: Duration::from_secs(3600),
+            node_monitor_interval: Duration::from_secs(5),
+            tag_filter: TagFilter::default(),
+            min_node_version: None,
+        }
     }
+}
+
+/// 定期读取配置文件，内容变化后用 `load` 重新加载设置并通知各任务
+///
+/// 加载或校验失败时记录错误并保留当前设置，修正配置文件后会再次尝试。
+pub fn watch_config_file<F>(
+    path: PathBuf,
+    poll_interval: Duration,
+    initial: RuntimeSettings,
+    load: F,
+) -> watch::Receiver<RuntimeSettings>
+where
+    F: Fn() -> anyhow::Result<RuntimeSettings> + Send + 'static,
+{
+    let (tx, rx) = watch::channel(initial);
+    let mut last_content = std::fs::read_to_string(&path).ok();
+
+    tokio::spawn(async move {
+        let mut ticker = interval(poll_interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+
+            let content = match tokio::fs::read_to_string(&path).await {
+                Ok(content) => Some(content),
+                Err(e) => {
+                    if last_content.is_some() {
+                        warn!(
+                            "Failed to read config file {}, keeping current settings: {}",
+                            path.display(),
+                            e
+                        );
+                    }
+                    last_content = None;
+                    None
+                }
+            };
+            if content.is_none() || content == last_content {
+                continue;
+            }
+            last_content = content;
+
+            match load() {
+                Ok(settings) => {
+                    info!(
+                        "Reloaded config file {}, settings other than intervals, tag filters and minimum node version take effect after a restart",
+                        path.display()
+                    );
+                    tx.send_if_modified(|current| {
+                        if *current == settings {
+                            return false;
+                        }
+                        *current = settings;
+                        true
+                    });
+                }
+                Err(e) => error!(
+                    "Invalid config file {}, keeping current settings: {:#}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+    });
+
+    rx
+}
+
+/// 间隔可以重新加载的定时器，间隔变化后重新开始计时
+pub struct ReloadableInterval<F> {
+    name: &'static str,
+    settings: watch::Receiver<RuntimeSettings>,
+    period_of: F,
+    period: Duration,
+    ticker: Option<Interval>,
+}
 
-    pub fn default_config() -> Self {
+impl<F> ReloadableInterval<F>
+where
+    F: Fn(&RuntimeSettings) -> Duration,
+{
+    pub fn new(
+        name: &'static str,
+        mut settings: watch::Receiver<RuntimeSettings>,
+        period_of: F,
+    ) -> Self {
+        let period = period_of(&settings.borrow_and_update());
         Self {
-            server: ServerConfig {
-                host: "127.0.0.1".to_string(),
-                port: 8080,
-                addr: SocketAddr::from(([127, 0, 0, 1], 8080)),
-            },
-            database: DatabaseConfig {
-                path: PathBuf::from("uptime.db"),
-                max_connections: 10,
-            },
-            health_check: HealthCheckConfig {
-                interval_seconds: 30,
-                timeout_seconds: 10,
-                max_retries: 3,
-            },
-            logging: LoggingConfig {
-                file_logger: Some(FileLoggerConfig {
-                    level: Some("info".to_string()),
-                    file: Some("easytier-uptime.log".to_string()),
-                    ..Default::default()
-                }),
-                console_logger: Some(ConsoleLoggerConfig {
-                    level: Some("info".to_string()),
-                }),
-            },
-            cors: CorsConfig {
-                allowed_origins: vec![
-                    "http://localhost:3000".to_string(),
-                    "http://localhost:8080".to_string(),
-                ],
-                allowed_methods: vec![
-                    "GET".to_string(),
-                    "POST".to_string(),
-                    "PUT".to_string(),
-                    "DELETE".to_string(),
-                    "OPTIONS".to_string(),
-                ],
-                allowed_headers: vec!["content-type".to_string(), "authorization".to_string()],
-                enabled: true,
-            },
-            security: SecurityConfig {
-                enable_compression: true,
-                secret_key: "default-secret-key".to_string(),
-                jwt_secret: "default-jwt-secret".to_string(),
-                admin_password: "admin123".to_string(),
-            },
-            distributed: DistributedConfig {
-                enabled: false,
-                backend_base_url: None,
-                api_key: None,
-                region: None,
-                peer_fetch_interval: 60,
-                status_report_interval: 30,
-            },
+            name,
+            settings,
+            period_of,
+            period,
+            ticker: (!period.is_zero()).then(|| interval(period)),
         }
     }
 
-    pub fn is_development(&self) -> bool {
-        env::var("NODE_ENV").unwrap_or_else(|_| "development".to_string()) == "development"
+    /// 当前间隔，为 0 表示暂停
+    pub fn period(&self) -> Duration {
+        self.period
     }
 
-    pub fn is_production(&self) -> bool {
-        env::var("NODE_ENV").unwrap_or_else(|_| "development".to_string()) == "production"
+    /// 等待下一次触发；间隔为 0 时一直等待，直到间隔被改为非 0
+    pub async fn tick(&mut self) {
+        loop {
+            let changed = match self.ticker.as_mut() {
+                Some(ticker) => tokio::select! {
+                    _ = ticker.tick() => false,
+                    Ok(()) = self.settings.changed() => true,
+                },
+                None => {
+                    if self.settings.changed().await.is_err() {
+                        // 配置不会再变化
+                        std::future::pending::<()>().await;
+                    }
+                    true
+                }
+            };
+            if !changed {
+                return;
+            }
+
+            let period = (self.period_of)(&self.settings.borrow_and_update());
+            if period != self.period {
+                info!("{} interval changed to {:?}", self.name, period);
+                self.period = period;
+                self.ticker = (!period.is_zero()).then(|| interval(period));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config_file() {
+        let config = ConfigFile::parse(
+            r#"
+            [backend]
+            base_url = "https://backend.example.com"
+            api_key = "secret"
+            region = "asia"
+
+            [intervals]
+            peer_fetch = 120
+
+            [health_check]
+            probe_mode = "handshake"
+
+            [tags]
+            include = ["asia+relay", "europe"]
+
+            [versions]
+            minimum = "2.4.0"
+
+            [notifier]
+            webhook_urls = ["https://hooks.example.com/uptime"]
+            mute = ["12@01:00-03:30"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.backend.region.as_deref(), Some("asia"));
+        assert_eq!(config.intervals.peer_fetch, Some(120));
+        assert_eq!(config.intervals.status_report, None);
+        assert_eq!(config.health_check.probe_mode, Some(ProbeMode::Handshake));
+        let include = config.tags.include.unwrap();
+        assert_eq!(include[0].to_string(), "asia+relay");
+        assert_eq!(config.versions.minimum.unwrap().to_string(), "2.4.0");
+        assert_eq!(config.notifier.mute.unwrap()[0].node_id, 12);
+
+        // 拼错的配置项和无效的值直接报错，不会被静默忽略
+        assert!(ConfigFile::parse("[intervals]\npeer_fetc = 10\n").is_err());
+        assert!(ConfigFile::parse("[tags]\ninclude = [\"asia+\"]\n").is_err());
+        assert!(ConfigFile::parse("[health_check]\nprobe_mode = \"ping\"\n").is_err());
+        assert!(ConfigFile::parse("[versions]\nminimum = \"latest\"\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reloadable_interval() {
+        let (tx, rx) = watch::channel(RuntimeSettings {
+            sla_report_interval: Duration::ZERO,
+            ..Default::default()
+        });
+        let mut ticker = ReloadableInterval::new("SLA report", rx, |s| s.sla_report_interval);
+        assert_eq!(ticker.period(), Duration::ZERO);
+
+        // 间隔为 0 时暂停
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), ticker.tick())
+                .await
+                .is_err()
+        );
+
+        tx.send_modify(|s| s.sla_report_interval = Duration::from_millis(10));
+        tokio::time::timeout(Duration::from_secs(1), ticker.tick())
+            .await
+            .unwrap();
+        assert_eq!(ticker.period(), Duration::from_millis(10));
     }
 }
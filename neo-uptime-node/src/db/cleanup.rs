@@ -1,4 +1,5 @@
 use crate::db::entity::*;
+use crate::db::operations::RollupOperations;
 use crate::db::Db;
 use sea_orm::*;
 use tokio::time::{sleep, Duration};
@@ -15,6 +16,10 @@ pub struct CleanupConfig {
     pub cleanup_interval_seconds: u64,
     /// 是否启用自动清理
     pub auto_cleanup_enabled: bool,
+    /// 清理原始记录前是否先按小时汇总
+    pub rollup_enabled: bool,
+    /// 小时汇总记录保留天数
+    pub rollup_retention_days: i64,
 }
 
 impl Default for CleanupConfig {
@@ -24,6 +29,8 @@ impl Default for CleanupConfig {
             max_health_records_per_node: 70000,
             cleanup_interval_seconds: 1200, // 20分钟
             auto_cleanup_enabled: true,
+            rollup_enabled: true,
+            rollup_retention_days: 365,
         }
     }
 }
@@ -87,6 +94,15 @@ impl CleanupManager {
     pub async fn perform_cleanup(db: &Db, config: &CleanupConfig) -> anyhow::Result<CleanupResult> {
         let mut result = CleanupResult::default();
 
+        // 先汇总已结束的小时，确保原始记录清理后长期历史仍然保留
+        if config.rollup_enabled {
+            result.health_rollups_created =
+                RollupOperations::rollup_completed_hours(db, chrono::Utc::now()).await?;
+
+            result.old_health_rollups_cleaned =
+                RollupOperations::cleanup_old_rollups(db, config.rollup_retention_days).await?;
+        }
+
         // 清理旧的健康记录
         let health_cleanup_result =
             Self::cleanup_old_health_records(db, config.health_record_retention_days).await?;
@@ -112,7 +128,8 @@ impl CleanupManager {
         db: &Db,
         days: i64,
     ) -> anyhow::Result<CleanupHealthRecordsResult> {
-        let cutoff = chrono::Local::now().fixed_offset() - chrono::Duration::days(days);
+        // 与写入时一致使用 UTC，保证时间字符串比较正确
+        let cutoff = chrono::Utc::now().fixed_offset() - chrono::Duration::days(days);
 
         let result = health_records::Entity::delete_many()
             .filter(health_records::Column::CheckedAt.lt(cutoff))
@@ -192,7 +209,7 @@ impl CleanupManager {
     }
 
     /// 执行数据库维护操作
-    async fn perform_database_maintenance(db: &Db) -> anyhow::Result<DatabaseMaintenanceResult> {
+    pub async fn perform_database_maintenance(db: &Db) -> anyhow::Result<DatabaseMaintenanceResult> {
         let mut vacuum_performed = false;
         let mut analyze_performed = false;
 
@@ -246,6 +263,8 @@ impl CleanupManager {
 
         let total_health_records = health_records::Entity::find().count(db.orm_db()).await?;
 
+        let total_health_rollups = health_rollups::Entity::find().count(db.orm_db()).await?;
+
         let active_nodes = shared_nodes::Entity::find()
             .filter(shared_nodes::Column::IsActive.eq(true))
             .count(db.orm_db())
@@ -255,6 +274,7 @@ impl CleanupManager {
             total_nodes,
             active_nodes,
             total_health_records,
+            total_health_rollups,
         })
     }
 
@@ -272,6 +292,8 @@ impl CleanupManager {
 /// 清理结果
 #[derive(Default, Debug, Clone, serde::Serialize)]
 pub struct CleanupResult {
+    pub health_rollups_created: u64,
+    pub old_health_rollups_cleaned: u64,
     pub old_health_records_cleaned: u64,
     pub old_instances_cleaned: u64,
     pub excess_health_records_cleaned: u64,
@@ -310,6 +332,7 @@ pub struct DatabaseStats {
     pub total_nodes: u64,
     pub active_nodes: u64,
     pub total_health_records: u64,
+    pub total_health_rollups: u64,
 }
 
 #[cfg(test)]
@@ -342,6 +365,8 @@ mod tests {
             max_health_records_per_node: 500,
             cleanup_interval_seconds: 1800,
             auto_cleanup_enabled: false,
+            rollup_enabled: true,
+            rollup_retention_days: 90,
         };
 
         let db = Db::memory_db().await;
@@ -3,7 +3,7 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "health_records")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -14,6 +14,13 @@ pub struct Model {
     #[sea_orm(column_type = "Text")]
     pub error_message: String,
     pub checked_at: DateTimeWithTimeZone,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub loss_rate: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub jitter_us: Option<f64>,
+    pub p50_latency: Option<i32>,
+    pub p95_latency: Option<i32>,
+    pub error_kind: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
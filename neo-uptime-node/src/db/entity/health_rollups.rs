@@ -0,0 +1,40 @@
+//! `SeaORM` Entity for hourly health record rollups
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "health_rollups")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub node_id: i32,
+    pub bucket_start: DateTimeWithTimeZone,
+    pub check_count: i32,
+    pub success_count: i32,
+    pub min_latency: Option<i32>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub avg_latency: Option<f64>,
+    pub p95_latency: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::shared_nodes::Entity",
+        from = "Column::NodeId",
+        to = "super::shared_nodes::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    SharedNodes,
+}
+
+impl Related<super::shared_nodes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SharedNodes.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
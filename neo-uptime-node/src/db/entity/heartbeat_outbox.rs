@@ -0,0 +1,39 @@
+//! `SeaORM` Entity for undelivered heartbeats
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "heartbeat_outbox")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub backend_peer_id: i32,
+    pub status: String,
+    pub peer: i32,
+    pub latency_ms: i32,
+    pub checked_at: DateTimeWithTimeZone,
+    pub attempts: i32,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_error: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+    pub state: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub quality: Option<String>,
+    pub error_kind: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub listeners: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub load: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub certificates: Option<String>,
+    pub version: Option<String>,
+    pub version_outdated: bool,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub features: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
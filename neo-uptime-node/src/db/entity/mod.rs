@@ -3,5 +3,12 @@
 pub mod prelude;
 
 pub mod health_records;
+pub mod health_rollups;
+pub mod heartbeat_outbox;
+pub mod node_certificates;
+pub mod node_listeners;
+pub mod node_state_events;
 pub mod node_tags;
+pub mod node_versions;
+pub mod probe_identity;
 pub mod shared_nodes;
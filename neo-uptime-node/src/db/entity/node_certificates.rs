@@ -0,0 +1,47 @@
+//! `SeaORM` Entity for TLS certificates presented by wss and quic listeners
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "node_certificates")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub node_id: i32,
+    pub url: String,
+    pub status: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub subject: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub issuer: Option<String>,
+    pub not_before: Option<DateTimeWithTimeZone>,
+    pub not_after: Option<DateTimeWithTimeZone>,
+    pub self_signed: Option<bool>,
+    pub hostname_match: Option<bool>,
+    pub trusted: Option<bool>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_error_message: Option<String>,
+    pub checked_at: DateTimeWithTimeZone,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::shared_nodes::Entity",
+        from = "Column::NodeId",
+        to = "super::shared_nodes::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    SharedNodes,
+}
+
+impl Related<super::shared_nodes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SharedNodes.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,5 +1,12 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
 pub use super::health_records::Entity as HealthRecords;
+pub use super::health_rollups::Entity as HealthRollups;
+pub use super::heartbeat_outbox::Entity as HeartbeatOutbox;
+pub use super::node_certificates::Entity as NodeCertificates;
+pub use super::node_listeners::Entity as NodeListeners;
+pub use super::node_state_events::Entity as NodeStateEvents;
 pub use super::node_tags::Entity as NodeTags;
+pub use super::node_versions::Entity as NodeVersions;
+pub use super::probe_identity::Entity as ProbeIdentity;
 pub use super::shared_nodes::Entity as SharedNodes;
@@ -0,0 +1,23 @@
+//! `SeaORM` Entity for the probe identity registered with a backend
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "probe_identity")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub backend_url: String,
+    pub probe_id: String,
+    pub name: String,
+    pub region: Option<String>,
+    pub secret: String,
+    pub created_at: DateTimeWithTimeZone,
+    pub registered_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -27,15 +27,30 @@ pub struct Model {
     pub mail: String,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
+    pub backend_peer_id: Option<i32>,
+    pub source: String,
+    pub last_synced_at: Option<DateTimeWithTimeZone>,
+    pub deactivated_at: Option<DateTimeWithTimeZone>,
+    pub released_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::health_records::Entity")]
     HealthRecords,
+    #[sea_orm(has_many = "super::health_rollups::Entity")]
+    HealthRollups,
+    #[sea_orm(has_many = "super::node_certificates::Entity")]
+    NodeCertificates,
+    #[sea_orm(has_many = "super::node_listeners::Entity")]
+    NodeListeners,
+    #[sea_orm(has_many = "super::node_state_events::Entity")]
+    NodeStateEvents,
     // add relation to node_tags
     #[sea_orm(has_many = "super::node_tags::Entity")]
     NodeTags,
+    #[sea_orm(has_many = "super::node_versions::Entity")]
+    NodeVersions,
 }
 
 impl Related<super::health_records::Entity> for Entity {
@@ -44,10 +59,40 @@ impl Related<super::health_records::Entity> for Entity {
     }
 }
 
+impl Related<super::health_rollups::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::HealthRollups.def()
+    }
+}
+
+impl Related<super::node_certificates::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::NodeCertificates.def()
+    }
+}
+
+impl Related<super::node_listeners::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::NodeListeners.def()
+    }
+}
+
+impl Related<super::node_state_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::NodeStateEvents.def()
+    }
+}
+
 impl Related<super::node_tags::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::NodeTags.def()
     }
 }
 
+impl Related<super::node_versions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::NodeVersions.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
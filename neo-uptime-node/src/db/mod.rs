@@ -89,6 +89,17 @@ impl Db {
         &self.orm_db
     }
 
+    /// 把 WAL 中的内容写回数据库文件并清空 WAL，用于进程退出前
+    pub async fn checkpoint(&self) -> Result<(), DbErr> {
+        self.orm_db
+            .execute(Statement::from_string(
+                sea_orm::DatabaseBackend::Sqlite,
+                "PRAGMA wal_checkpoint(TRUNCATE)".to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+
     /// 清理旧的健康度记录（删除30天前的记录）
     pub async fn cleanup_old_health_records(&self) -> Result<u64, DbErr> {
         use chrono::Duration;
@@ -177,6 +188,167 @@ impl From<&str> for HealthStatus {
     }
 }
 
+/// 节点派生状态：由连续检查结果经滞后处理得出，避免单次检查失败造成抖动
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeState {
+    /// 在线
+    Up,
+    /// 最近有检查失败，尚未确认离线
+    Degraded,
+    /// 离线
+    Down,
+    /// 尚无检查结果
+    Unknown,
+}
+
+impl fmt::Display for NodeState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeState::Up => write!(f, "up"),
+            NodeState::Degraded => write!(f, "degraded"),
+            NodeState::Down => write!(f, "down"),
+            NodeState::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+impl From<&str> for NodeState {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "up" => NodeState::Up,
+            "degraded" => NodeState::Degraded,
+            "down" => NodeState::Down,
+            _ => NodeState::Unknown,
+        }
+    }
+}
+
+/// 检查失败原因分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeErrorKind {
+    /// 域名解析失败
+    DnsResolutionFailed,
+    /// 连接被拒绝
+    ConnectRefused,
+    /// 连接超时
+    ConnectTimeout,
+    /// 握手被拒绝，通常是网络名或密钥不匹配
+    HandshakeRejected,
+    /// 没有到目标节点的路由
+    NoRoute,
+    /// 本地探测实例异常
+    InstanceError,
+    /// 无法识别的错误
+    Unknown,
+}
+
+impl ProbeErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProbeErrorKind::DnsResolutionFailed => "dns_resolution_failed",
+            ProbeErrorKind::ConnectRefused => "connect_refused",
+            ProbeErrorKind::ConnectTimeout => "connect_timeout",
+            ProbeErrorKind::HandshakeRejected => "handshake_rejected",
+            ProbeErrorKind::NoRoute => "no_route",
+            ProbeErrorKind::InstanceError => "instance_error",
+            ProbeErrorKind::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for ProbeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for ProbeErrorKind {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "dns_resolution_failed" => ProbeErrorKind::DnsResolutionFailed,
+            "connect_refused" => ProbeErrorKind::ConnectRefused,
+            "connect_timeout" => ProbeErrorKind::ConnectTimeout,
+            "handshake_rejected" => ProbeErrorKind::HandshakeRejected,
+            "no_route" => ProbeErrorKind::NoRoute,
+            "instance_error" => ProbeErrorKind::InstanceError,
+            _ => ProbeErrorKind::Unknown,
+        }
+    }
+}
+
+/// wss / quic 地址出示的 TLS 证书状态，按严重程度从高到低判定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CertStatus {
+    /// 无法完成 TLS 握手，没有拿到证书
+    Unavailable,
+    /// 不在有效期内（已过期或尚未生效）
+    Expired,
+    /// 将在告警期内过期
+    Expiring,
+    /// 自签名证书，EasyTier 默认生成的证书即为此类，只检查有效期
+    SelfSigned,
+    /// 证书与节点域名或 IP 不匹配
+    HostnameMismatch,
+    /// 证书链无法校验到受信任的根证书
+    Untrusted,
+    /// 有效
+    Valid,
+}
+
+impl CertStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CertStatus::Unavailable => "unavailable",
+            CertStatus::Expired => "expired",
+            CertStatus::Expiring => "expiring",
+            CertStatus::SelfSigned => "self_signed",
+            CertStatus::HostnameMismatch => "hostname_mismatch",
+            CertStatus::Untrusted => "untrusted",
+            CertStatus::Valid => "valid",
+        }
+    }
+
+    /// 需要告警的状态
+    pub fn needs_attention(&self) -> bool {
+        !matches!(self, CertStatus::SelfSigned | CertStatus::Valid)
+    }
+}
+
+impl fmt::Display for CertStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for CertStatus {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "expired" => CertStatus::Expired,
+            "expiring" => CertStatus::Expiring,
+            "self_signed" => CertStatus::SelfSigned,
+            "hostname_mismatch" => CertStatus::HostnameMismatch,
+            "untrusted" => CertStatus::Untrusted,
+            "valid" => CertStatus::Valid,
+            _ => CertStatus::Unavailable,
+        }
+    }
+}
+
+/// 链路质量：最近一段滑动窗口内的丢包率、抖动和延迟分位数（微秒）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LinkQuality {
+    /// 平均丢包率（0~1）
+    pub loss_rate: Option<f64>,
+    /// 抖动：RTT 标准差
+    pub jitter_us: Option<f64>,
+    /// RTT 中位数
+    pub p50_latency: Option<i32>,
+    /// RTT P95
+    pub p95_latency: Option<i32>,
+}
+
 /// 健康统计信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HealthStats {
@@ -295,6 +467,14 @@ impl entity::health_records::Model {
     }
 }
 
+/// Model 的扩展方法
+impl entity::node_state_events::Model {
+    /// 获取转换后的状态
+    pub fn get_to_state(&self) -> NodeState {
+        NodeState::from(self.to_state.as_str())
+    }
+}
+
 /// Model 的扩展方法
 impl entity::shared_nodes::Model {
     /// 创建新的活动模型
@@ -347,5 +527,7 @@ mod tests {
         assert_eq!(stats.total_nodes, 0);
         assert_eq!(stats.active_nodes, 0);
         assert_eq!(stats.total_health_records, 0);
+
+        db.checkpoint().await.unwrap();
     }
 }
@@ -1,11 +1,19 @@
+use crate::cert_check::CertificateInfo;
 use crate::models::CreateNodeRequest;
 use crate::db::entity::*;
 use crate::db::Db;
 use crate::db::HealthStats;
 use crate::db::HealthStatus;
+use crate::db::{CertStatus, LinkQuality, ProbeErrorKind};
+use crate::db::NodeState;
 use sea_orm::*;
 use std::collections::{HashMap, HashSet};
 
+/// 手动添加的节点来源标识
+pub const NODE_SOURCE_MANUAL: &str = "manual";
+/// 从后端同步的节点来源标识
+pub const NODE_SOURCE_BACKEND: &str = "backend";
+
 /// 节点管理操作
 pub struct NodeOperations;
 
@@ -31,6 +39,11 @@ impl NodeOperations {
             mail: Set(req.mail.unwrap_or_default()),
             created_at: Set(chrono::Utc::now().fixed_offset()),
             updated_at: Set(chrono::Utc::now().fixed_offset()),
+            backend_peer_id: Set(None),
+            source: Set(NODE_SOURCE_MANUAL.to_string()),
+            last_synced_at: Set(None),
+            deactivated_at: Set(None),
+            released_at: Set(None),
         }
     }
 
@@ -122,25 +135,265 @@ impl NodeOperations {
         Ok(count > 0)
     }
 
+    /// 更新节点当前版本，并记入版本历史
     pub async fn update_node_version(
         db: &Db,
         node_id: i32,
         version: String,
     ) -> Result<shared_nodes::Model, DbErr> {
+        let txn = db.orm_db().begin().await?;
         let mut node = shared_nodes::Entity::find_by_id(node_id)
-            .one(db.orm_db())
+            .one(&txn)
             .await?
             .ok_or(DbErr::RecordNotFound("Node not found".to_string()))?;
 
+        let now = chrono::Utc::now();
+        if !version.is_empty() {
+            VersionOperations::record_seen(&txn, node_id, &version, now).await?;
+        }
+
         let mut node = node.into_active_model();
 
         node.version = Set(version);
-        node.updated_at = Set(chrono::Utc::now().fixed_offset());
+        node.updated_at = Set(now.fixed_offset());
 
-        let updated_node = shared_nodes::Entity::update(node).exec(db.orm_db()).await?;
+        let updated_node = shared_nodes::Entity::update(node).exec(&txn).await?;
+        txn.commit().await?;
 
         Ok(updated_node)
     }
+
+    /// 根据后端节点ID获取节点
+    pub async fn get_node_by_backend_peer_id(
+        db: &Db,
+        backend_peer_id: i32,
+    ) -> Result<Option<shared_nodes::Model>, DbErr> {
+        shared_nodes::Entity::find()
+            .filter(shared_nodes::Column::BackendPeerId.eq(backend_peer_id))
+            .order_by_asc(shared_nodes::Column::Id)
+            .one(db.orm_db())
+            .await
+    }
+
+    /// 获取所有来自后端的节点，按后端节点ID索引
+    pub async fn get_backend_nodes_map(
+        db: &Db,
+    ) -> Result<HashMap<i32, shared_nodes::Model>, DbErr> {
+        let nodes = shared_nodes::Entity::find()
+            .filter(shared_nodes::Column::BackendPeerId.is_not_null())
+            .order_by_asc(shared_nodes::Column::Id)
+            .all(db.orm_db())
+            .await?;
+
+        let mut map = HashMap::new();
+        for node in nodes {
+            if let Some(backend_peer_id) = node.backend_peer_id {
+                map.entry(backend_peer_id).or_insert(node);
+            }
+        }
+        Ok(map)
+    }
+
+    /// 创建从后端同步的节点，后端节点ID、同步时间和审核状态随节点一次写入
+    pub async fn create_backend_node(
+        db: &Db,
+        req: CreateNodeRequest,
+        backend_peer_id: i32,
+    ) -> Result<shared_nodes::Model, DbErr> {
+        let mut node = Self::create_node_model(req);
+        node.backend_peer_id = Set(Some(backend_peer_id));
+        node.source = Set(NODE_SOURCE_BACKEND.to_string());
+        node.is_approved = Set(true);
+        node.last_synced_at = Set(Some(chrono::Utc::now().fixed_offset()));
+        node.insert(db.orm_db()).await
+    }
+
+    /// 记录节点在本次后端列表中出现，只更新同步时间
+    pub async fn mark_synced(
+        db: &Db,
+        node_ids: Vec<i32>,
+        synced_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), DbErr> {
+        if node_ids.is_empty() {
+            return Ok(());
+        }
+        shared_nodes::Entity::update_many()
+            .col_expr(
+                shared_nodes::Column::LastSyncedAt,
+                sea_query::Expr::value(synced_at.fixed_offset()),
+            )
+            .filter(shared_nodes::Column::Id.is_in(node_ids))
+            .exec(db.orm_db())
+            .await?;
+        Ok(())
+    }
+
+    /// 获取需要监控的节点（未停用，也没有分配给其他探测节点）
+    pub async fn get_monitored_nodes(db: &Db) -> Result<Vec<shared_nodes::Model>, DbErr> {
+        shared_nodes::Entity::find()
+            .filter(shared_nodes::Column::DeactivatedAt.is_null())
+            .filter(shared_nodes::Column::ReleasedAt.is_null())
+            .order_by_asc(shared_nodes::Column::Id)
+            .all(db.orm_db())
+            .await
+    }
+
+    /// 停用在 `synced_before` 之后没有再出现在后端列表中的节点，返回本次停用的节点
+    ///
+    /// 从未同步过的节点以创建时间为准；手动添加的节点不受影响。
+    pub async fn deactivate_missing_backend_nodes(
+        db: &Db,
+        synced_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<shared_nodes::Model>, DbErr> {
+        let synced_before = synced_before.fixed_offset();
+        let nodes = shared_nodes::Entity::find()
+            .filter(shared_nodes::Column::Source.eq(NODE_SOURCE_BACKEND))
+            .filter(shared_nodes::Column::DeactivatedAt.is_null())
+            .filter(
+                Condition::any()
+                    .add(shared_nodes::Column::LastSyncedAt.lt(synced_before))
+                    .add(
+                        Condition::all()
+                            .add(shared_nodes::Column::LastSyncedAt.is_null())
+                            .add(shared_nodes::Column::CreatedAt.lt(synced_before)),
+                    ),
+            )
+            .order_by_asc(shared_nodes::Column::Id)
+            .all(db.orm_db())
+            .await?;
+        Self::mark_deactivated(db, &nodes).await?;
+        Ok(nodes)
+    }
+
+    /// 释放绑定到指定后端节点ID、已分配给其他探测节点的节点，返回本次停止监控的节点
+    ///
+    /// 释放的节点仍在后端列表中，因此同时记录同步时间并清除停用标记：
+    /// 它们不会被当作消失的节点停用，历史数据也不会被清理。
+    pub async fn release_backend_peers(
+        db: &Db,
+        backend_peer_ids: Vec<i32>,
+        released_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<shared_nodes::Model>, DbErr> {
+        if backend_peer_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let nodes = shared_nodes::Entity::find()
+            .filter(shared_nodes::Column::Source.eq(NODE_SOURCE_BACKEND))
+            .filter(shared_nodes::Column::BackendPeerId.is_in(backend_peer_ids))
+            .order_by_asc(shared_nodes::Column::Id)
+            .all(db.orm_db())
+            .await?;
+        if nodes.is_empty() {
+            return Ok(nodes);
+        }
+
+        let released_at = released_at.fixed_offset();
+        let txn = db.orm_db().begin().await?;
+        shared_nodes::Entity::update_many()
+            .col_expr(
+                shared_nodes::Column::LastSyncedAt,
+                sea_query::Expr::value(released_at),
+            )
+            .col_expr(
+                shared_nodes::Column::DeactivatedAt,
+                sea_query::Expr::value(Option::<chrono::DateTime<chrono::FixedOffset>>::None),
+            )
+            .filter(shared_nodes::Column::Id.is_in(nodes.iter().map(|n| n.id)))
+            .exec(&txn)
+            .await?;
+        shared_nodes::Entity::update_many()
+            .col_expr(
+                shared_nodes::Column::ReleasedAt,
+                sea_query::Expr::value(released_at),
+            )
+            .col_expr(
+                shared_nodes::Column::UpdatedAt,
+                sea_query::Expr::value(released_at),
+            )
+            .filter(shared_nodes::Column::Id.is_in(nodes.iter().map(|n| n.id)))
+            .filter(shared_nodes::Column::ReleasedAt.is_null())
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+
+        Ok(nodes
+            .into_iter()
+            .filter(|n| n.released_at.is_none() && n.deactivated_at.is_none())
+            .collect())
+    }
+
+    async fn mark_deactivated(db: &Db, nodes: &[shared_nodes::Model]) -> Result<(), DbErr> {
+        if nodes.is_empty() {
+            return Ok(());
+        }
+
+        let now = chrono::Utc::now().fixed_offset();
+        shared_nodes::Entity::update_many()
+            .col_expr(
+                shared_nodes::Column::DeactivatedAt,
+                sea_query::Expr::value(now),
+            )
+            .col_expr(shared_nodes::Column::UpdatedAt, sea_query::Expr::value(now))
+            .filter(shared_nodes::Column::Id.is_in(nodes.iter().map(|n| n.id)))
+            .exec(db.orm_db())
+            .await?;
+        Ok(())
+    }
+
+    /// 彻底删除停用时间早于 `deactivated_before` 的节点及其全部历史数据，返回被删除的节点ID
+    pub async fn purge_deactivated_nodes(
+        db: &Db,
+        deactivated_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<i32>, DbErr> {
+        let node_ids: Vec<i32> = shared_nodes::Entity::find()
+            .select_only()
+            .column(shared_nodes::Column::Id)
+            .filter(shared_nodes::Column::DeactivatedAt.lt(deactivated_before.fixed_offset()))
+            .into_tuple()
+            .all(db.orm_db())
+            .await?;
+        if node_ids.is_empty() {
+            return Ok(node_ids);
+        }
+
+        // 外键级联只在开启了 foreign_keys 的连接上生效，这里显式删除子表数据
+        let txn = db.orm_db().begin().await?;
+        health_records::Entity::delete_many()
+            .filter(health_records::Column::NodeId.is_in(node_ids.clone()))
+            .exec(&txn)
+            .await?;
+        health_rollups::Entity::delete_many()
+            .filter(health_rollups::Column::NodeId.is_in(node_ids.clone()))
+            .exec(&txn)
+            .await?;
+        node_state_events::Entity::delete_many()
+            .filter(node_state_events::Column::NodeId.is_in(node_ids.clone()))
+            .exec(&txn)
+            .await?;
+        node_listeners::Entity::delete_many()
+            .filter(node_listeners::Column::NodeId.is_in(node_ids.clone()))
+            .exec(&txn)
+            .await?;
+        node_certificates::Entity::delete_many()
+            .filter(node_certificates::Column::NodeId.is_in(node_ids.clone()))
+            .exec(&txn)
+            .await?;
+        node_tags::Entity::delete_many()
+            .filter(node_tags::Column::NodeId.is_in(node_ids.clone()))
+            .exec(&txn)
+            .await?;
+        node_versions::Entity::delete_many()
+            .filter(node_versions::Column::NodeId.is_in(node_ids.clone()))
+            .exec(&txn)
+            .await?;
+        shared_nodes::Entity::delete_many()
+            .filter(shared_nodes::Column::Id.is_in(node_ids.clone()))
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+
+        Ok(node_ids)
+    }
 }
 
 /// 健康记录操作
@@ -154,9 +407,16 @@ impl HealthOperations {
         status: HealthStatus,
         response_time: Option<i32>,
         error_message: Option<String>,
+        error_kind: Option<ProbeErrorKind>,
+        quality: &LinkQuality,
     ) -> Result<health_records::Model, DbErr> {
-        let record =
+        let mut record =
             health_records::Model::new_active_model(node_id, status, response_time, error_message);
+        record.error_kind = Set(error_kind.map(|kind| kind.to_string()));
+        record.loss_rate = Set(quality.loss_rate);
+        record.jitter_us = Set(quality.jitter_us);
+        record.p50_latency = Set(quality.p50_latency);
+        record.p95_latency = Set(quality.p95_latency);
 
         let insert_result = health_records::Entity::insert(record)
             .exec(db.orm_db())
@@ -192,6 +452,20 @@ impl HealthOperations {
         query.all(db.orm_db()).await
     }
 
+    /// 获取节点在给定时间之后的健康记录（按时间升序）
+    pub async fn get_node_health_records_since(
+        db: &Db,
+        node_id: i32,
+        from: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<health_records::Model>, DbErr> {
+        health_records::Entity::find()
+            .filter(health_records::Column::NodeId.eq(node_id))
+            .filter(health_records::Column::CheckedAt.gte(from.fixed_offset()))
+            .order_by_asc(health_records::Column::CheckedAt)
+            .all(db.orm_db())
+            .await
+    }
+
     /// 获取节点最近的健康状态
     pub async fn get_latest_health_status(
         db: &Db,
@@ -353,114 +627,1362 @@ impl NodeOperations {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::Db;
+/// 健康记录按小时汇总操作
+pub struct RollupOperations;
 
-    #[tokio::test]
-    async fn test_node_operations() {
-        let db = Db::memory_db().await;
+impl RollupOperations {
+    const BUCKET_SECS: i64 = 3600;
+    /// 汇总时每次读取的原始记录时长（小时）
+    const ROLLUP_PAGE_HOURS: i64 = 24;
 
-        let req = CreateNodeRequest {
-            name: "Test Node".to_string(),
-            host: "test.example.com".to_string(),
-            port: 11010,
-            protocol: "tcp".to_string(),
-            description: Some("Test node".to_string()),
-            max_connections: 100,
-            allow_relay: false,
-            network_name: "test-network".to_string(),
-            network_secret: Some("test-secret".to_string()),
-            qq_number: Some("123456789".to_string()),
-            wechat: Some("test_wechat".to_string()),
-            mail: Some("test@example.com".to_string()),
-        };
+    /// 将时间截断到所在小时的起点
+    pub fn bucket_start(time: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::Utc> {
+        let ts = time.timestamp();
+        chrono::DateTime::from_timestamp(ts - ts.rem_euclid(Self::BUCKET_SECS), 0).unwrap_or(time)
+    }
 
-        // 测试创建节点
-        let node = NodeOperations::create_node(&db, req).await.unwrap();
+    /// 汇总所有已结束且尚未汇总的小时，返回新增的汇总条数
+    ///
+    /// 每个节点从最后一个已汇总的小时之后继续，已写入的小时不会被重新计算，
+    /// 因此原始记录被清理后不会覆盖已有的汇总。原始记录按 `ROLLUP_PAGE_HOURS`
+    /// 小时分页读取，积压很久的节点也不会一次载入全部记录。
+    pub async fn rollup_completed_hours(
+        db: &Db,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, DbErr> {
+        let current_bucket = Self::bucket_start(now).fixed_offset();
+        let page = chrono::Duration::seconds(Self::BUCKET_SECS * Self::ROLLUP_PAGE_HOURS);
+        let node_ids: Vec<i32> = shared_nodes::Entity::find()
+            .select_only()
+            .column(shared_nodes::Column::Id)
+            .into_tuple()
+            .all(db.orm_db())
+            .await?;
 
-        assert_eq!(node.name, "Test Node");
-        assert_eq!(node.host, "test.example.com");
-        assert_eq!(node.port, 11010);
-        assert!(node.is_active);
+        let mut created = 0;
+        for node_id in node_ids {
+            let last_rollup = health_rollups::Entity::find()
+                .filter(health_rollups::Column::NodeId.eq(node_id))
+                .order_by_desc(health_rollups::Column::BucketStart)
+                .one(db.orm_db())
+                .await?;
+            let mut cursor = last_rollup
+                .map(|last| last.bucket_start + chrono::Duration::seconds(Self::BUCKET_SECS));
 
-        // 测试获取节点
-        let found_node = NodeOperations::get_node_by_id(&db, node.id).await.unwrap();
-        assert!(found_node.is_some());
-        assert_eq!(found_node.unwrap().id, node.id);
+            loop {
+                // 从游标之后的第一条记录所在的小时开始取一页，跳过没有记录的时段
+                let mut next = health_records::Entity::find()
+                    .filter(health_records::Column::NodeId.eq(node_id))
+                    .filter(health_records::Column::CheckedAt.lt(current_bucket))
+                    .order_by_asc(health_records::Column::CheckedAt);
+                if let Some(cursor) = cursor {
+                    next = next.filter(health_records::Column::CheckedAt.gte(cursor));
+                }
+                let Some(first) = next.one(db.orm_db()).await? else {
+                    break;
+                };
 
-        // 测试获取所有节点
-        let all_nodes = NodeOperations::get_all_nodes(&db).await.unwrap();
-        assert_eq!(all_nodes.len(), 1);
+                let page_start = Self::bucket_start(first.checked_at.to_utc()).fixed_offset();
+                let page_end = (page_start + page).min(current_bucket);
+                let records = health_records::Entity::find()
+                    .filter(health_records::Column::NodeId.eq(node_id))
+                    .filter(health_records::Column::CheckedAt.gte(page_start))
+                    .filter(health_records::Column::CheckedAt.lt(page_end))
+                    .order_by_asc(health_records::Column::CheckedAt)
+                    .all(db.orm_db())
+                    .await?;
+                cursor = Some(page_end);
 
-        // 测试节点存在性检查
-        let exists = NodeOperations::node_exists(&db, "test.example.com", 11010, "tcp")
-            .await
-            .unwrap();
-        assert!(exists);
+                let rollups = Self::build_rollups(node_id, &records);
+                if rollups.is_empty() {
+                    continue;
+                }
+                // 只统计实际写入的行，与已有汇总冲突而跳过的小时不计入
+                created += health_rollups::Entity::insert_many(rollups)
+                    .on_conflict(
+                        sea_query::OnConflict::columns([
+                            health_rollups::Column::NodeId,
+                            health_rollups::Column::BucketStart,
+                        ])
+                        .do_nothing()
+                        .to_owned(),
+                    )
+                    .exec_without_returning(db.orm_db())
+                    .await?;
+            }
+        }
 
-        let not_exists = NodeOperations::node_exists(&db, "nonexistent.com", 8080, "tcp")
-            .await
-            .unwrap();
-        assert!(!not_exists);
+        Ok(created)
     }
 
-    #[tokio::test]
-    async fn test_health_operations() {
-        let db = Db::memory_db().await;
+    /// 将按时间升序排列的原始记录分组为每小时一条汇总
+    fn build_rollups(
+        node_id: i32,
+        records: &[health_records::Model],
+    ) -> Vec<health_rollups::ActiveModel> {
+        let now = chrono::Utc::now().fixed_offset();
+        let mut rollups = Vec::new();
 
-        let req = CreateNodeRequest {
-            name: "Test Node".to_string(),
-            host: "test.example.com".to_string(),
-            port: 11010,
-            protocol: "tcp".to_string(),
-            description: Some("Test node".to_string()),
-            max_connections: 100,
-            allow_relay: false,
-            network_name: "test-network".to_string(),
-            network_secret: Some("test-secret".to_string()),
-            qq_number: Some("123456789".to_string()),
-            wechat: Some("test_wechat".to_string()),
-            mail: Some("test@example.com".to_string()),
-        };
+        for bucket in records.chunk_by(|a, b| {
+            Self::bucket_start(a.checked_at.to_utc()) == Self::bucket_start(b.checked_at.to_utc())
+        }) {
+            let bucket_start = Self::bucket_start(bucket[0].checked_at.to_utc()).fixed_offset();
+            let success_count = bucket.iter().filter(|r| r.is_healthy()).count();
 
-        // 创建测试节点
-        let node = NodeOperations::create_node(&db, req).await.unwrap();
-        // 测试创建健康记录
-        let record = HealthOperations::create_health_record(
-            &db,
-            node.id,
-            HealthStatus::Healthy,
-            Some(100),
-            None,
-        )
-        .await
-        .unwrap();
+            let mut latencies: Vec<i32> = bucket
+                .iter()
+                .filter(|r| r.is_healthy() && r.response_time > 0)
+                .map(|r| r.response_time)
+                .collect();
+            latencies.sort_unstable();
 
-        assert_eq!(record.node_id, node.id);
-        assert!(record.is_healthy());
-        assert_eq!(record.response_time, 100);
+            let (min_latency, avg_latency, p95_latency) = if latencies.is_empty() {
+                (None, None, None)
+            } else {
+                let sum: i64 = latencies.iter().map(|&l| l as i64).sum();
+                let p95_index = (latencies.len() * 95).div_ceil(100).saturating_sub(1);
+                (
+                    Some(latencies[0]),
+                    Some(sum as f64 / latencies.len() as f64),
+                    Some(latencies[p95_index]),
+                )
+            };
 
-        // 测试获取健康记录
-        let records = HealthOperations::get_node_health_records(&db, node.id, None, None)
+            rollups.push(health_rollups::ActiveModel {
+                node_id: Set(node_id),
+                bucket_start: Set(bucket_start),
+                check_count: Set(bucket.len() as i32),
+                success_count: Set(success_count as i32),
+                min_latency: Set(min_latency),
+                avg_latency: Set(avg_latency),
+                p95_latency: Set(p95_latency),
+                created_at: Set(now),
+                ..Default::default()
+            });
+        }
+
+        rollups
+    }
+
+    /// 获取节点在给定时间之后的汇总记录（按时间升序）
+    pub async fn get_node_rollups(
+        db: &Db,
+        node_id: i32,
+        from: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<health_rollups::Model>, DbErr> {
+        health_rollups::Entity::find()
+            .filter(health_rollups::Column::NodeId.eq(node_id))
+            .filter(health_rollups::Column::BucketStart.gte(from.fixed_offset()))
+            .order_by_asc(health_rollups::Column::BucketStart)
+            .all(db.orm_db())
             .await
-            .unwrap();
-        assert_eq!(records.len(), 1);
+    }
 
-        // 测试获取最新状态
-        let latest = HealthOperations::get_latest_health_status(&db, node.id)
+    /// 获取所有节点在给定时间之后的汇总记录（按节点、时间升序）
+    pub async fn get_rollups_since(
+        db: &Db,
+        from: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<health_rollups::Model>, DbErr> {
+        health_rollups::Entity::find()
+            .filter(health_rollups::Column::BucketStart.gte(from.fixed_offset()))
+            .order_by_asc(health_rollups::Column::NodeId)
+            .order_by_asc(health_rollups::Column::BucketStart)
+            .all(db.orm_db())
             .await
-            .unwrap();
-        assert!(latest.is_some());
-        assert_eq!(latest.unwrap().id, record.id);
+    }
 
-        // 测试健康统计
-        let stats = HealthOperations::get_health_stats(&db, node.id, 24)
+    /// 清理旧的汇总记录
+    pub async fn cleanup_old_rollups(db: &Db, days: i64) -> Result<u64, DbErr> {
+        let cutoff = chrono::Utc::now().fixed_offset() - chrono::Duration::days(days);
+
+        let result = health_rollups::Entity::delete_many()
+            .filter(health_rollups::Column::BucketStart.lt(cutoff))
+            .exec(db.orm_db())
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+}
+
+/// 离线心跳暂存操作
+pub struct OutboxOperations;
+
+impl OutboxOperations {
+    /// 暂存未送达的心跳，并按上限裁剪最旧的记录
+    pub async fn enqueue(
+        db: &Db,
+        entries: Vec<heartbeat_outbox::ActiveModel>,
+        max_entries: u64,
+    ) -> Result<u64, DbErr> {
+        if entries.is_empty() {
+            return Ok(0);
+        }
+        heartbeat_outbox::Entity::insert_many(entries)
+            .exec(db.orm_db())
+            .await?;
+        Self::trim_to_size(db, max_entries).await
+    }
+
+    /// 按检查时间顺序获取最早的心跳
+    pub async fn fetch_oldest(
+        db: &Db,
+        limit: u64,
+    ) -> Result<Vec<heartbeat_outbox::Model>, DbErr> {
+        Self::fetch_after(db, None, limit).await
+    }
+
+    /// 按检查时间顺序获取排在 `after` 之后的心跳，用于分页遍历整个发件箱
+    pub async fn fetch_after(
+        db: &Db,
+        after: Option<&heartbeat_outbox::Model>,
+        limit: u64,
+    ) -> Result<Vec<heartbeat_outbox::Model>, DbErr> {
+        let mut query = heartbeat_outbox::Entity::find();
+        if let Some(after) = after {
+            query = query.filter(
+                Condition::any()
+                    .add(heartbeat_outbox::Column::CheckedAt.gt(after.checked_at))
+                    .add(
+                        Condition::all()
+                            .add(heartbeat_outbox::Column::CheckedAt.eq(after.checked_at))
+                            .add(heartbeat_outbox::Column::Id.gt(after.id)),
+                    ),
+            );
+        }
+        query
+            .order_by_asc(heartbeat_outbox::Column::CheckedAt)
+            .order_by_asc(heartbeat_outbox::Column::Id)
+            .limit(limit)
+            .all(db.orm_db())
             .await
-            .unwrap();
-        assert_eq!(stats.total_checks, 1);
-        assert_eq!(stats.healthy_count, 1);
-        assert_eq!(stats.health_percentage, 100.0);
+    }
+
+    /// 暂存的心跳数量
+    pub async fn count(db: &Db) -> Result<u64, DbErr> {
+        heartbeat_outbox::Entity::find().count(db.orm_db()).await
+    }
+
+    /// 删除已送达的心跳
+    pub async fn delete_by_ids(db: &Db, ids: Vec<i32>) -> Result<u64, DbErr> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let result = heartbeat_outbox::Entity::delete_many()
+            .filter(heartbeat_outbox::Column::Id.is_in(ids))
+            .exec(db.orm_db())
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// 记录一次投递失败；超过最大尝试次数的心跳直接丢弃，此时返回 true
+    pub async fn record_failure(
+        db: &Db,
+        entry: heartbeat_outbox::Model,
+        error: String,
+        max_attempts: i32,
+    ) -> Result<bool, DbErr> {
+        if entry.attempts + 1 >= max_attempts {
+            heartbeat_outbox::Entity::delete_by_id(entry.id)
+                .exec(db.orm_db())
+                .await?;
+            return Ok(true);
+        }
+
+        let attempts = entry.attempts + 1;
+        let mut entry = entry.into_active_model();
+        entry.attempts = Set(attempts);
+        entry.last_error = Set(Some(error));
+        entry.update(db.orm_db()).await?;
+        Ok(false)
+    }
+
+    /// 删除早于给定时长的心跳
+    pub async fn prune_older_than(db: &Db, max_age: chrono::Duration) -> Result<u64, DbErr> {
+        let cutoff = chrono::Utc::now().fixed_offset() - max_age;
+        let result = heartbeat_outbox::Entity::delete_many()
+            .filter(heartbeat_outbox::Column::CheckedAt.lt(cutoff))
+            .exec(db.orm_db())
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// 仅保留检查时间最新的 max_entries 条心跳，与重放顺序一致
+    pub async fn trim_to_size(db: &Db, max_entries: u64) -> Result<u64, DbErr> {
+        let total = Self::count(db).await?;
+        if total <= max_entries {
+            return Ok(0);
+        }
+
+        // 第一个需要保留的记录
+        let keep = heartbeat_outbox::Entity::find()
+            .order_by_desc(heartbeat_outbox::Column::CheckedAt)
+            .order_by_desc(heartbeat_outbox::Column::Id)
+            .offset(max_entries.saturating_sub(1))
+            .one(db.orm_db())
+            .await?;
+
+        let mut query = heartbeat_outbox::Entity::delete_many();
+        if let Some(keep) = keep.filter(|_| max_entries > 0) {
+            query = query.filter(
+                Condition::any()
+                    .add(heartbeat_outbox::Column::CheckedAt.lt(keep.checked_at))
+                    .add(
+                        Condition::all()
+                            .add(heartbeat_outbox::Column::CheckedAt.eq(keep.checked_at))
+                            .add(heartbeat_outbox::Column::Id.lt(keep.id)),
+                    ),
+            );
+        }
+        let result = query.exec(db.orm_db()).await?;
+        Ok(result.rows_affected)
+    }
+}
+
+/// 节点状态转换事件操作
+pub struct StateEventOperations;
+
+impl StateEventOperations {
+    /// 记录一次状态转换
+    pub async fn record_transition(
+        db: &Db,
+        node_id: i32,
+        from: NodeState,
+        to: NodeState,
+        reason: Option<String>,
+        at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<node_state_events::Model, DbErr> {
+        let event = node_state_events::ActiveModel {
+            node_id: Set(node_id),
+            from_state: Set(from.to_string()),
+            to_state: Set(to.to_string()),
+            reason: Set(reason),
+            created_at: Set(at.fixed_offset()),
+            ..Default::default()
+        };
+        event.insert(db.orm_db()).await
+    }
+
+    /// 获取节点最近一次状态转换
+    pub async fn get_latest_event(
+        db: &Db,
+        node_id: i32,
+    ) -> Result<Option<node_state_events::Model>, DbErr> {
+        node_state_events::Entity::find()
+            .filter(node_state_events::Column::NodeId.eq(node_id))
+            .order_by_desc(node_state_events::Column::CreatedAt)
+            .order_by_desc(node_state_events::Column::Id)
+            .one(db.orm_db())
+            .await
+    }
+}
+
+/// 节点监听地址操作
+pub struct ListenerOperations;
+
+impl ListenerOperations {
+    /// 设置节点的监听地址（替换为给定集合），协议取自地址的 scheme，无法识别的地址被忽略
+    pub async fn set_node_listeners(db: &Db, node_id: i32, urls: Vec<String>) -> Result<(), DbErr> {
+        let mut wanted: HashMap<String, String> = HashMap::new();
+        for url in urls {
+            let url = url.trim();
+            if let Some((scheme, rest)) = url.split_once("://") {
+                if !scheme.is_empty() && !rest.is_empty() {
+                    wanted.insert(url.to_string(), scheme.to_lowercase());
+                }
+            }
+        }
+
+        let existing = Self::get_node_listeners(db, node_id).await?;
+        let existing_urls: HashSet<String> = existing.iter().map(|m| m.url.clone()).collect();
+
+        let to_delete: Vec<i32> = existing
+            .iter()
+            .filter(|m| !wanted.contains_key(&m.url))
+            .map(|m| m.id)
+            .collect();
+        if !to_delete.is_empty() {
+            node_listeners::Entity::delete_many()
+                .filter(node_listeners::Column::Id.is_in(to_delete))
+                .exec(db.orm_db())
+                .await?;
+        }
+
+        let now = chrono::Utc::now().fixed_offset();
+        let to_insert: Vec<node_listeners::ActiveModel> = wanted
+            .into_iter()
+            .filter(|(url, _)| !existing_urls.contains(url))
+            .map(|(url, protocol)| node_listeners::ActiveModel {
+                node_id: Set(node_id),
+                url: Set(url),
+                protocol: Set(protocol),
+                created_at: Set(now),
+                ..Default::default()
+            })
+            .collect();
+        if !to_insert.is_empty() {
+            node_listeners::Entity::insert_many(to_insert)
+                .exec(db.orm_db())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// 获取节点的全部监听地址
+    pub async fn get_node_listeners(
+        db: &Db,
+        node_id: i32,
+    ) -> Result<Vec<node_listeners::Model>, DbErr> {
+        node_listeners::Entity::find()
+            .filter(node_listeners::Column::NodeId.eq(node_id))
+            .order_by_asc(node_listeners::Column::Id)
+            .all(db.orm_db())
+            .await
+    }
+
+    /// 获取所有节点的监听地址，按节点分组
+    pub async fn get_listeners_map(
+        db: &Db,
+    ) -> Result<HashMap<i32, Vec<node_listeners::Model>>, DbErr> {
+        let listeners = node_listeners::Entity::find()
+            .order_by_asc(node_listeners::Column::Id)
+            .all(db.orm_db())
+            .await?;
+        let mut map: HashMap<i32, Vec<node_listeners::Model>> = HashMap::new();
+        for listener in listeners {
+            map.entry(listener.node_id).or_default().push(listener);
+        }
+        Ok(map)
+    }
+
+    /// 记录监听地址最近一次检查结果
+    pub async fn update_listener_status(
+        db: &Db,
+        listener_id: i32,
+        status: HealthStatus,
+        response_time: Option<i32>,
+        error_message: Option<String>,
+        error_kind: Option<ProbeErrorKind>,
+    ) -> Result<(), DbErr> {
+        let listener = node_listeners::ActiveModel {
+            id: Set(listener_id),
+            last_status: Set(Some(status.to_string())),
+            last_response_time: Set(response_time),
+            last_error_message: Set(error_message),
+            last_error_kind: Set(error_kind.map(|kind| kind.to_string())),
+            last_checked_at: Set(Some(chrono::Utc::now().fixed_offset())),
+            ..Default::default()
+        };
+        node_listeners::Entity::update(listener)
+            .exec(db.orm_db())
+            .await?;
+        Ok(())
+    }
+}
+
+/// 证书检查结果操作
+pub struct CertificateOperations;
+
+impl CertificateOperations {
+    /// 获取节点各地址最近一次的证书检查结果
+    pub async fn get_node_certificates(
+        db: &Db,
+        node_id: i32,
+    ) -> Result<Vec<node_certificates::Model>, DbErr> {
+        node_certificates::Entity::find()
+            .filter(node_certificates::Column::NodeId.eq(node_id))
+            .order_by_asc(node_certificates::Column::Url)
+            .all(db.orm_db())
+            .await
+    }
+
+    /// 获取所有节点的证书检查结果，按节点分组
+    pub async fn get_certificates_map(
+        db: &Db,
+    ) -> Result<HashMap<i32, Vec<node_certificates::Model>>, DbErr> {
+        let certificates = node_certificates::Entity::find()
+            .order_by_asc(node_certificates::Column::Url)
+            .all(db.orm_db())
+            .await?;
+        let mut map: HashMap<i32, Vec<node_certificates::Model>> = HashMap::new();
+        for certificate in certificates {
+            map.entry(certificate.node_id).or_default().push(certificate);
+        }
+        Ok(map)
+    }
+
+    /// 记录一个地址的证书检查结果，`certificate` 为握手失败的原因时只更新状态和错误
+    pub async fn record_check(
+        db: &Db,
+        node_id: i32,
+        url: &str,
+        status: CertStatus,
+        certificate: Result<&CertificateInfo, String>,
+    ) -> Result<(), DbErr> {
+        let existing = node_certificates::Entity::find()
+            .filter(node_certificates::Column::NodeId.eq(node_id))
+            .filter(node_certificates::Column::Url.eq(url))
+            .one(db.orm_db())
+            .await?;
+
+        let now = chrono::Utc::now().fixed_offset();
+        let mut model = match existing {
+            Some(existing) => existing.into_active_model(),
+            None => node_certificates::ActiveModel {
+                node_id: Set(node_id),
+                url: Set(url.to_string()),
+                created_at: Set(now),
+                ..Default::default()
+            },
+        };
+        model.status = Set(status.to_string());
+        model.checked_at = Set(now);
+        match certificate {
+            Ok(cert) => {
+                model.subject = Set(Some(cert.subject.clone()));
+                model.issuer = Set(Some(cert.issuer.clone()));
+                model.not_before = Set(Some(cert.not_before.fixed_offset()));
+                model.not_after = Set(Some(cert.not_after.fixed_offset()));
+                model.self_signed = Set(Some(cert.self_signed));
+                model.hostname_match = Set(Some(cert.hostname_match));
+                model.trusted = Set(Some(cert.trusted));
+                model.last_error_message = Set(None);
+            }
+            // 保留上一次拿到的证书信息，便于排查
+            Err(error) => model.last_error_message = Set(Some(error)),
+        }
+        model.save(db.orm_db()).await?;
+        Ok(())
+    }
+
+    /// 删除节点不再使用的地址的检查结果
+    pub async fn retain_node_certificates(
+        db: &Db,
+        node_id: i32,
+        urls: &[String],
+    ) -> Result<u64, DbErr> {
+        let result = node_certificates::Entity::delete_many()
+            .filter(node_certificates::Column::NodeId.eq(node_id))
+            .filter(node_certificates::Column::Url.is_not_in(urls.iter().cloned()))
+            .exec(db.orm_db())
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+/// 节点版本历史操作
+pub struct VersionOperations;
+
+impl VersionOperations {
+    /// 记录一次检查到的版本：与最近一条历史相同时延长其 `last_seen_at`，否则新增一条
+    ///
+    /// 每条历史是节点连续运行同一版本的时间段，升级后回滚到旧版本同样新增一条。
+    pub async fn record_seen<C: ConnectionTrait>(
+        conn: &C,
+        node_id: i32,
+        version: &str,
+        seen_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), DbErr> {
+        let latest = node_versions::Entity::find()
+            .filter(node_versions::Column::NodeId.eq(node_id))
+            .order_by_desc(node_versions::Column::FirstSeenAt)
+            .order_by_desc(node_versions::Column::Id)
+            .one(conn)
+            .await?;
+
+        let seen_at = seen_at.fixed_offset();
+        match latest {
+            Some(latest) if latest.version == version => {
+                let mut latest = latest.into_active_model();
+                latest.last_seen_at = Set(seen_at);
+                latest.update(conn).await?;
+            }
+            _ => {
+                node_versions::ActiveModel {
+                    node_id: Set(node_id),
+                    version: Set(version.to_string()),
+                    first_seen_at: Set(seen_at),
+                    last_seen_at: Set(seen_at),
+                    ..Default::default()
+                }
+                .insert(conn)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 获取节点的版本历史，最近的在前
+    pub async fn get_node_versions(
+        db: &Db,
+        node_id: i32,
+    ) -> Result<Vec<node_versions::Model>, DbErr> {
+        node_versions::Entity::find()
+            .filter(node_versions::Column::NodeId.eq(node_id))
+            .order_by_desc(node_versions::Column::FirstSeenAt)
+            .order_by_desc(node_versions::Column::Id)
+            .all(db.orm_db())
+            .await
+    }
+}
+
+/// 探测节点身份操作
+pub struct ProbeIdentityOperations;
+
+impl ProbeIdentityOperations {
+    /// 获取在指定后端注册的身份
+    pub async fn get_by_backend(
+        db: &Db,
+        backend_url: &str,
+    ) -> Result<Option<probe_identity::Model>, DbErr> {
+        probe_identity::Entity::find()
+            .filter(probe_identity::Column::BackendUrl.eq(backend_url))
+            .one(db.orm_db())
+            .await
+    }
+
+    /// 保存注册结果，同一后端已有身份时覆盖
+    pub async fn save(
+        db: &Db,
+        backend_url: &str,
+        probe_id: String,
+        name: String,
+        region: Option<String>,
+        secret: String,
+    ) -> Result<probe_identity::Model, DbErr> {
+        let now = chrono::Utc::now().fixed_offset();
+        match Self::get_by_backend(db, backend_url).await? {
+            Some(existing) => {
+                let mut identity = existing.into_active_model();
+                identity.probe_id = Set(probe_id);
+                identity.name = Set(name);
+                identity.region = Set(region);
+                identity.secret = Set(secret);
+                identity.registered_at = Set(now);
+                identity.update(db.orm_db()).await
+            }
+            None => {
+                probe_identity::ActiveModel {
+                    backend_url: Set(backend_url.to_string()),
+                    probe_id: Set(probe_id),
+                    name: Set(name),
+                    region: Set(region),
+                    secret: Set(secret),
+                    created_at: Set(now),
+                    registered_at: Set(now),
+                    ..Default::default()
+                }
+                .insert(db.orm_db())
+                .await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::Db;
+
+    /// 测试用的节点请求，主机名由名称生成
+    pub(crate) fn test_node(name: &str) -> CreateNodeRequest {
+        CreateNodeRequest {
+            name: name.to_string(),
+            host: format!("{}.example.com", name.to_lowercase().replace(' ', "-")),
+            port: 11010,
+            protocol: "tcp".to_string(),
+            description: None,
+            max_connections: 100,
+            allow_relay: true,
+            network_name: "test-network".to_string(),
+            network_secret: None,
+            qq_number: None,
+            wechat: None,
+            mail: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_node_operations() {
+        let db = Db::memory_db().await;
+
+        let req = CreateNodeRequest {
+            name: "Test Node".to_string(),
+            host: "test.example.com".to_string(),
+            port: 11010,
+            protocol: "tcp".to_string(),
+            description: Some("Test node".to_string()),
+            max_connections: 100,
+            allow_relay: false,
+            network_name: "test-network".to_string(),
+            network_secret: Some("test-secret".to_string()),
+            qq_number: Some("123456789".to_string()),
+            wechat: Some("test_wechat".to_string()),
+            mail: Some("test@example.com".to_string()),
+        };
+
+        // 测试创建节点
+        let node = NodeOperations::create_node(&db, req).await.unwrap();
+
+        assert_eq!(node.name, "Test Node");
+        assert_eq!(node.host, "test.example.com");
+        assert_eq!(node.port, 11010);
+        assert!(node.is_active);
+
+        // 测试获取节点
+        let found_node = NodeOperations::get_node_by_id(&db, node.id).await.unwrap();
+        assert!(found_node.is_some());
+        assert_eq!(found_node.unwrap().id, node.id);
+
+        // 测试获取所有节点
+        let all_nodes = NodeOperations::get_all_nodes(&db).await.unwrap();
+        assert_eq!(all_nodes.len(), 1);
+
+        // 测试节点存在性检查
+        let exists = NodeOperations::node_exists(&db, "test.example.com", 11010, "tcp")
+            .await
+            .unwrap();
+        assert!(exists);
+
+        let not_exists = NodeOperations::node_exists(&db, "nonexistent.com", 8080, "tcp")
+            .await
+            .unwrap();
+        assert!(!not_exists);
+    }
+
+    #[tokio::test]
+    async fn test_health_operations() {
+        let db = Db::memory_db().await;
+
+        let req = CreateNodeRequest {
+            name: "Test Node".to_string(),
+            host: "test.example.com".to_string(),
+            port: 11010,
+            protocol: "tcp".to_string(),
+            description: Some("Test node".to_string()),
+            max_connections: 100,
+            allow_relay: false,
+            network_name: "test-network".to_string(),
+            network_secret: Some("test-secret".to_string()),
+            qq_number: Some("123456789".to_string()),
+            wechat: Some("test_wechat".to_string()),
+            mail: Some("test@example.com".to_string()),
+        };
+
+        // 创建测试节点
+        let node = NodeOperations::create_node(&db, req).await.unwrap();
+        // 测试创建健康记录
+        let quality = LinkQuality {
+            loss_rate: Some(0.05),
+            jitter_us: Some(12.5),
+            p50_latency: Some(100),
+            p95_latency: Some(180),
+        };
+        let record = HealthOperations::create_health_record(
+            &db,
+            node.id,
+            HealthStatus::Healthy,
+            Some(100),
+            None,
+            None,
+            &quality,
+        )
+        .await
+        .unwrap();
+        assert_eq!(record.loss_rate, Some(0.05));
+        assert_eq!(record.p95_latency, Some(180));
+
+        assert_eq!(record.node_id, node.id);
+        assert!(record.is_healthy());
+        assert_eq!(record.response_time, 100);
+
+        // 测试获取健康记录
+        let records = HealthOperations::get_node_health_records(&db, node.id, None, None)
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+
+        // 测试获取最新状态
+        let latest = HealthOperations::get_latest_health_status(&db, node.id)
+            .await
+            .unwrap();
+        assert!(latest.is_some());
+        assert_eq!(latest.unwrap().id, record.id);
+
+        // 测试健康统计
+        let stats = HealthOperations::get_health_stats(&db, node.id, 24)
+            .await
+            .unwrap();
+        assert_eq!(stats.total_checks, 1);
+        assert_eq!(stats.healthy_count, 1);
+        assert_eq!(stats.health_percentage, 100.0);
+
+        // 失败记录保存错误分类
+        let record = HealthOperations::create_health_record(
+            &db,
+            node.id,
+            HealthStatus::Unhealthy,
+            None,
+            Some("connection refused".to_string()),
+            Some(ProbeErrorKind::ConnectRefused),
+            &LinkQuality::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(record.error_kind.as_deref(), Some("connect_refused"));
+    }
+
+    #[tokio::test]
+    async fn test_backend_peer_binding() {
+        let db = Db::memory_db().await;
+
+        let manual = NodeOperations::create_node(&db, test_node("Manual"))
+            .await
+            .unwrap();
+        assert_eq!(manual.backend_peer_id, None);
+        assert_eq!(manual.source, NODE_SOURCE_MANUAL);
+        assert!(!manual.is_approved);
+
+        let req = CreateNodeRequest {
+            description: Some("edited by operator".to_string()),
+            ..test_node("Backend")
+        };
+        let node = NodeOperations::create_backend_node(&db, req, 42)
+            .await
+            .unwrap();
+        assert_eq!(node.backend_peer_id, Some(42));
+        assert_eq!(node.source, NODE_SOURCE_BACKEND);
+        assert!(node.is_approved);
+        let synced_at = node.last_synced_at.unwrap();
+
+        // 只更新同步时间，其他字段和更新时间不变
+        let later = chrono::Utc::now() + chrono::Duration::minutes(1);
+        NodeOperations::mark_synced(&db, vec![node.id], later)
+            .await
+            .unwrap();
+        let synced = NodeOperations::get_node_by_id(&db, node.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(synced.last_synced_at.unwrap() > synced_at);
+        assert_eq!(synced.updated_at, node.updated_at);
+
+        // 查找不依赖 description 内容
+        let found = NodeOperations::get_node_by_backend_peer_id(&db, 42)
+            .await
+            .unwrap();
+        assert_eq!(found.map(|n| n.id), Some(node.id));
+
+        let map = NodeOperations::get_backend_nodes_map(&db).await.unwrap();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&42).map(|n| n.id), Some(node.id));
+    }
+
+    #[tokio::test]
+    async fn test_deactivate_and_purge_missing_nodes() {
+        let db = Db::memory_db().await;
+
+        let manual = NodeOperations::create_node(&db, test_node("manual"))
+            .await
+            .unwrap();
+        let backend = NodeOperations::create_backend_node(&db, test_node("backend"), 7)
+            .await
+            .unwrap();
+        HealthOperations::create_health_record(
+            &db,
+            backend.id,
+            HealthStatus::Healthy,
+            Some(100),
+            None,
+            None,
+            &LinkQuality::default(),
+        )
+        .await
+        .unwrap();
+
+        // 宽限期内同步过的节点不会被停用
+        let an_hour_ago = chrono::Utc::now() - chrono::Duration::hours(1);
+        let deactivated = NodeOperations::deactivate_missing_backend_nodes(&db, an_hour_ago)
+            .await
+            .unwrap();
+        assert!(deactivated.is_empty());
+
+        let later = chrono::Utc::now() + chrono::Duration::seconds(1);
+        let deactivated = NodeOperations::deactivate_missing_backend_nodes(&db, later)
+            .await
+            .unwrap();
+        assert_eq!(
+            deactivated.iter().map(|n| n.id).collect::<Vec<_>>(),
+            vec![backend.id]
+        );
+        let monitored = NodeOperations::get_monitored_nodes(&db).await.unwrap();
+        assert_eq!(
+            monitored.iter().map(|n| n.id).collect::<Vec<_>>(),
+            vec![manual.id]
+        );
+
+        // 已停用的节点不会重复停用，历史数据仍然保留
+        assert!(NodeOperations::deactivate_missing_backend_nodes(&db, later)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(NodeOperations::purge_deactivated_nodes(&db, an_hour_ago)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let purged = NodeOperations::purge_deactivated_nodes(&db, later)
+            .await
+            .unwrap();
+        assert_eq!(purged, vec![backend.id]);
+        assert!(NodeOperations::get_node_by_id(&db, backend.id)
+            .await
+            .unwrap()
+            .is_none());
+        let records = HealthOperations::get_node_health_records(&db, backend.id, None, None)
+            .await
+            .unwrap();
+        assert!(records.is_empty());
+        assert!(NodeOperations::get_node_by_id(&db, manual.id)
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_release_backend_peers() {
+        let db = Db::memory_db().await;
+
+        let released = NodeOperations::create_backend_node(&db, test_node("released"), 7)
+            .await
+            .unwrap();
+        let missing = NodeOperations::create_backend_node(&db, test_node("missing"), 8)
+            .await
+            .unwrap();
+        let kept = NodeOperations::create_backend_node(&db, test_node("kept"), 9)
+            .await
+            .unwrap();
+
+        // 消失过的节点又出现在列表中，但分配给了其他探测节点
+        let later = chrono::Utc::now() + chrono::Duration::seconds(1);
+        NodeOperations::mark_synced(&db, vec![released.id, kept.id], later)
+            .await
+            .unwrap();
+        let deactivated = NodeOperations::deactivate_missing_backend_nodes(&db, later)
+            .await
+            .unwrap();
+        assert_eq!(
+            deactivated.iter().map(|n| n.id).collect::<Vec<_>>(),
+            vec![missing.id]
+        );
+
+        let now = later + chrono::Duration::seconds(1);
+        let newly_released = NodeOperations::release_backend_peers(&db, vec![7, 8], now)
+            .await
+            .unwrap();
+        assert_eq!(
+            newly_released.iter().map(|n| n.id).collect::<Vec<_>>(),
+            vec![released.id]
+        );
+        let monitored = NodeOperations::get_monitored_nodes(&db).await.unwrap();
+        assert_eq!(
+            monitored.iter().map(|n| n.id).collect::<Vec<_>>(),
+            vec![kept.id]
+        );
+
+        // 释放的节点仍在后端列表中，不会被当作消失的节点停用或清理
+        let deactivated = NodeOperations::deactivate_missing_backend_nodes(&db, now)
+            .await
+            .unwrap();
+        assert_eq!(
+            deactivated.iter().map(|n| n.id).collect::<Vec<_>>(),
+            vec![kept.id]
+        );
+        let far_future = now + chrono::Duration::days(365);
+        let purged = NodeOperations::purge_deactivated_nodes(&db, far_future)
+            .await
+            .unwrap();
+        assert_eq!(purged, vec![kept.id]);
+
+        // 已释放的节点不会重复返回
+        assert!(NodeOperations::release_backend_peers(&db, vec![7, 8], now)
+            .await
+            .unwrap()
+            .is_empty());
+        let node = NodeOperations::get_node_by_id(&db, missing.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(node.released_at.is_some());
+        assert!(node.deactivated_at.is_none());
+    }
+
+    fn outbox_entry(backend_peer_id: i32, minutes_ago: i64) -> heartbeat_outbox::ActiveModel {
+        let now = chrono::Utc::now().fixed_offset();
+        heartbeat_outbox::ActiveModel {
+            backend_peer_id: Set(backend_peer_id),
+            status: Set("online".to_string()),
+            peer: Set(0),
+            latency_ms: Set(10),
+            checked_at: Set(now - chrono::Duration::minutes(minutes_ago)),
+            attempts: Set(0),
+            last_error: Set(None),
+            created_at: Set(now),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_outbox_operations() {
+        let db = Db::memory_db().await;
+
+        // 按检查时间顺序取出，而不是插入顺序
+        OutboxOperations::enqueue(&db, vec![outbox_entry(1, 5), outbox_entry(2, 10)], 100)
+            .await
+            .unwrap();
+        let oldest = OutboxOperations::fetch_oldest(&db, 10).await.unwrap();
+        assert_eq!(
+            oldest.iter().map(|e| e.backend_peer_id).collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+
+        // 超过上限时丢弃检查时间最早的记录，而不是最早插入的
+        let trimmed = OutboxOperations::enqueue(&db, vec![outbox_entry(3, 1)], 2)
+            .await
+            .unwrap();
+        assert_eq!(trimmed, 1);
+        let remaining = OutboxOperations::fetch_oldest(&db, 10).await.unwrap();
+        assert_eq!(
+            remaining
+                .iter()
+                .map(|e| e.backend_peer_id)
+                .collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+
+        // 分页从上一页最后一条之后继续
+        let next = OutboxOperations::fetch_after(&db, Some(&remaining[0]), 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            next.iter().map(|e| e.backend_peer_id).collect::<Vec<_>>(),
+            vec![3]
+        );
+
+        // 超过最大尝试次数后丢弃
+        let entry = OutboxOperations::fetch_oldest(&db, 1).await.unwrap().remove(0);
+        let dropped = OutboxOperations::record_failure(&db, entry.clone(), "boom".to_string(), 2)
+            .await
+            .unwrap();
+        assert!(!dropped);
+        let entry = OutboxOperations::fetch_oldest(&db, 1).await.unwrap().remove(0);
+        assert_eq!(entry.attempts, 1);
+        assert_eq!(entry.last_error, Some("boom".to_string()));
+        let dropped = OutboxOperations::record_failure(&db, entry, "boom".to_string(), 2)
+            .await
+            .unwrap();
+        assert!(dropped);
+        assert_eq!(OutboxOperations::count(&db).await.unwrap(), 1);
+
+        // 过期记录被清理
+        let pruned = OutboxOperations::prune_older_than(&db, chrono::Duration::seconds(30))
+            .await
+            .unwrap();
+        assert_eq!(pruned, 1);
+        assert_eq!(OutboxOperations::count(&db).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_rollup_completed_hours() {
+        let db = Db::memory_db().await;
+
+        let node = NodeOperations::create_node(&db, test_node("Rollup"))
+            .await
+            .unwrap();
+
+        let now = chrono::Utc::now();
+        let prev_hour = RollupOperations::bucket_start(now) - chrono::Duration::hours(1);
+        let samples = [
+            (HealthStatus::Healthy, Some(100)),
+            (HealthStatus::Healthy, Some(300)),
+            (HealthStatus::Healthy, Some(200)),
+            (HealthStatus::Unhealthy, None),
+        ];
+        for (i, (status, latency)) in samples.into_iter().enumerate() {
+            let mut record =
+                health_records::Model::new_active_model(node.id, status, latency, None);
+            record.checked_at =
+                Set((prev_hour + chrono::Duration::minutes(i as i64 * 10)).fixed_offset());
+            health_records::Entity::insert(record)
+                .exec(db.orm_db())
+                .await
+                .unwrap();
+        }
+        // 当前小时尚未结束，不应被汇总
+        HealthOperations::create_health_record(
+            &db,
+            node.id,
+            HealthStatus::Healthy,
+            Some(1),
+            None,
+            None,
+            &LinkQuality::default(),
+        )
+        .await
+        .unwrap();
+
+        let created = RollupOperations::rollup_completed_hours(&db, now)
+            .await
+            .unwrap();
+        assert_eq!(created, 1);
+
+        let rollups = RollupOperations::get_node_rollups(&db, node.id, prev_hour)
+            .await
+            .unwrap();
+        assert_eq!(rollups.len(), 1);
+        let rollup = &rollups[0];
+        assert_eq!(rollup.bucket_start.to_utc(), prev_hour);
+        assert_eq!(rollup.check_count, 4);
+        assert_eq!(rollup.success_count, 3);
+        assert_eq!(rollup.min_latency, Some(100));
+        assert_eq!(rollup.avg_latency, Some(200.0));
+        assert_eq!(rollup.p95_latency, Some(300));
+
+        // 再次执行不会重复汇总
+        let created = RollupOperations::rollup_completed_hours(&db, now)
+            .await
+            .unwrap();
+        assert_eq!(created, 0);
+
+        // 积压跨越多页的记录全部汇总，中间没有记录的时段被跳过
+        let backlog_start = prev_hour - chrono::Duration::days(3);
+        for hours in [0, 30, 60] {
+            let mut record = health_records::Model::new_active_model(
+                node.id,
+                HealthStatus::Healthy,
+                Some(50),
+                None,
+            );
+            record.checked_at =
+                Set((backlog_start + chrono::Duration::hours(hours)).fixed_offset());
+            health_records::Entity::insert(record)
+                .exec(db.orm_db())
+                .await
+                .unwrap();
+        }
+        health_rollups::Entity::delete_many()
+            .exec(db.orm_db())
+            .await
+            .unwrap();
+
+        let created = RollupOperations::rollup_completed_hours(&db, now)
+            .await
+            .unwrap();
+        assert_eq!(created, 4);
+        let rollups = RollupOperations::get_node_rollups(&db, node.id, backlog_start)
+            .await
+            .unwrap();
+        assert_eq!(
+            rollups.iter().map(|r| r.check_count).collect::<Vec<_>>(),
+            vec![1, 1, 1, 4]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_state_event_operations() {
+        let db = Db::memory_db().await;
+        let node = NodeOperations::create_node(&db, test_node("State"))
+            .await
+            .unwrap();
+
+        assert!(StateEventOperations::get_latest_event(&db, node.id)
+            .await
+            .unwrap()
+            .is_none());
+
+        let now = chrono::Utc::now();
+        StateEventOperations::record_transition(
+            &db,
+            node.id,
+            NodeState::Unknown,
+            NodeState::Up,
+            None,
+            now - chrono::Duration::minutes(5),
+        )
+        .await
+        .unwrap();
+        StateEventOperations::record_transition(
+            &db,
+            node.id,
+            NodeState::Up,
+            NodeState::Degraded,
+            Some("Destination node not online".to_string()),
+            now,
+        )
+        .await
+        .unwrap();
+
+        let latest = StateEventOperations::get_latest_event(&db, node.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.get_to_state(), NodeState::Degraded);
+        assert_eq!(latest.from_state, "up");
+        assert_eq!(latest.reason.as_deref(), Some("Destination node not online"));
+    }
+
+    #[tokio::test]
+    async fn test_listener_operations() {
+        let db = Db::memory_db().await;
+        let node = NodeOperations::create_node(&db, test_node("Listener"))
+            .await
+            .unwrap();
+
+        ListenerOperations::set_node_listeners(
+            &db,
+            node.id,
+            vec![
+                "tcp://listener.example.com:11010".to_string(),
+                "UDP://listener.example.com:11010".to_string(),
+                "not a url".to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+        let listeners = ListenerOperations::get_node_listeners(&db, node.id)
+            .await
+            .unwrap();
+        let mut protocols: Vec<_> = listeners.iter().map(|l| l.protocol.as_str()).collect();
+        protocols.sort();
+        assert_eq!(protocols, vec!["tcp", "udp"]);
+
+        // 记录检查结果
+        let udp = listeners.iter().find(|l| l.protocol == "udp").unwrap();
+        ListenerOperations::update_listener_status(
+            &db,
+            udp.id,
+            HealthStatus::Unhealthy,
+            None,
+            Some("connection refused".to_string()),
+            Some(ProbeErrorKind::ConnectRefused),
+        )
+        .await
+        .unwrap();
+
+        // 替换集合时保留未变化的地址及其检查结果
+        ListenerOperations::set_node_listeners(
+            &db,
+            node.id,
+            vec![
+                "UDP://listener.example.com:11010".to_string(),
+                "wss://listener.example.com:11012".to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+        let map = ListenerOperations::get_listeners_map(&db).await.unwrap();
+        let listeners = &map[&node.id];
+        assert_eq!(listeners.len(), 2);
+        let udp = listeners.iter().find(|l| l.protocol == "udp").unwrap();
+        assert_eq!(udp.last_status.as_deref(), Some("unhealthy"));
+        assert_eq!(udp.last_error_kind.as_deref(), Some("connect_refused"));
+        assert!(listeners.iter().any(|l| l.protocol == "wss"));
+    }
+
+    #[tokio::test]
+    async fn test_certificate_operations() {
+        let db = Db::memory_db().await;
+        let req = CreateNodeRequest {
+            port: 11012,
+            protocol: "wss".to_string(),
+            ..test_node("TLS")
+        };
+        let node = NodeOperations::create_node(&db, req).await.unwrap();
+        let wss = "wss://tls.example.com:11012/";
+        let quic = "quic://tls.example.com:11013";
+
+        let now = chrono::Utc::now();
+        let cert = CertificateInfo {
+            subject: "CN=tls.example.com".to_string(),
+            issuer: "CN=Example CA".to_string(),
+            not_before: now - chrono::Duration::days(80),
+            not_after: now + chrono::Duration::days(10),
+            self_signed: false,
+            hostname_match: true,
+            trusted: true,
+        };
+        CertificateOperations::record_check(&db, node.id, wss, CertStatus::Expiring, Ok(&cert))
+            .await
+            .unwrap();
+        CertificateOperations::record_check(
+            &db,
+            node.id,
+            quic,
+            CertStatus::Unavailable,
+            Err("connection refused".to_string()),
+        )
+        .await
+        .unwrap();
+
+        // 握手失败时保留上一次拿到的证书信息
+        CertificateOperations::record_check(
+            &db,
+            node.id,
+            wss,
+            CertStatus::Unavailable,
+            Err("timed out".to_string()),
+        )
+        .await
+        .unwrap();
+        let certs = CertificateOperations::get_node_certificates(&db, node.id)
+            .await
+            .unwrap();
+        assert_eq!(certs.len(), 2);
+        let wss_cert = certs.iter().find(|c| c.url == wss).unwrap();
+        assert_eq!(CertStatus::from(wss_cert.status.as_str()), CertStatus::Unavailable);
+        assert_eq!(wss_cert.last_error_message.as_deref(), Some("timed out"));
+        assert_eq!(wss_cert.not_after.map(|t| t.to_utc()), Some(cert.not_after));
+
+        // 节点不再使用的地址被删除
+        let removed =
+            CertificateOperations::retain_node_certificates(&db, node.id, &[wss.to_string()])
+                .await
+                .unwrap();
+        assert_eq!(removed, 1);
+        let map = CertificateOperations::get_certificates_map(&db).await.unwrap();
+        assert_eq!(map[&node.id].len(), 1);
+        assert_eq!(map[&node.id][0].url, wss);
+    }
+
+    #[tokio::test]
+    async fn test_version_history() {
+        let db = Db::memory_db().await;
+        let node = NodeOperations::create_node(&db, test_node("Version"))
+            .await
+            .unwrap();
+
+        // 同一版本只延长最近一条历史，升级和回滚各新增一条
+        for version in ["2.3.0-aa", "2.3.0-aa", "2.4.0-bb", "2.3.0-aa", ""] {
+            NodeOperations::update_node_version(&db, node.id, version.to_string())
+                .await
+                .unwrap();
+        }
+        let history = VersionOperations::get_node_versions(&db, node.id)
+            .await
+            .unwrap();
+        let versions = history
+            .iter()
+            .map(|v| v.version.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(versions, vec!["2.3.0-aa", "2.4.0-bb", "2.3.0-aa"]);
+        let first = history.last().unwrap();
+        assert!(first.last_seen_at >= first.first_seen_at);
+
+        // 空版本不计入历史，但仍覆盖节点当前版本
+        let node = NodeOperations::get_node_by_id(&db, node.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(node.version, "");
     }
 }
@@ -0,0 +1,177 @@
+//! 端到端测试：在本进程内启动真实的 EasyTier 节点和模拟后端，走完获取节点、探测、上报的完整流程
+//!
+//! 节点只监听 127.0.0.1 的 TCP 端口且不创建 TUN 设备，不需要额外权限，随 `cargo test` 一起运行。
+
+use std::{sync::Arc, time::Duration};
+
+use dashmap::DashMap;
+use easytier::{
+    common::config::{ConfigFileControl, ConfigLoader, NetworkIdentity, TomlConfigLoader},
+    instance_manager::NetworkInstanceManager,
+};
+
+use crate::{
+    backend_client::{BackendClient, BackendPeer},
+    check_schedule::CheckIntervalConfig,
+    collect_heartbeats,
+    db::{Db, NodeState, ProbeErrorKind},
+    health_checker::{HealthChecker, ProbeMode},
+    health_checker_manager::HealthCheckerManager,
+    node_state::StateMachineConfig,
+    probe_backend::{mock::MockBackend, ProbeBackend},
+    sync_peers_to_db, PeerMetadataMap,
+};
+
+pub(crate) const NETWORK_NAME: &str = "e2e-net";
+pub(crate) const NETWORK_SECRET: &str = "e2e-secret";
+// Upper bound for a node to reach its expected state
+const STATE_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub(crate) fn free_tcp_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+/// 启动一个只监听本地 TCP 端口的 EasyTier 节点，作为被监控的公共节点
+pub(crate) fn start_local_node(instance_mgr: &NetworkInstanceManager, port: u16) {
+    let cfg = TomlConfigLoader::default();
+    cfg.set_inst_name(format!("e2e-node-{}", port));
+    cfg.set_network_identity(NetworkIdentity::new(
+        NETWORK_NAME.to_string(),
+        NETWORK_SECRET.to_string(),
+    ));
+    cfg.set_listeners(vec![format!("tcp://127.0.0.1:{}", port).parse().unwrap()]);
+    let mut flags = cfg.get_flags();
+    flags.no_tun = true;
+    cfg.set_flags(flags);
+
+    instance_mgr
+        .run_network_instance(cfg, false, ConfigFileControl::STATIC_CONFIG)
+        .unwrap();
+}
+
+fn backend_peer(id: i32, port: u16) -> BackendPeer {
+    serde_json::from_value(serde_json::json!({
+        "id": id,
+        "name": format!("e2e-peer-{}", id),
+        "public_ip": format!("127.0.0.1:{}", port),
+        "protocol": "tcp",
+        "network_name": NETWORK_NAME,
+        "network_secret": NETWORK_SECRET,
+        "status": "online",
+    }))
+    .unwrap()
+}
+
+/// 使用模拟后端运行探测节点的各个环节
+struct Probe {
+    db: Db,
+    backend: Arc<dyn ProbeBackend>,
+    health_checker: Arc<HealthChecker>,
+    manager: HealthCheckerManager,
+    peer_metadata: PeerMetadataMap,
+}
+
+impl Probe {
+    async fn new(backend: &MockBackend) -> Self {
+        let db = Db::memory_db().await;
+        let health_checker = Arc::new(
+            HealthChecker::new(db.clone())
+                .with_probe_mode(ProbeMode::Handshake)
+                .with_handshake_ping_duration(Duration::from_secs(1))
+                .with_check_interval_config(CheckIntervalConfig {
+                    base: Duration::from_secs(1),
+                    ..Default::default()
+                })
+                .with_state_machine_config(StateMachineConfig {
+                    failure_threshold: 1,
+                    recovery_threshold: 1,
+                    min_dwell: chrono::Duration::zero(),
+                }),
+        );
+        let backend =
+            BackendClient::new(backend.base_url().to_string(), Some("key".to_string())).unwrap();
+
+        Self {
+            manager: HealthCheckerManager::new(health_checker.clone(), db.clone()),
+            db,
+            backend: Arc::new(backend),
+            health_checker,
+            peer_metadata: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// 从后端获取节点并开始监控
+    async fn sync(&self) {
+        let peers = self.backend.fetch_peers(None).await.unwrap();
+        sync_peers_to_db(&self.db, &self.health_checker, &self.peer_metadata, peers)
+            .await
+            .unwrap();
+        self.manager.refresh_nodes().await.unwrap();
+    }
+
+    async fn wait_for_state(&self, expected: NodeState) {
+        tokio::time::timeout(STATE_TIMEOUT, async {
+            while !self
+                .health_checker
+                .get_all_nodes_state()
+                .iter()
+                .any(|(_, state)| *state == expected)
+            {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("node did not reach {} in {:?}", expected, STATE_TIMEOUT));
+    }
+
+    /// 收集心跳并上报给后端
+    async fn report(&self) {
+        let items =
+            collect_heartbeats(&self.db, &self.health_checker, &self.peer_metadata, None).await;
+        let outcomes = self.backend.report_heartbeats(&items).await.unwrap();
+        assert!(outcomes.iter().all(|o| o.is_success()));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_probe_reports_reachable_node_online() {
+    let instance_mgr = NetworkInstanceManager::new();
+    let port = free_tcp_port();
+    start_local_node(&instance_mgr, port);
+
+    let backend = MockBackend::start(vec![backend_peer(1, port)]).await;
+    let probe = Probe::new(&backend).await;
+    probe.sync().await;
+    probe.wait_for_state(NodeState::Up).await;
+    probe.report().await;
+
+    let heartbeats = backend.heartbeats();
+    assert_eq!(heartbeats.len(), 1);
+    assert_eq!(heartbeats[0].node_id, 1);
+    assert_eq!(heartbeats[0].status, "online");
+    assert_eq!(heartbeats[0].state.as_deref(), Some("up"));
+    assert_eq!(heartbeats[0].error_kind, None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_probe_reports_unreachable_node_offline() {
+    // 端口上没有节点监听
+    let backend = MockBackend::start(vec![backend_peer(2, free_tcp_port())]).await;
+    let probe = Probe::new(&backend).await;
+    probe.sync().await;
+    probe.wait_for_state(NodeState::Down).await;
+    probe.report().await;
+
+    let heartbeats = backend.heartbeats();
+    assert_eq!(heartbeats.len(), 1);
+    assert_eq!(heartbeats[0].node_id, 2);
+    assert_eq!(heartbeats[0].status, "offline");
+    assert_eq!(
+        heartbeats[0].error_kind,
+        Some(ProbeErrorKind::ConnectRefused)
+    );
+}
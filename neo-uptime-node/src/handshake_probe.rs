@@ -0,0 +1,211 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use easytier::{
+    common::{
+        config::{ConfigLoader, TomlConfigLoader},
+        global_ctx::{ArcGlobalCtx, GlobalCtx},
+        new_peer_id,
+    },
+    connector::create_connector_by_url,
+    peers::{create_packet_recv_chan, peer_conn::PeerConn},
+    tunnel::IpVersion,
+};
+use tracing::debug;
+
+use crate::{
+    db::ProbeErrorKind,
+    health_checker::ProbeResult,
+    probe_error::{classify_connect_error, ProbeError},
+};
+
+// Upper bound for dialing the node before the handshake starts
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 轻量探测：只建立一条连接并完成 `PeerConn` 握手，不为节点启动完整的网络实例
+///
+/// 握手时校验网络名和密钥摘要，之后在 `ping_duration` 内测量 RTT 和丢包率，结束后断开连接。
+pub struct HandshakeProbe {
+    url: String,
+    global_ctx: ArcGlobalCtx,
+    ping_duration: Duration,
+}
+
+impl HandshakeProbe {
+    /// 由节点配置创建探测器，使用配置中的第一个 peer 地址和网络身份
+    pub fn new(cfg: &TomlConfigLoader, ping_duration: Duration) -> anyhow::Result<Self> {
+        let url = cfg
+            .get_peers()
+            .first()
+            .map(|peer| peer.uri.to_string())
+            .ok_or_else(|| anyhow::anyhow!("node config has no peer uri"))?;
+
+        Ok(Self::for_url(cfg, url, ping_duration))
+    }
+
+    /// 使用节点配置中的网络身份探测指定的监听地址
+    pub fn for_url(cfg: &TomlConfigLoader, url: String, ping_duration: Duration) -> Self {
+        Self {
+            url,
+            global_ctx: Arc::new(GlobalCtx::new(cfg.clone())),
+            ping_duration,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// 执行一次探测
+    pub async fn probe(&self) -> anyhow::Result<ProbeResult> {
+        let url = &self.url;
+        let connect_error = |e: String| ProbeError::new(classify_connect_error(&e), e);
+
+        let mut connector = create_connector_by_url(url, &self.global_ctx, IpVersion::Both)
+            .await
+            .map_err(|e| connect_error(format!("create connector for {} failed: {:?}", url, e)))?;
+        let tunnel = match tokio::time::timeout(CONNECT_TIMEOUT, connector.connect()).await {
+            Ok(Ok(tunnel)) => tunnel,
+            Ok(Err(e)) => {
+                anyhow::bail!(connect_error(format!("connect to {} failed: {:?}", url, e)))
+            }
+            Err(_) => anyhow::bail!(ProbeError::new(
+                ProbeErrorKind::ConnectTimeout,
+                format!("connect to {} timed out after {:?}", url, CONNECT_TIMEOUT)
+            )),
+        };
+
+        let mut conn = PeerConn::new(new_peer_id(), self.global_ctx.clone(), tunnel);
+        let handshake_started = Instant::now();
+        conn.do_handshake_as_client().await.map_err(|e| {
+            ProbeError::new(
+                ProbeErrorKind::HandshakeRejected,
+                format!("handshake with {} failed: {:?}", url, e),
+            )
+        })?;
+        let handshake_rtt = handshake_started.elapsed();
+
+        // 同一网络的节点只有在密钥一致时才会回传摘要；网络名不同说明对端是托管其他网络的公共节点
+        let remote = conn.get_network_identity();
+        let local = self.global_ctx.get_network_identity();
+        if remote.network_name == local.network_name && remote != local {
+            anyhow::bail!(ProbeError::new(
+                ProbeErrorKind::HandshakeRejected,
+                format!("network secret mismatch with {}", url)
+            ));
+        }
+
+        let (packet_send, mut packet_recv) = create_packet_recv_chan();
+        conn.start_recv_loop(packet_send).await;
+        conn.start_pingpong();
+
+        let deadline = tokio::time::sleep(self.ping_duration);
+        tokio::pin!(deadline);
+        loop {
+            tokio::select! {
+                _ = &mut deadline => break,
+                // 丢弃对端发来的路由同步等数据包，避免接收循环阻塞；连接关闭时提前结束
+                packet = packet_recv.recv() => if packet.is_none() { break },
+            }
+        }
+
+        let info = conn.get_conn_info();
+        if info.is_closed {
+            anyhow::bail!(ProbeError::new(
+                ProbeErrorKind::HandshakeRejected,
+                format!("{} closed the connection after handshake", url)
+            ));
+        }
+
+        // 测量窗口内没有完成 ping 时，退回使用握手往返时间
+        let response_time = info
+            .stats
+            .map(|stats| stats.latency_us)
+            .filter(|latency_us| *latency_us > 0)
+            .unwrap_or(handshake_rtt.as_micros() as u64);
+
+        debug!(
+            "Handshake probe to {} done: network={}, features={:?}, latency={}us, loss_rate={}",
+            url, info.network_name, info.features, response_time, info.loss_rate
+        );
+
+        // 握手只携带协议版本号，节点的 EasyTier 版本要通过路由同步才能拿到
+        Ok(ProbeResult {
+            version: None,
+            response_time,
+            loss_rate: info.loss_rate,
+            load: None,
+            features: info.features,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use easytier::{common::config::NetworkIdentity, instance_manager::NetworkInstanceManager};
+    use tokio::net::TcpStream;
+
+    use super::*;
+    use crate::{
+        e2e_tests::{free_tcp_port, start_local_node, NETWORK_NAME, NETWORK_SECRET},
+        probe_error::error_kind,
+    };
+
+    fn probe_with_secret(port: u16, secret: &str) -> HandshakeProbe {
+        let cfg = TomlConfigLoader::default();
+        cfg.set_network_identity(NetworkIdentity::new(
+            NETWORK_NAME.to_string(),
+            secret.to_string(),
+        ));
+        HandshakeProbe::for_url(
+            &cfg,
+            format!("tcp://127.0.0.1:{}", port),
+            Duration::from_millis(500),
+        )
+    }
+
+    /// 启动本地 EasyTier 节点，等到它开始监听后返回端口
+    async fn local_node(instance_mgr: &NetworkInstanceManager) -> u16 {
+        let port = free_tcp_port();
+        start_local_node(instance_mgr, port);
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while TcpStream::connect(("127.0.0.1", port)).await.is_err() {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        })
+        .await
+        .expect("local node did not start listening");
+        port
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_handshake_probe_local_node() {
+        let instance_mgr = NetworkInstanceManager::new();
+        let port = local_node(&instance_mgr).await;
+
+        let result = probe_with_secret(port, NETWORK_SECRET)
+            .probe()
+            .await
+            .unwrap();
+        assert!(result.response_time > 0);
+        assert!((0.0..=1.0).contains(&result.loss_rate));
+        assert_eq!(result.version, None);
+        assert!(result.load.is_none());
+        // 当前版本的 EasyTier 在握手中不通告任何特性标志
+        assert_eq!(result.features, Vec::<String>::new());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_handshake_probe_secret_mismatch() {
+        let instance_mgr = NetworkInstanceManager::new();
+        let port = local_node(&instance_mgr).await;
+
+        let err = probe_with_secret(port, "wrong-secret")
+            .probe()
+            .await
+            .unwrap_err();
+        assert_eq!(error_kind(&err), ProbeErrorKind::HandshakeRejected);
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
+    collections::HashMap,
     ops::{DerefMut, Div},
-    sync::Arc,
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 
@@ -13,15 +14,35 @@ use easytier::{
     },
     defer,
     instance_manager::NetworkInstanceManager,
+    proto::{
+        api::instance::ListGlobalForeignNetworkRequest, rpc_types::controller::BaseController,
+    },
 };
 use serde::{Deserialize, Serialize};
 use sqlx::any;
+use tokio::sync::broadcast;
 use tracing::{debug, error, info, instrument, warn};
 
-use crate::db::{
-    entity::shared_nodes,
-    operations::{HealthOperations, NodeOperations},
-    Db, HealthStatus,
+use crate::{
+    cert_check::{self, CertCheckConfig},
+    check_schedule::{CheckIntervalConfig, PendingTransition},
+    db::{
+        entity::shared_nodes,
+        operations::{
+            CertificateOperations, HealthOperations, ListenerOperations, NodeOperations,
+            StateEventOperations,
+        },
+        CertStatus, Db, HealthStatus, LinkQuality, NodeState, ProbeErrorKind,
+    },
+    handshake_probe::HandshakeProbe,
+    metrics,
+    node_load::NodeLoad,
+    node_state::{
+        CheckOutcome, NodeStateChange, NodeStateMachine, StateMachineConfig, StateTransition,
+    },
+    probe_error::{classify_connect_error, error_kind, latest_connect_error, ProbeError},
+    quality::{QualityWindow, DEFAULT_QUALITY_WINDOW},
+    tls_probe,
 };
 
 pub struct HealthCheckOneNode {
@@ -72,8 +93,16 @@ pub struct HealthyMemRecord {
     node_id: i32,
     current_health_status: HealthStatus,
     last_error_info: Option<String>,
+    last_error_kind: Option<ProbeErrorKind>,
     last_check_time: chrono::DateTime<chrono::Utc>,
     last_response_time: Option<i32>,
+    state_machine: NodeStateMachine,
+    quality_window: QualityWindow,
+    last_load: Option<NodeLoad>,
+    /// 最近一次实测的外部节点总数，检查失败时保留
+    last_foreign_peer_count: Option<u32>,
+    /// 最近一次成功检查时对端通告的特性标志，检查失败时保留
+    last_features: Vec<String>,
 
     // the current time is corresponding to the index by modulo with UNIX-timestamp.
     total_check_counter_ring: Vec<RingItem>,
@@ -86,8 +115,14 @@ impl HealthyMemRecord {
             node_id,
             current_health_status: HealthStatus::Unknown,
             last_error_info: None,
+            last_error_kind: None,
             last_check_time: chrono::Utc::now(),
             last_response_time: None,
+            state_machine: NodeStateMachine::new(chrono::Utc::now()),
+            quality_window: QualityWindow::default(),
+            last_load: None,
+            last_foreign_peer_count: None,
+            last_features: Vec::new(),
             total_check_counter_ring: vec![Default::default(); HEALTH_CHECK_RING_SIZE],
             healthy_counter_ring: vec![Default::default(); HEALTH_CHECK_RING_SIZE],
         }
@@ -113,6 +148,7 @@ impl HealthyMemRecord {
             } else {
                 Some(latest.error_message.clone())
             };
+            mem_record.last_error_kind = latest.error_kind.as_deref().map(ProbeErrorKind::from);
         }
 
         // 填充环形缓冲区
@@ -147,11 +183,13 @@ impl HealthyMemRecord {
         status: HealthStatus,
         response_time: Option<i32>,
         error_message: Option<String>,
+        error_kind: Option<ProbeErrorKind>,
     ) {
         self.current_health_status = status.clone();
         self.last_check_time = chrono::Utc::now();
         self.last_response_time = response_time;
         self.last_error_info = error_message;
+        self.last_error_kind = error_kind;
 
         // 更新环形缓冲区
         let now = chrono::Utc::now().timestamp() as usize;
@@ -164,6 +202,64 @@ impl HealthyMemRecord {
         }
     }
 
+    /// 根据本次检查结果推进状态机，返回发生的状态转换
+    pub fn observe_state(
+        &mut self,
+        outcome: CheckOutcome,
+        config: &StateMachineConfig,
+    ) -> Option<StateTransition> {
+        self.state_machine
+            .observe(outcome, chrono::Utc::now(), config)
+    }
+
+    /// 记录一次成功检查的延迟（微秒）和丢包率
+    pub fn observe_quality(&mut self, rtt_us: u64, loss_rate: f64, window_size: usize) {
+        self.quality_window.push(rtt_us, loss_rate, window_size);
+    }
+
+    /// 记录一次失败的检查，在质量窗口中按全部丢包计算
+    pub fn observe_failed_check(&mut self, window_size: usize) {
+        self.quality_window.push_failure(window_size);
+    }
+
+    /// 获取滑动窗口内的链路质量
+    pub fn get_link_quality(&self) -> LinkQuality {
+        self.quality_window.stats()
+    }
+
+    /// 记录本次检查实测的外部网络负载，检查失败或握手探测时为 None
+    pub fn observe_load(&mut self, load: Option<NodeLoad>) {
+        if let Some(load) = &load {
+            self.last_foreign_peer_count = Some(load.foreign_peer_count);
+        }
+        self.last_load = load;
+    }
+
+    /// 获取最近一次实测的外部网络负载
+    pub fn get_last_load(&self) -> Option<&NodeLoad> {
+        self.last_load.as_ref()
+    }
+
+    /// 获取最近一次实测的外部节点总数，之后的检查失败时仍返回该值
+    pub fn get_last_foreign_peer_count(&self) -> Option<u32> {
+        self.last_foreign_peer_count
+    }
+
+    /// 记录本次成功检查时对端通告的特性标志
+    pub fn observe_features(&mut self, features: Vec<String>) {
+        self.last_features = features;
+    }
+
+    /// 获取最近一次成功检查时对端通告的特性标志
+    pub fn get_last_features(&self) -> &[String] {
+        &self.last_features
+    }
+
+    /// 从最近一次持久化的状态转换恢复派生状态
+    pub fn restore_state(&mut self, state: NodeState, since: chrono::DateTime<chrono::Utc>) {
+        self.state_machine = NodeStateMachine::restore(state, since);
+    }
+
     /// 获取健康统计信息
     pub fn get_health_stats(&self, hours: u64) -> crate::db::HealthStats {
         let now = chrono::Utc::now().timestamp() as usize;
@@ -199,6 +295,21 @@ impl HealthyMemRecord {
         &self.current_health_status
     }
 
+    /// 获取派生状态
+    pub fn get_node_state(&self) -> NodeState {
+        self.state_machine.state()
+    }
+
+    /// 获取进入当前派生状态的时间
+    pub fn get_node_state_since(&self) -> chrono::DateTime<chrono::Utc> {
+        self.state_machine.since()
+    }
+
+    /// 最近的检查结果与派生状态不一致，状态转换待确认
+    pub fn is_state_pending(&self) -> bool {
+        self.state_machine.is_pending()
+    }
+
     /// 获取最后检查时间
     pub fn get_last_check_time(&self) -> chrono::DateTime<chrono::Utc> {
         self.last_check_time
@@ -214,6 +325,11 @@ impl HealthyMemRecord {
         &self.last_error_info
     }
 
+    /// 获取最后错误分类
+    pub fn get_last_error_kind(&self) -> Option<ProbeErrorKind> {
+        self.last_error_kind
+    }
+
     pub fn get_counter_ring(&mut self) -> (Vec<u64>, Vec<u64>) {
         let now = self.last_check_time.timestamp() as usize;
 
@@ -246,8 +362,77 @@ pub struct HealthChecker {
     node_tasks: DashMap<i32, ScopedTask<()>>,
     node_records: Arc<DashMap<i32, HealthyMemRecord>>,
     node_cfg: Arc<DashMap<i32, TomlConfigLoader>>,
+    state_config: StateMachineConfig,
+    state_changes: broadcast::Sender<NodeStateChange>,
+    interval_config: CheckIntervalConfig,
+    node_intervals: Arc<DashMap<i32, Duration>>,
+    quality_window: usize,
+    probe_mode: ProbeMode,
+    handshake_ping_duration: Duration,
+    cert_config: CertCheckConfig,
+}
+
+/// 节点探测方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeMode {
+    /// 为每个节点启动完整的网络实例，可获取节点版本和连接数
+    #[default]
+    Instance,
+    /// 只完成握手和 ping，不启动网络实例，适合监控大量节点
+    Handshake,
+}
+
+/// 一次成功的节点检查结果
+#[derive(Debug)]
+pub struct ProbeResult {
+    /// 节点版本，握手探测无法获取
+    pub version: Option<String>,
+    /// 延迟（微秒）
+    pub response_time: u64,
+    /// 连接丢包率（0~1）
+    pub loss_rate: f32,
+    /// 节点承载的外部网络负载，握手探测无法获取
+    pub load: Option<NodeLoad>,
+    /// 对端在握手中通告的特性标志
+    pub features: Vec<String>,
+}
+
+/// 单个节点的探测目标
+enum NodeProbe {
+    Instance(uuid::Uuid),
+    Handshake(HandshakeProbe),
+}
+
+impl std::fmt::Display for NodeProbe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeProbe::Instance(inst_id) => write!(f, "inst id: {}", inst_id),
+            NodeProbe::Handshake(probe) => write!(f, "handshake: {}", probe.url()),
+        }
+    }
 }
 
+/// 单个节点检查任务使用的共享状态
+#[derive(Clone)]
+struct CheckTaskContext {
+    instance_mgr: Arc<NetworkInstanceManager>,
+    db: Db,
+    node_records: Arc<DashMap<i32, HealthyMemRecord>>,
+    state_config: StateMachineConfig,
+    state_changes: broadcast::Sender<NodeStateChange>,
+    interval_config: CheckIntervalConfig,
+    node_intervals: Arc<DashMap<i32, Duration>>,
+    quality_window: usize,
+    handshake_ping_duration: Duration,
+    cert_config: CertCheckConfig,
+}
+
+// Buffered state changes per subscriber before it starts lagging
+const STATE_CHANGE_CHANNEL_CAPACITY: usize = 1024;
+// How long a handshake probe pings the node before disconnecting
+const DEFAULT_HANDSHAKE_PING_DURATION: Duration = Duration::from_secs(3);
+
 impl HealthChecker {
     pub fn new(db: Db) -> Self {
         let instance_mgr = Arc::new(NetworkInstanceManager::new());
@@ -258,17 +443,93 @@ impl HealthChecker {
             node_tasks: DashMap::new(),
             node_records: Arc::new(DashMap::new()),
             node_cfg: Arc::new(DashMap::new()),
+            state_config: StateMachineConfig::default(),
+            state_changes: broadcast::channel(STATE_CHANGE_CHANNEL_CAPACITY).0,
+            interval_config: CheckIntervalConfig::default(),
+            node_intervals: Arc::new(DashMap::new()),
+            quality_window: DEFAULT_QUALITY_WINDOW,
+            probe_mode: ProbeMode::default(),
+            handshake_ping_duration: DEFAULT_HANDSHAKE_PING_DURATION,
+            cert_config: CertCheckConfig::default(),
+        }
+    }
+
+    /// 设置节点探测方式
+    pub fn with_probe_mode(mut self, mode: ProbeMode) -> Self {
+        self.probe_mode = mode;
+        self
+    }
+
+    /// 设置握手探测在握手完成后测量 RTT 的时长
+    pub fn with_handshake_ping_duration(mut self, duration: Duration) -> Self {
+        self.handshake_ping_duration = duration;
+        self
+    }
+
+    /// 设置计算丢包率、抖动和延迟分位数的滑动窗口大小
+    pub fn with_quality_window(mut self, window_size: usize) -> Self {
+        self.quality_window = window_size.max(1);
+        self
+    }
+
+    /// 设置检查间隔及自适应策略
+    pub fn with_check_interval_config(mut self, config: CheckIntervalConfig) -> Self {
+        self.interval_config = config;
+        self
+    }
+
+    /// 设置 wss / quic 地址的证书检查间隔和过期告警时长
+    pub fn with_cert_check_config(mut self, config: CertCheckConfig) -> Self {
+        self.cert_config = config;
+        self
+    }
+
+    /// 设置单个节点的基础检查间隔（来自后端元数据），None 表示使用全局间隔
+    pub fn set_node_check_interval(&self, node_id: i32, interval: Option<Duration>) {
+        match interval {
+            Some(interval) => {
+                let previous = self.node_intervals.insert(node_id, interval);
+                if previous != Some(interval) {
+                    info!("Node {} check interval set to {:?}", node_id, interval);
+                }
+            }
+            None => {
+                self.node_intervals.remove(&node_id);
+            }
         }
     }
 
+    /// 订阅节点状态变化
+    pub fn subscribe_state_changes(&self) -> broadcast::Receiver<NodeStateChange> {
+        self.state_changes.subscribe()
+    }
+
+    /// 设置节点状态机的阈值和最小停留时间
+    pub fn with_state_machine_config(mut self, config: StateMachineConfig) -> Self {
+        self.state_config = config;
+        self
+    }
+
+    /// 用最近一次持久化的状态转换恢复内存记录的派生状态
+    async fn restore_node_state(&self, mem_record: &mut HealthyMemRecord) -> anyhow::Result<()> {
+        let node_id = mem_record.node_id;
+        if let Some(event) = StateEventOperations::get_latest_event(&self.db, node_id)
+            .await
+            .with_context(|| format!("Failed to get latest state event for node {}", node_id))?
+        {
+            mem_record.restore_state(event.get_to_state(), event.created_at.to_utc());
+        }
+        Ok(())
+    }
+
     /// 启动时从数据库加载所有节点的健康记录到内存
     pub async fn load_health_records_from_db(&self) -> anyhow::Result<()> {
         info!("Loading health records from database...");
 
-        // 获取所有活跃节点
-        let nodes = NodeOperations::get_all_nodes(&self.db)
+        // 获取所有未停用的节点
+        let nodes = NodeOperations::get_monitored_nodes(&self.db)
             .await
-            .with_context(|| "Failed to get all nodes from database")?;
+            .with_context(|| "Failed to get monitored nodes from database")?;
 
         let from_date = chrono::Utc::now().naive_utc()
             - chrono::Duration::seconds(HEALTH_CHECK_RING_MAX_DURATION_SEC as i64);
@@ -283,7 +544,8 @@ impl HealthChecker {
                     })?;
 
             // 创建内存记录
-            let mem_record = HealthyMemRecord::from_db_records(node.id, &records);
+            let mut mem_record = HealthyMemRecord::from_db_records(node.id, &records);
+            self.restore_node_state(&mut mem_record).await?;
             self.node_records.insert(node.id, mem_record);
 
             debug!(
@@ -301,6 +563,11 @@ impl HealthChecker {
         Ok(())
     }
 
+    /// 是否保存有节点的内存健康记录
+    pub fn has_node_record(&self, node_id: i32) -> bool {
+        self.node_records.contains_key(&node_id)
+    }
+
     /// 获取节点的内存健康记录
     pub fn get_node_memory_record(&self, node_id: i32) -> Option<HealthyMemRecord> {
         self.node_records.get(&node_id).map(|entry| entry.clone())
@@ -332,6 +599,14 @@ impl HealthChecker {
             .collect()
     }
 
+    /// 获取所有节点的派生状态（从内存）
+    pub fn get_all_nodes_state(&self) -> Vec<(i32, NodeState)> {
+        self.node_records
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().get_node_state()))
+            .collect()
+    }
+
     pub async fn try_update_node(&self, node_id: i32) -> anyhow::Result<()> {
         let old_cfg = self
             .node_cfg
@@ -387,7 +662,31 @@ impl HealthChecker {
         node_info: &shared_nodes::Model,
         max_time: Duration,
     ) -> anyhow::Result<()> {
+        self.probe_once(node_info, max_time).await.map(|_| ())
+    }
+
+    /// 对不在监控中的节点做一次检查，`max_time` 内失败会重试，失败时返回最后一次检查的错误
+    pub async fn probe_once(
+        &self,
+        node_info: &shared_nodes::Model,
+        max_time: Duration,
+    ) -> anyhow::Result<ProbeResult> {
         let cfg = self.get_node_cfg_with_model(node_info, None).await?;
+        if self.probe_mode == ProbeMode::Handshake {
+            let probe = HandshakeProbe::new(&cfg, self.handshake_ping_duration)?;
+            return tokio::time::timeout(max_time, probe.probe())
+                .await
+                .map_err(|_| {
+                    ProbeError::new(
+                        ProbeErrorKind::ConnectTimeout,
+                        format!(
+                            "Connection test for node {} timed out after {:?}",
+                            node_info.name, max_time
+                        ),
+                    )
+                })?;
+        }
+
         defer!({
             let _ = self
                 .instance_mgr
@@ -403,14 +702,14 @@ impl HealthChecker {
         
         while now.elapsed() < max_time {
             match Self::test_node_healthy(cfg.get_id(), self.instance_mgr.clone()).await {
-                Ok(_) => {
+                Ok(result) => {
                     info!(
                         "Connection test successful for node {} after {} retries and {:?}",
                         node_info.name,
                         retry_count,
                         now.elapsed()
                     );
-                    return Ok(());
+                    return Ok(result);
                 }
                 Err(e) => {
                     retry_count += 1;
@@ -426,13 +725,11 @@ impl HealthChecker {
             tokio::time::sleep(Duration::from_millis(CONNECTION_INIT_RETRY_INTERVAL_MS)).await;
         }
         
-        Err(anyhow::anyhow!(
-            "Connection test failed for node {} after {:?} ({} retries): {:?}",
-            node_info.name,
-            max_time,
-            retry_count,
-            last_err
-        ))
+        let err = last_err.unwrap_or_else(|| anyhow::anyhow!("no check completed"));
+        Err(err.context(format!(
+            "Connection test failed for node {} after {:?} ({} retries)",
+            node_info.name, max_time, retry_count
+        )))
     }
 
     async fn get_node_cfg(
@@ -455,10 +752,18 @@ impl HealthChecker {
             cfg.dump()
         );
 
-        self.instance_mgr
-            .run_network_instance(cfg.clone(), true, ConfigFileControl::STATIC_CONFIG)
-            .with_context(|| "failed to run network instance")?;
-        self.inst_id_map.insert(node_id, cfg.get_id());
+        let probe = match self.probe_mode {
+            ProbeMode::Instance => {
+                self.instance_mgr
+                    .run_network_instance(cfg.clone(), true, ConfigFileControl::STATIC_CONFIG)
+                    .with_context(|| "failed to run network instance")?;
+                self.inst_id_map.insert(node_id, cfg.get_id());
+                NodeProbe::Instance(cfg.get_id())
+            }
+            ProbeMode::Handshake => {
+                NodeProbe::Handshake(HandshakeProbe::new(&cfg, self.handshake_ping_duration)?)
+            }
+        };
 
         // 初始化内存记录（如果不存在）
         if !self.node_records.contains_key(&node_id) {
@@ -469,7 +774,10 @@ impl HealthChecker {
                 HealthOperations::get_node_health_records(&self.db, node_id, Some(from_date), None)
                     .await
             {
-                let mem_record = HealthyMemRecord::from_db_records(node_id, &records);
+                let mut mem_record = HealthyMemRecord::from_db_records(node_id, &records);
+                if let Err(e) = self.restore_node_state(&mut mem_record).await {
+                    warn!("{:?}", e);
+                }
                 self.node_records.insert(node_id, mem_record);
                 info!(
                     "Initialized memory record for node {} with {} historical records",
@@ -486,10 +794,20 @@ impl HealthChecker {
         // 启动健康检查任务
         let task = ScopedTask::from(tokio::spawn(Self::node_health_check_task(
             node_id,
-            cfg.get_id(),
-            Arc::clone(&self.instance_mgr),
-            self.db.clone(),
-            Arc::clone(&self.node_records),
+            probe,
+            cfg.clone(),
+            CheckTaskContext {
+                instance_mgr: Arc::clone(&self.instance_mgr),
+                db: self.db.clone(),
+                node_records: Arc::clone(&self.node_records),
+                state_config: self.state_config.clone(),
+                state_changes: self.state_changes.clone(),
+                interval_config: self.interval_config.clone(),
+                node_intervals: Arc::clone(&self.node_intervals),
+                quality_window: self.quality_window,
+                handshake_ping_duration: self.handshake_ping_duration,
+                cert_config: self.cert_config.clone(),
+            },
         )));
         self.node_tasks.insert(node_id, task);
         self.node_cfg.insert(node_id, cfg.clone());
@@ -511,24 +829,63 @@ impl HealthChecker {
         Ok(())
     }
 
+    /// 停止节点的检查任务并丢弃其内存记录，用于节点停用或删除；数据库中的历史数据不受影响
+    pub async fn forget_node(&self, node_id: i32) -> anyhow::Result<()> {
+        self.remove_node(node_id).await?;
+        self.node_records.remove(&node_id);
+        self.node_intervals.remove(&node_id);
+        Ok(())
+    }
+
+    /// 停止所有节点的检查任务，内存记录保留，用于退出前上报最后的状态
+    pub fn stop_checks(&self) -> usize {
+        let count = self.node_tasks.len();
+        self.node_tasks.clear();
+        count
+    }
+
+    /// 删除全部网络实例，用于进程退出
+    pub fn delete_all_instances(&self) -> anyhow::Result<usize> {
+        let inst_ids = self.instance_mgr.list_network_instance_ids();
+        self.inst_id_map.clear();
+        self.node_cfg.clear();
+        if inst_ids.is_empty() {
+            return Ok(0);
+        }
+        self.instance_mgr
+            .delete_network_instance(inst_ids.clone())
+            .with_context(|| "failed to delete network instances")?;
+        Ok(inst_ids.len())
+    }
+
     #[instrument(err, ret, skip(instance_mgr))]
     async fn test_node_healthy(
         inst_id: uuid::Uuid,
         instance_mgr: Arc<NetworkInstanceManager>,
-        // return version, response time on healthy, conn_count
-    ) -> anyhow::Result<(String, u64, u32)> {
+    ) -> anyhow::Result<ProbeResult> {
+        let instance_error = |msg: String| ProbeError::new(ProbeErrorKind::InstanceError, msg);
+
         let Some(instance) = instance_mgr.get_network_info(&inst_id).await else {
-            anyhow::bail!("Health check node instance not found (inst_id: {})", inst_id);
+            anyhow::bail!(instance_error(format!(
+                "Health check node instance not found (inst_id: {})",
+                inst_id
+            )));
         };
 
         let running = instance.running;
         // health check node is not running, update db
         if !running {
-            anyhow::bail!("Health check node is not running (inst_id: {})", inst_id);
+            anyhow::bail!(instance_error(format!(
+                "Health check node is not running (inst_id: {})",
+                inst_id
+            )));
         }
 
         if let Some(err) = instance.error_msg {
-            anyhow::bail!("Health check node has error (inst_id: {}): {}", inst_id, err);
+            anyhow::bail!(instance_error(format!(
+                "Health check node has error (inst_id: {}): {}",
+                inst_id, err
+            )));
         }
 
         let p = instance.peer_route_pairs;
@@ -539,19 +896,38 @@ impl HealthChecker {
                 !route.feature_flag.unwrap().is_public_server && route.hostname != "NeoUptimeDeamon"
             }) && x.peer.as_ref().is_some_and(|p| !p.conns.is_empty())
         }) else {
-            anyhow::bail!(
-                "Destination node not online (inst_id: {}, peer_count: {}, check network_secret and connectivity)",
-                inst_id,
-                p.len()
-            );
+            // 根据最近一次连接错误判断失败原因，没有连接错误时视为无路由
+            let connect_error = latest_connect_error(&instance.events);
+            let kind = connect_error
+                .as_deref()
+                .map(classify_connect_error)
+                .unwrap_or(ProbeErrorKind::NoRoute);
+            anyhow::bail!(ProbeError::new(
+                kind,
+                format!(
+                    "Destination node not online (inst_id: {}, peer_count: {}, connect error: {})",
+                    inst_id,
+                    p.len(),
+                    connect_error.as_deref().unwrap_or("none")
+                )
+            ));
         };
 
         let Some(route_info) = &dst_node.route else {
-            anyhow::bail!("Destination node route not found (inst_id: {})", inst_id);
+            anyhow::bail!(ProbeError::new(
+                ProbeErrorKind::NoRoute,
+                format!("Destination node route not found (inst_id: {})", inst_id)
+            ));
         };
 
         let Some(peer_info) = &dst_node.peer else {
-            anyhow::bail!("Destination node peer info not found (inst_id: {})", inst_id);
+            anyhow::bail!(ProbeError::new(
+                ProbeErrorKind::NoRoute,
+                format!(
+                    "Destination node peer info not found (inst_id: {})",
+                    inst_id
+                )
+            ));
         };
 
         let version = route_info
@@ -562,123 +938,481 @@ impl HealthChecker {
             .unwrap_or("")
             .to_string();
 
-        // 计算响应时间（这里可以根据实际需要实现）
-        let response_time = peer_info
+        // 取延迟最低的连接的延迟和丢包率
+        let best_conn = peer_info
             .conns
             .iter()
-            .filter_map(|x| x.stats)
-            .map(|x| x.latency_us)
-            .min()
-            .unwrap_or(0);
+            .filter_map(|x| x.stats.map(|stats| (stats.latency_us, x.loss_rate)))
+            .min_by_key(|(latency_us, _)| *latency_us);
+        let (response_time, loss_rate) = best_conn.unwrap_or((0, 0.0));
+        // 同一节点的各条连接在握手时通告的特性标志相同
+        let features = peer_info
+            .conns
+            .first()
+            .map(|conn| conn.features.clone())
+            .unwrap_or_default();
 
         let peer_id = peer_info.peer_id;
 
-        let conn_count = if let Some(summary) = instance.foreign_network_summary {
-            summary
-                .info_map
-                .get(&peer_id)
-                .map(|x| x.network_count)
-                .unwrap_or(0)
-        } else {
-            0
+        let summary = instance
+            .foreign_network_summary
+            .and_then(|summary| summary.info_map.get(&peer_id).cloned())
+            .map(|info| (info.network_count, info.peer_count));
+        let peers_per_network = Self::list_foreign_network_peers(&inst_id, &instance_mgr, peer_id)
+            .await
+            .inspect_err(|e| {
+                debug!(
+                    "Failed to list foreign networks (inst_id: {}): {:#}",
+                    inst_id, e
+                )
+            })
+            .ok();
+        let feature_flag = route_info.feature_flag.unwrap_or_default();
+
+        Ok(ProbeResult {
+            version: Some(version),
+            response_time,
+            loss_rate,
+            load: Some(NodeLoad::measure(
+                summary,
+                peers_per_network,
+                feature_flag.avoid_relay_data,
+                feature_flag.no_relay_kcp,
+            )),
+            features,
+        })
+    }
+
+    /// 通过 `ListGlobalForeignNetwork` 获取目标节点上每个外部网络的节点数
+    async fn list_foreign_network_peers(
+        inst_id: &uuid::Uuid,
+        instance_mgr: &NetworkInstanceManager,
+        peer_id: u32,
+    ) -> anyhow::Result<Vec<u32>> {
+        let service = instance_mgr
+            .get_instance_service(inst_id)
+            .with_context(|| format!("instance service not found (inst_id: {})", inst_id))?;
+        let resp = service
+            .get_peer_manage_service()
+            .list_global_foreign_network(
+                BaseController::default(),
+                ListGlobalForeignNetworkRequest::default(),
+            )
+            .await?;
+
+        Ok(resp
+            .foreign_networks
+            .get(&peer_id)
+            .map(|networks| {
+                networks
+                    .foreign_networks
+                    .iter()
+                    .map(|network| network.peer_ids.len() as u32)
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// 逐个握手探测节点的监听地址，结果写回 node_listeners，返回检查失败的地址
+    ///
+    /// 每个地址独立探测，探测器按地址缓存，地址列表变化时重建。读取监听地址失败时返回 None。
+    async fn check_listeners(
+        ctx: &CheckTaskContext,
+        node_id: i32,
+        cfg: &TomlConfigLoader,
+        probes: &mut HashMap<String, HandshakeProbe>,
+    ) -> Option<Vec<String>> {
+        let listeners = match ListenerOperations::get_node_listeners(&ctx.db, node_id).await {
+            Ok(listeners) => listeners,
+            Err(e) => {
+                error!("Failed to get listeners for node {}: {}", node_id, e);
+                return None;
+            }
         };
 
-        Ok((version, response_time, conn_count))
+        probes.retain(|url, _| listeners.iter().any(|l| &l.url == url));
+        for listener in &listeners {
+            probes.entry(listener.url.clone()).or_insert_with(|| {
+                HandshakeProbe::for_url(cfg, listener.url.clone(), ctx.handshake_ping_duration)
+            });
+        }
+
+        let probes = &*probes;
+        let results = futures::future::join_all(
+            listeners
+                .iter()
+                .map(|listener| async move { (listener, probes[&listener.url].probe().await) }),
+        )
+        .await;
+
+        let mut failing = Vec::new();
+        for (listener, result) in results {
+            if result.is_err() {
+                failing.push(listener.url.clone());
+            }
+            let (status, response_time, error_message, kind) = match result {
+                Ok(probe_result) => (
+                    HealthStatus::Healthy,
+                    Some(probe_result.response_time as i32),
+                    None,
+                    None,
+                ),
+                Err(e) => {
+                    debug!(
+                        "Listener {} of node {} check failed: {}",
+                        listener.url, node_id, e
+                    );
+                    (
+                        HealthStatus::Unhealthy,
+                        None,
+                        Some(e.to_string()),
+                        Some(error_kind(&e)),
+                    )
+                }
+            };
+            if let Err(e) = ListenerOperations::update_listener_status(
+                &ctx.db,
+                listener.id,
+                status,
+                response_time,
+                error_message,
+                kind,
+            )
+            .await
+            {
+                error!(
+                    "Failed to update listener {} status for node {}: {}",
+                    listener.url, node_id, e
+                );
+            }
+        }
+        Some(failing)
+    }
+
+    /// 检查节点主地址和监听地址中 wss / quic 地址出示的证书，结果写入 node_certificates
+    ///
+    /// 证书状态变为需要关注（过期、即将过期、域名不匹配等）时告警，恢复时记录日志。
+    async fn check_certificates(ctx: &CheckTaskContext, node_id: i32, cfg: &TomlConfigLoader) {
+        let listeners = match ListenerOperations::get_node_listeners(&ctx.db, node_id).await {
+            Ok(listeners) => listeners,
+            Err(e) => {
+                error!("Failed to get listeners for node {}: {}", node_id, e);
+                return;
+            }
+        };
+        let urls = cert_check::tls_urls(
+            cfg.get_peers()
+                .into_iter()
+                .map(|peer| peer.uri.to_string())
+                .chain(listeners.into_iter().map(|l| l.url)),
+        );
+
+        let previous: HashMap<String, CertStatus> =
+            match CertificateOperations::get_node_certificates(&ctx.db, node_id).await {
+                Ok(certs) => certs
+                    .into_iter()
+                    .map(|c| (c.url, CertStatus::from(c.status.as_str())))
+                    .collect(),
+                Err(e) => {
+                    error!("Failed to get certificates for node {}: {}", node_id, e);
+                    return;
+                }
+            };
+        if let Err(e) =
+            CertificateOperations::retain_node_certificates(&ctx.db, node_id, &urls).await
+        {
+            error!(
+                "Failed to clean up certificates for node {}: {}",
+                node_id, e
+            );
+        }
+
+        let results = futures::future::join_all(
+            urls.iter()
+                .map(|url| async move { (url, tls_probe::fetch_certificate(url).await) }),
+        )
+        .await;
+
+        let now = chrono::Utc::now();
+        for (url, result) in results {
+            let status = match &result {
+                Ok(cert) => cert.status(now, ctx.cert_config.expiry_warning),
+                Err(_) => CertStatus::Unavailable,
+            };
+            if previous.get(url) != Some(&status) {
+                let not_after = result.as_ref().ok().map(|cert| cert.not_after);
+                if status.needs_attention() {
+                    warn!(
+                        "Certificate of node {} at {} is {} (expires at {:?})",
+                        node_id, url, status, not_after
+                    );
+                } else {
+                    info!(
+                        "Certificate of node {} at {} is {} (expires at {:?})",
+                        node_id, url, status, not_after
+                    );
+                }
+            }
+
+            let certificate = result.as_ref().map_err(|e| format!("{:#}", e));
+            if let Err(e) =
+                CertificateOperations::record_check(&ctx.db, node_id, url, status, certificate)
+                    .await
+            {
+                error!(
+                    "Failed to record certificate of node {} at {}: {}",
+                    node_id, url, e
+                );
+            }
+        }
     }
 
     async fn node_health_check_task(
         node_id: i32,
-        inst_id: uuid::Uuid,
-        instance_mgr: Arc<NetworkInstanceManager>,
-        db: Db,
-        node_records: Arc<DashMap<i32, HealthyMemRecord>>,
+        probe: NodeProbe,
+        cfg: TomlConfigLoader,
+        ctx: CheckTaskContext,
     ) {
-        /// 记录健康状态到数据库和内存
+        /// 记录健康状态到内存和数据库，并推进节点状态机
+        ///
+        /// 主地址可用但有监听地址失败时，节点状态按降级处理。
         async fn record_health_status(
-            db: &Db,
-            node_records: &Arc<DashMap<i32, HealthyMemRecord>>,
+            ctx: &CheckTaskContext,
             node_id: i32,
             status: HealthStatus,
-            response_time: Option<i32>,
+            probe: Option<&ProbeResult>,
             error_message: Option<String>,
+            error_kind: Option<ProbeErrorKind>,
+            failing_listeners: &[String],
         ) {
+            let response_time = probe.map(|p| p.response_time as i32);
+
+            // 更新内存记录和链路质量窗口
+            let outcome = if status != HealthStatus::Healthy {
+                CheckOutcome::Unhealthy
+            } else if failing_listeners.is_empty() {
+                CheckOutcome::Healthy
+            } else {
+                CheckOutcome::Impaired
+            };
+            let update = |record: &mut HealthyMemRecord| {
+                record.update_health_status(
+                    status.clone(),
+                    response_time,
+                    error_message.clone(),
+                    error_kind,
+                );
+                record.observe_load(probe.and_then(|p| p.load.clone()));
+                match probe {
+                    Some(probe) => {
+                        record.observe_quality(
+                            probe.response_time,
+                            probe.loss_rate as f64,
+                            ctx.quality_window,
+                        );
+                        record.observe_features(probe.features.clone());
+                    }
+                    None => record.observe_failed_check(ctx.quality_window),
+                }
+                (
+                    record.observe_state(outcome, &ctx.state_config),
+                    record.get_link_quality(),
+                )
+            };
+            let (transition, quality) = if let Some(mut record) = ctx.node_records.get_mut(&node_id)
+            {
+                update(&mut record)
+            } else {
+                let mut new_record = HealthyMemRecord::new(node_id);
+                let result = update(&mut new_record);
+                ctx.node_records.insert(node_id, new_record);
+                result
+            };
+
             // 写入数据库
             if let Err(e) = HealthOperations::create_health_record(
-                db,
+                &ctx.db,
                 node_id,
-                status.clone(),
+                status,
                 response_time,
                 error_message.clone(),
+                error_kind,
+                &quality,
             )
             .await
             {
                 error!("Failed to create health record for node {}: {}", node_id, e);
             }
 
-            // 更新内存记录
-            if let Some(mut record) = node_records.get_mut(&node_id) {
-                record.update_health_status(status, response_time, error_message);
-            } else {
-                let mut new_record = HealthyMemRecord::new(node_id);
-                new_record.update_health_status(status, response_time, error_message);
-                node_records.insert(node_id, new_record);
+            // 持久化状态转换
+            let Some(transition) = transition else {
+                return;
+            };
+            info!(
+                "Node {} state changed: {} -> {}",
+                node_id, transition.from, transition.to
+            );
+            let reason = error_message.or_else(|| {
+                (!failing_listeners.is_empty())
+                    .then(|| format!("Listeners failed: {}", failing_listeners.join(", ")))
+            });
+            if let Err(e) = StateEventOperations::record_transition(
+                &ctx.db,
+                node_id,
+                transition.from,
+                transition.to,
+                reason.clone(),
+                transition.at,
+            )
+            .await
+            {
+                error!("Failed to record state event for node {}: {}", node_id, e);
             }
+
+            // 没有订阅者时发送失败，忽略即可
+            let _ = ctx.state_changes.send(NodeStateChange {
+                node_id,
+                transition,
+                reason,
+            });
         }
-        let mut tick = tokio::time::interval(Duration::from_secs(5));
-        let mut counter: u64 = 0;
-        loop {
-            if counter != 0 {
-                tick.tick().await;
-            }
-            counter += 1;
-
-            match Self::test_node_healthy(inst_id, instance_mgr.clone()).await {
-                Ok((version, response_time, conn_count)) => {
-                    if let Err(e) = NodeOperations::update_node_status(
-                        &db,
-                        node_id,
-                        true,
-                        Some(conn_count as i32),
-                    )
-                    .await
-                    {
-                        error!("Failed to update node status for node {}: {}", node_id, e);
-                    }
 
-                    record_health_status(
-                        &db,
-                        &node_records,
-                        node_id,
-                        HealthStatus::Healthy,
-                        Some(response_time as i32),
-                        None,
-                    )
-                    .await;
+        let db = &ctx.db;
+        let base_interval = || {
+            ctx.node_intervals
+                .get(&node_id)
+                .map(|d| *d)
+                .unwrap_or(ctx.interval_config.base)
+        };
+        // 最近一次检查失败的监听地址，由监听地址检查更新，主地址检查沿用
+        let failing_listeners: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        // 监听地址和证书各自按自己的间隔检查，较慢的握手或 TLS 探测不会推迟主地址的检查结果
+        let listener_checks = async {
+            let mut probes = HashMap::new();
+            loop {
+                if let Some(failing) = Self::check_listeners(&ctx, node_id, &cfg, &mut probes).await
+                {
+                    *failing_listeners.lock().unwrap() = failing;
+                }
+                // 监听地址只按基础间隔检查，不随状态确认期加快
+                tokio::time::sleep(base_interval()).await;
+            }
+        };
+        let certificate_checks = async {
+            // 证书很少变化，按单独的间隔检查
+            let interval = ctx.cert_config.interval;
+            if interval.is_zero() {
+                return;
+            }
+            loop {
+                Self::check_certificates(&ctx, node_id, &cfg).await;
+                tokio::time::sleep(interval).await;
+            }
+        };
 
-                    // update node version
-                    if let Err(e) = NodeOperations::update_node_version(&db, node_id, version).await
-                    {
-                        error!("Failed to update node version for node {}: {}", node_id, e);
+        let node_checks = async {
+            let mut confirm_checks = 0;
+            loop {
+                let base = base_interval();
+                let result = match &probe {
+                    NodeProbe::Instance(inst_id) => {
+                        Self::test_node_healthy(*inst_id, ctx.instance_mgr.clone()).await
                     }
-                }
-                Err(e) => {
-                    if let Err(e) =
-                        NodeOperations::update_node_status(&db, node_id, false, None).await
-                    {
-                        error!("Failed to update node status for node {}: {}", node_id, e);
+                    NodeProbe::Handshake(handshake) => handshake.probe().await,
+                };
+                let failing = failing_listeners.lock().unwrap().clone();
+                metrics::global()
+                    .record_check(result.as_ref().err().map(|e| error_kind(e).as_str()));
+
+                match result {
+                    Ok(probe_result) => {
+                        if let Err(e) = NodeOperations::update_node_status(
+                            db,
+                            node_id,
+                            true,
+                            probe_result
+                                .load
+                                .as_ref()
+                                .map(|load| load.foreign_network_count as i32),
+                        )
+                        .await
+                        {
+                            error!("Failed to update node status for node {}: {}", node_id, e);
+                        }
+
+                        record_health_status(
+                            &ctx,
+                            node_id,
+                            HealthStatus::Healthy,
+                            Some(&probe_result),
+                            None,
+                            None,
+                            &failing,
+                        )
+                        .await;
+
+                        // update node version
+                        if let Some(version) = probe_result.version {
+                            if let Err(e) =
+                                NodeOperations::update_node_version(db, node_id, version).await
+                            {
+                                error!("Failed to update node version for node {}: {}", node_id, e);
+                            }
+                        }
                     }
+                    Err(e) => {
+                        if let Err(e) =
+                            NodeOperations::update_node_status(db, node_id, false, None).await
+                        {
+                            error!("Failed to update node status for node {}: {}", node_id, e);
+                        }
+
+                        record_health_status(
+                            &ctx,
+                            node_id,
+                            HealthStatus::Unhealthy,
+                            None,
+                            Some(format!("{}, err: {}", probe, e)),
+                            Some(error_kind(&e)),
+                            &failing,
+                        )
+                        .await;
+                    }
+                }
 
-                    record_health_status(
-                        &db,
-                        &node_records,
-                        node_id,
-                        HealthStatus::Unhealthy,
-                        None,
-                        Some(format!("inst id: {}, err: {}", inst_id, e)),
-                    )
-                    .await;
+                // 根据节点当前状态和待确认的状态转换计算下一次检查的间隔
+                let now = chrono::Utc::now();
+                let (state, state_since, state_pending) = ctx
+                    .node_records
+                    .get(&node_id)
+                    .map(|r| {
+                        (
+                            r.get_node_state(),
+                            r.get_node_state_since(),
+                            r.is_state_pending(),
+                        )
+                    })
+                    .unwrap_or((NodeState::Unknown, now, false));
+                // 快速确认的次数从停留时间结束后、每次出现不一致的检查结果时重新计算
+                let dwell_until = state_since + ctx.state_config.min_dwell;
+                if !state_pending || now < dwell_until {
+                    confirm_checks = 0;
+                }
+                let pending = state_pending.then_some(PendingTransition {
+                    dwell_until,
+                    confirm_checks,
+                });
+                if pending.is_some() && now >= dwell_until {
+                    confirm_checks += 1;
                 }
+                let next =
+                    ctx.interval_config
+                        .next_interval(base, state, state_since, pending, now);
+                tokio::time::sleep(next).await;
             }
-        }
+        };
+
+        tokio::join!(node_checks, listener_checks, certificate_checks);
     }
 }
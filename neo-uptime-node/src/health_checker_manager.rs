@@ -1,12 +1,14 @@
 use std::{collections::HashSet, sync::Arc, time::Duration};
 
 use anyhow::Context as _;
-use tokio::time::{interval, Interval};
+use tokio::sync::watch;
 use tracing::{error, info};
 
 use crate::{
+    config::{ReloadableInterval, RuntimeSettings},
     db::{entity::shared_nodes, operations::NodeOperations, Db},
     health_checker::HealthChecker,
+    tag_filter::TagFilter,
 };
 
 /// HealthChecker的封装器，用于监控数据库中节点的添加和删除
@@ -14,7 +16,8 @@ pub struct HealthCheckerManager {
     health_checker: Arc<HealthChecker>,
     db: Db,
     current_nodes: Arc<tokio::sync::RwLock<HashSet<i32>>>,
-    monitor_interval: Duration,
+    settings: watch::Receiver<RuntimeSettings>,
+    monitor_task: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
 }
 
 impl HealthCheckerManager {
@@ -24,13 +27,14 @@ impl HealthCheckerManager {
             health_checker,
             db,
             current_nodes: Arc::new(tokio::sync::RwLock::new(HashSet::new())),
-            monitor_interval: Duration::from_secs(1), // 默认每1秒检查一次
+            settings: watch::channel(RuntimeSettings::default()).1,
+            monitor_task: std::sync::Mutex::new(None),
         }
     }
 
-    /// 设置监控间隔
-    pub fn with_monitor_interval(mut self, interval: Duration) -> Self {
-        self.monitor_interval = interval;
+    /// 设置监控间隔和标签过滤条件的来源，配置重新加载后立即生效
+    pub fn with_settings(mut self, settings: watch::Receiver<RuntimeSettings>) -> Self {
+        self.settings = settings;
         self
     }
 
@@ -40,34 +44,56 @@ impl HealthCheckerManager {
         let health_checker = Arc::clone(&self.health_checker);
         let db = self.db.clone();
         let current_nodes = Arc::clone(&self.current_nodes);
-        let monitor_interval = self.monitor_interval;
+        let settings = self.settings.clone();
 
-        tokio::spawn(async move {
-            let mut ticker = interval(monitor_interval);
+        let task = tokio::spawn(async move {
+            let mut ticker = ReloadableInterval::new("Node monitor", settings.clone(), |s| {
+                s.node_monitor_interval
+            });
             loop {
-                if let Err(e) = Self::check_node_changes(&health_checker, &db, &current_nodes).await
+                let tag_filter = settings.borrow().tag_filter.clone();
+                if let Err(e) =
+                    Self::check_node_changes(&health_checker, &db, &current_nodes, &tag_filter)
+                        .await
                 {
                     tracing::error!("Error checking node changes: {}", e);
                 }
                 ticker.tick().await;
             }
         });
+        if let Some(previous) = self.monitor_task.lock().unwrap().replace(task) {
+            previous.abort();
+        }
 
         Ok(())
     }
 
+    /// 停止监控任务，不再添加或移除节点
+    pub fn stop_monitoring(&self) {
+        if let Some(task) = self.monitor_task.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+
     /// 检查节点变化并更新监控
     async fn check_node_changes(
         health_checker: &Arc<HealthChecker>,
         db: &Db,
         current_nodes: &Arc<tokio::sync::RwLock<HashSet<i32>>>,
+        tag_filter: &TagFilter,
     ) -> anyhow::Result<()> {
-        // 获取数据库中当前的所有节点
-        let db_nodes = NodeOperations::get_all_nodes(db)
+        // 获取数据库中当前需要监控的节点，已停用的节点视为删除
+        let db_nodes = NodeOperations::get_monitored_nodes(db)
             .await
-            .with_context(|| "Failed to get all nodes from database")?;
+            .with_context(|| "Failed to get monitored nodes from database")?;
 
-        let db_node_ids: HashSet<i32> = db_nodes.iter().map(|node| node.id).collect();
+        let mut db_node_ids: HashSet<i32> = db_nodes.iter().map(|node| node.id).collect();
+
+        // 不匹配标签过滤条件的节点不监控
+        let filtered_out = tag_filter
+            .retain_matching(db, &mut db_node_ids)
+            .await
+            .with_context(|| "Failed to filter nodes by tags")?;
 
         let mut current_nodes_guard = current_nodes.write().await;
 
@@ -89,10 +115,30 @@ impl HealthCheckerManager {
             }
         }
 
+        // 不匹配标签过滤条件的节点丢弃内存记录，不再上报，
+        // 包括启动时加载了历史记录但尚未开始监控的节点
+        for &node_id in &filtered_out {
+            if !current_nodes_guard.contains(&node_id) && !health_checker.has_node_record(node_id) {
+                continue;
+            }
+            if let Err(e) = health_checker.forget_node(node_id).await {
+                error!(
+                    "Failed to remove node {} from health checker: {}",
+                    node_id, e
+                );
+                continue;
+            }
+            current_nodes_guard.remove(&node_id);
+            info!(
+                "Node {} does not match the tag filter, stopped monitoring",
+                node_id
+            );
+        }
+
         // 检查删除的节点
         let nodes_to_remove: Vec<i32> = current_nodes_guard
             .iter()
-            .filter(|&&node_id| !db_node_ids.contains(&node_id))
+            .filter(|&&node_id| !db_node_ids.contains(&node_id) && !filtered_out.contains(&node_id))
             .copied()
             .collect();
 
@@ -114,7 +160,14 @@ impl HealthCheckerManager {
 
     /// 手动触发节点变化检查
     pub async fn refresh_nodes(&self) -> anyhow::Result<()> {
-        Self::check_node_changes(&self.health_checker, &self.db, &self.current_nodes).await
+        let tag_filter = self.settings.borrow().tag_filter.clone();
+        Self::check_node_changes(
+            &self.health_checker,
+            &self.db,
+            &self.current_nodes,
+            &tag_filter,
+        )
+        .await
     }
 
     /// 获取当前监控的节点数量
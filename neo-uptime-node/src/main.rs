@@ -7,36 +7,85 @@
 //! - Communicates only via HTTP API (no local database dependency)
 
 mod backend_client;
+mod cert_check;
+mod check_schedule;
+mod cli;
 mod config;
 mod db;
+#[cfg(test)]
+mod e2e_tests;
+mod handshake_probe;
 mod health_checker;
 mod health_checker_manager;
+mod metrics;
 mod migrator;
 mod models;
+mod node_load;
+mod node_state;
+mod node_version;
+mod notifier;
+mod outbox;
+mod probe_backend;
+mod probe_error;
+mod quality;
+mod sharding;
+mod sla;
+mod status_server;
+mod tag_filter;
+mod tls_probe;
 
 use anyhow::{Context, Result};
-use clap::Parser;
-use config::{AppConfig, DistributedConfig};
+use cert_check::CertCheckConfig;
+use check_schedule::CheckIntervalConfig;
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser};
+use config::{ConfigFile, ReloadableInterval, RuntimeSettings};
 use dashmap::DashMap;
-use db::Db;
+use db::cleanup::{CleanupConfig, CleanupManager};
+use db::{CertStatus, Db, HealthStatus, LinkQuality, NodeState, ProbeErrorKind};
+use easytier::common::config::{ConsoleLoggerConfig, FileLoggerConfig, LoggingConfig};
 use easytier::utils::init_logger;
-use health_checker::HealthChecker;
+use health_checker::{HealthChecker, ProbeMode};
 use health_checker_manager::HealthCheckerManager;
 use mimalloc::MiMalloc;
-use std::collections::HashMap;
+use node_state::StateMachineConfig;
+use node_version::NodeVersion;
+use notifier::{MuteWindow, Notifier, NotifierConfig};
+use outbox::{HeartbeatOutbox, OutboxConfig};
+use probe_backend::{FileBackend, ProbeBackend};
+use sharding::{ShardAssignment, ShardConfig, ShardRole};
+use status_server::StatusServer;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::process::ExitCode;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::time::interval;
+use std::time::{Duration, Instant};
+use tag_filter::{TagExpr, TagFilter};
+use tokio::sync::watch;
 use tracing::{debug, error, info, warn};
 
-use backend_client::{BackendClient, BackendPeer};
-use db::entity::shared_nodes;
-use db::operations::NodeOperations;
+use backend_client::{
+    BackendClient, BackendPeer, CertificateHeartbeat, HeartbeatItem, HeartbeatQuality,
+    ListenerHeartbeat, ProbeRegistration,
+};
+use db::entity::{node_certificates, node_listeners};
+use db::operations::{CertificateOperations, ListenerOperations, NodeOperations};
 use sea_orm::{ActiveModelTrait, IntoActiveModel, Set};
 
 /// Global mapping of local node ID to backend peer metadata
 type PeerMetadataMap = Arc<DashMap<i32, BackendPeer>>;
 
+/// How long nodes that disappear from the backend peer list are kept
+#[derive(Debug, Clone, Copy)]
+struct NodeRetention {
+    /// Missing for longer than this, the node stops being monitored
+    grace_period: chrono::Duration,
+    /// Deactivated for longer than this, the node and its history are deleted
+    purge_after: chrono::Duration,
+}
+
+/// How often the config file is checked for changes
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
 #[global_allocator]
 static GLOBAL_MIMALLOC: MiMalloc = MiMalloc;
 
@@ -49,18 +98,38 @@ static GLOBAL_MIMALLOC: MiMalloc = MiMalloc;
     long_about = "A standalone probe that monitors EasyTier peers and reports status to a central backend"
 )]
 struct Args {
+    /// Run a one-shot command instead of the probe
+    #[command(subcommand)]
+    command: Option<cli::Command>,
+
+    /// TOML config file; options given on the command line or through the environment take precedence
+    #[arg(long, env = "CONFIG_FILE")]
+    config: Option<PathBuf>,
+
     /// Backend base URL (e.g., https://backend.example.com)
-    #[arg(long, env = "BACKEND_BASE_URL", required = true)]
-    backend_base_url: String,
+    #[arg(long, env = "BACKEND_BASE_URL")]
+    backend_base_url: Option<String>,
 
     /// API key for authentication with backend
-    #[arg(long, env = "API_KEY", required = true)]
-    api_key: String,
+    #[arg(long, env = "API_KEY")]
+    api_key: Option<String>,
+
+    /// Read peers from a local JSON or TOML file instead of the backend
+    #[arg(long, env = "PEERS_FILE", conflicts_with = "backend_base_url")]
+    peers_file: Option<PathBuf>,
+
+    /// File heartbeats are appended to (JSON lines) when using a peers file
+    #[arg(long, env = "REPORT_FILE", default_value = "neo-uptime-reports.jsonl")]
+    report_file: PathBuf,
 
     /// Region identifier (optional)
     #[arg(long, env = "REGION")]
     region: Option<String>,
 
+    /// Name the probe registers with (a generated name is kept in the database if unset)
+    #[arg(long, env = "PROBE_NAME")]
+    probe_name: Option<String>,
+
     /// Peer fetch interval in seconds
     #[arg(long, env = "PEER_FETCH_INTERVAL", default_value = "60")]
     peer_fetch_interval: u64,
@@ -69,36 +138,485 @@ struct Args {
     #[arg(long, env = "STATUS_REPORT_INTERVAL", default_value = "30")]
     status_report_interval: u64,
 
-    /// Health check interval in seconds (per peer)
+    /// Interval in seconds for reporting per-node SLA to the backend, 0 disables it
+    #[arg(long, env = "SLA_REPORT_INTERVAL", default_value = "3600")]
+    sla_report_interval: u64,
+
+    /// Health check interval in seconds (per peer, unless the backend sets one)
     #[arg(long, env = "HEALTH_CHECK_INTERVAL", default_value = "5")]
     health_check_interval: u64,
 
+    /// Maximum health check interval in seconds for nodes that have been down for long
+    #[arg(long, env = "HEALTH_CHECK_MAX_INTERVAL", default_value = "300")]
+    health_check_max_interval: u64,
+
+    /// Seconds a node must be down before its check interval starts backing off
+    #[arg(long, env = "HEALTH_CHECK_BACKOFF_AFTER", default_value = "600")]
+    health_check_backoff_after: i64,
+
+    /// Interval in seconds for the checks confirming a pending state change
+    #[arg(long, env = "HEALTH_CHECK_CONFIRM_INTERVAL", default_value = "1")]
+    health_check_confirm_interval: u64,
+
+    /// Probes monitoring each node within a region (primary + backups)
+    #[arg(long, env = "SHARD_REPLICAS", default_value = "2")]
+    shard_replicas: usize,
+
+    /// Seconds without activity after which another probe's nodes are taken over
+    #[arg(long, env = "PROBE_STALE_AFTER", default_value = "180")]
+    probe_stale_after: i64,
+
+    /// Seconds a backend node may be missing from the peer list before it stops being monitored
+    #[arg(long, env = "MISSING_NODE_GRACE_PERIOD", default_value = "3600")]
+    missing_node_grace_period: i64,
+
+    /// Days a deactivated node and its history are kept before being deleted
+    #[arg(long, env = "DEACTIVATED_NODE_RETENTION_DAYS", default_value = "30")]
+    deactivated_node_retention_days: i64,
+
+    /// Interval in seconds for picking up added and removed nodes from the database
+    #[arg(long, env = "NODE_MONITOR_INTERVAL", default_value = "5")]
+    node_monitor_interval: u64,
+
+    /// Only monitor nodes matching one of these tag expressions (comma separated, `a+b` requires both)
+    #[arg(long, env = "INCLUDE_TAGS", value_delimiter = ',')]
+    include_tags: Vec<TagExpr>,
+
+    /// Never monitor nodes matching one of these tag expressions
+    #[arg(long, env = "EXCLUDE_TAGS", value_delimiter = ',')]
+    exclude_tags: Vec<TagExpr>,
+
+    /// Flag nodes running an EasyTier version older than this one (e.g. 2.4.0) as outdated
+    #[arg(long, env = "MIN_NODE_VERSION")]
+    min_node_version: Option<NodeVersion>,
+
+    /// How nodes are probed: a full network instance per node, or a lightweight handshake
+    #[arg(long, env = "PROBE_MODE", value_enum, default_value = "instance")]
+    probe_mode: ProbeMode,
+
+    /// Seconds a handshake probe keeps pinging the node to measure RTT
+    #[arg(long, env = "HANDSHAKE_PING_DURATION", default_value = "3")]
+    handshake_ping_duration: u64,
+
+    /// Number of recent checks used for loss rate, jitter and latency percentiles
+    #[arg(long, env = "QUALITY_WINDOW", default_value = "60")]
+    quality_window: usize,
+
+    /// Interval in seconds for checking TLS certificates of wss and quic addresses (0 disables)
+    #[arg(long, env = "CERT_CHECK_INTERVAL", default_value = "21600")]
+    cert_check_interval: u64,
+
+    /// Days before expiry at which a certificate is reported as expiring
+    #[arg(long, env = "CERT_EXPIRY_WARNING_DAYS", default_value = "14")]
+    cert_expiry_warning_days: u64,
+
     /// Database path for local caching (optional)
     #[arg(long, env = "DATABASE_PATH", default_value = "neo-uptime-node.db")]
     database_path: String,
+
+    /// Maximum age in hours of undelivered heartbeats kept for replay
+    #[arg(long, env = "OUTBOX_MAX_AGE_HOURS", default_value = "24")]
+    outbox_max_age_hours: i64,
+
+    /// Maximum number of undelivered heartbeats kept for replay
+    #[arg(long, env = "OUTBOX_MAX_ENTRIES", default_value = "50000")]
+    outbox_max_entries: u64,
+
+    /// Days to keep raw health records (older records survive as hourly rollups)
+    #[arg(long, env = "HEALTH_RECORD_RETENTION_DAYS", default_value = "30")]
+    health_record_retention_days: i64,
+
+    /// Maximum number of raw health records kept per node
+    #[arg(long, env = "MAX_HEALTH_RECORDS_PER_NODE", default_value = "70000")]
+    max_health_records_per_node: u64,
+
+    /// Days to keep hourly health rollups
+    #[arg(long, env = "ROLLUP_RETENTION_DAYS", default_value = "365")]
+    rollup_retention_days: i64,
+
+    /// Database cleanup interval in seconds
+    #[arg(long, env = "CLEANUP_INTERVAL", default_value = "1200")]
+    cleanup_interval: u64,
+
+    /// Consecutive failed checks before a node is considered down
+    #[arg(long, env = "STATE_FAILURE_THRESHOLD", default_value = "3")]
+    state_failure_threshold: u32,
+
+    /// Consecutive successful checks before a degraded or down node is considered up again
+    #[arg(long, env = "STATE_RECOVERY_THRESHOLD", default_value = "2")]
+    state_recovery_threshold: u32,
+
+    /// Minimum time in seconds a node stays in a state before it can change again
+    #[arg(long, env = "STATE_MIN_DWELL", default_value = "30")]
+    state_min_dwell: i64,
+
+    /// Webhook URLs notified on node state changes (comma separated)
+    #[arg(long, env = "WEBHOOK_URLS", value_delimiter = ',')]
+    webhook_urls: Vec<String>,
+
+    /// Secret used to sign webhook payloads (HMAC-SHA256)
+    #[arg(long, env = "WEBHOOK_SECRET")]
+    webhook_secret: Option<String>,
+
+    /// Daily per-node mute windows in UTC, e.g. "12@01:00-03:00" (comma separated)
+    #[arg(long, env = "WEBHOOK_MUTE", value_delimiter = ',')]
+    webhook_mute: Vec<MuteWindow>,
+
+    /// Seconds to collect state changes before notifying
+    #[arg(long, env = "WEBHOOK_BATCH_WINDOW", default_value = "10")]
+    webhook_batch_window: u64,
+
+    /// State changes per batch above which a single digest is sent instead
+    #[arg(long, env = "WEBHOOK_DIGEST_THRESHOLD", default_value = "5")]
+    webhook_digest_threshold: usize,
+
+    /// Also notify transitions between up and degraded
+    #[arg(long, env = "WEBHOOK_NOTIFY_DEGRADED")]
+    webhook_notify_degraded: bool,
+
+    /// Address the local status server listens on
+    #[arg(long, env = "SERVER_HOST", default_value = "127.0.0.1")]
+    server_host: IpAddr,
+
+    /// Port the local status server listens on
+    #[arg(long, env = "SERVER_PORT", default_value = "8080")]
+    server_port: u16,
+
+    /// Log level for the console and the log file
+    #[arg(long, env = "LOG_LEVEL", default_value = "info")]
+    log_level: String,
+
+    /// Log file path
+    #[arg(long, env = "LOG_FILE", default_value = "easytier-uptime.log")]
+    log_file: String,
+
+    /// Seconds allowed for the final heartbeat flush and cleanup on shutdown
+    #[arg(long, env = "SHUTDOWN_TIMEOUT", default_value = "30")]
+    shutdown_timeout: u64,
+}
+
+/// Copy values from the config file into `Args`, skipping options given explicitly
+macro_rules! apply_config_file {
+    ($args:ident, $matches:ident, { $($value:expr => $field:ident),* $(,)? }) => {
+        $(
+            if let Some(value) = $value {
+                let explicit = matches!(
+                    $matches.value_source(stringify!($field)),
+                    Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+                );
+                if !explicit {
+                    $args.$field = value.into();
+                }
+            }
+        )*
+    };
+}
+
+impl Args {
+    /// Build the arguments from the command line and environment, filling the remaining
+    /// options from the config file if one is given
+    fn load(matches: &ArgMatches) -> Result<Self> {
+        let mut args = Self::from_arg_matches(matches)?;
+        if let Some(path) = &args.config {
+            let file = ConfigFile::load(path)?;
+            args.apply_config_file(file, matches);
+        }
+        Ok(args)
+    }
+
+    fn apply_config_file(&mut self, file: ConfigFile, matches: &ArgMatches) {
+        let args = self;
+        apply_config_file!(args, matches, {
+            file.backend.base_url => backend_base_url,
+            file.backend.api_key => api_key,
+            file.backend.peers_file => peers_file,
+            file.backend.report_file => report_file,
+            file.backend.region => region,
+            file.backend.probe_name => probe_name,
+            file.server.host => server_host,
+            file.server.port => server_port,
+            file.database.path => database_path,
+            file.logging.level => log_level,
+            file.logging.file => log_file,
+            file.shutdown.timeout => shutdown_timeout,
+            file.intervals.peer_fetch => peer_fetch_interval,
+            file.intervals.status_report => status_report_interval,
+            file.intervals.sla_report => sla_report_interval,
+            file.intervals.node_monitor => node_monitor_interval,
+            file.intervals.cleanup => cleanup_interval,
+            file.health_check.interval => health_check_interval,
+            file.health_check.max_interval => health_check_max_interval,
+            file.health_check.backoff_after => health_check_backoff_after,
+            file.health_check.confirm_interval => health_check_confirm_interval,
+            file.health_check.probe_mode => probe_mode,
+            file.health_check.handshake_ping_duration => handshake_ping_duration,
+            file.health_check.quality_window => quality_window,
+            file.health_check.cert_check_interval => cert_check_interval,
+            file.health_check.cert_expiry_warning_days => cert_expiry_warning_days,
+            file.state.failure_threshold => state_failure_threshold,
+            file.state.recovery_threshold => state_recovery_threshold,
+            file.state.min_dwell => state_min_dwell,
+            file.sharding.replicas => shard_replicas,
+            file.sharding.stale_after => probe_stale_after,
+            file.retention.health_record_days => health_record_retention_days,
+            file.retention.max_health_records_per_node => max_health_records_per_node,
+            file.retention.rollup_days => rollup_retention_days,
+            file.retention.missing_node_grace_period => missing_node_grace_period,
+            file.retention.deactivated_node_days => deactivated_node_retention_days,
+            file.retention.outbox_max_age_hours => outbox_max_age_hours,
+            file.retention.outbox_max_entries => outbox_max_entries,
+            file.tags.include => include_tags,
+            file.tags.exclude => exclude_tags,
+            file.versions.minimum => min_node_version,
+            file.notifier.webhook_urls => webhook_urls,
+            file.notifier.secret => webhook_secret,
+            file.notifier.mute => webhook_mute,
+            file.notifier.batch_window => webhook_batch_window,
+            file.notifier.digest_threshold => webhook_digest_threshold,
+            file.notifier.notify_degraded => webhook_notify_degraded,
+        });
+    }
+
+    /// Check the settings the probe needs before anything is started
+    fn validate(&self) -> Result<()> {
+        match (&self.backend_base_url, &self.peers_file) {
+            (Some(_), Some(_)) => {
+                anyhow::bail!("backend base URL and peers file are mutually exclusive")
+            }
+            (None, None) => anyhow::bail!("either a backend base URL or a peers file is required"),
+            (Some(_), None) if self.api_key.is_none() => {
+                anyhow::bail!("an API key is required with the backend base URL")
+            }
+            _ => {}
+        }
+
+        let positive = [
+            ("peer fetch interval", self.peer_fetch_interval),
+            ("status report interval", self.status_report_interval),
+            ("node monitor interval", self.node_monitor_interval),
+            ("health check interval", self.health_check_interval),
+            ("cleanup interval", self.cleanup_interval),
+            ("quality window", self.quality_window as u64),
+            ("shard replicas", self.shard_replicas as u64),
+            ("outbox max entries", self.outbox_max_entries),
+            (
+                "state failure threshold",
+                self.state_failure_threshold as u64,
+            ),
+            (
+                "state recovery threshold",
+                self.state_recovery_threshold as u64,
+            ),
+        ];
+        for (name, value) in positive {
+            anyhow::ensure!(value > 0, "{} must be greater than 0", name);
+        }
+        anyhow::ensure!(
+            self.health_check_max_interval >= self.health_check_interval,
+            "health check max interval must not be less than the health check interval"
+        );
+        anyhow::ensure!(
+            self.probe_stale_after > 0,
+            "probe stale after must be greater than 0"
+        );
+
+        let non_negative = [
+            (
+                "health check backoff after",
+                self.health_check_backoff_after,
+            ),
+            ("missing node grace period", self.missing_node_grace_period),
+            ("state min dwell", self.state_min_dwell),
+        ];
+        for (name, value) in non_negative {
+            anyhow::ensure!(value >= 0, "{} must not be negative", name);
+        }
+
+        let retention_days = [
+            ("health record retention", self.health_record_retention_days),
+            ("rollup retention", self.rollup_retention_days),
+            (
+                "deactivated node retention",
+                self.deactivated_node_retention_days,
+            ),
+        ];
+        for (name, days) in retention_days {
+            anyhow::ensure!(days > 0, "{} must be at least one day", name);
+        }
+        anyhow::ensure!(
+            self.outbox_max_age_hours > 0,
+            "outbox max age must be at least one hour"
+        );
+
+        for url in &self.webhook_urls {
+            anyhow::ensure!(
+                url.starts_with("http://") || url.starts_with("https://"),
+                "webhook URL '{}' must start with http:// or https://",
+                url
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Settings that can change while the probe is running
+    fn runtime_settings(&self) -> RuntimeSettings {
+        RuntimeSettings {
+            peer_fetch_interval: Duration::from_secs(self.peer_fetch_interval),
+            status_report_interval: Duration::from_secs(self.status_report_interval),
+            sla_report_interval: Duration::from_secs(self.sla_report_interval),
+            node_monitor_interval: Duration::from_secs(self.node_monitor_interval),
+            tag_filter: TagFilter {
+                include: self.include_tags.clone(),
+                exclude: self.exclude_tags.clone(),
+            },
+            min_node_version: self.min_node_version.clone(),
+        }
+    }
+
+    fn logging_config(&self) -> LoggingConfig {
+        LoggingConfig {
+            file_logger: Some(FileLoggerConfig {
+                level: Some(self.log_level.clone()),
+                file: Some(self.log_file.clone()),
+                ..Default::default()
+            }),
+            console_logger: Some(ConsoleLoggerConfig {
+                level: Some(self.log_level.clone()),
+            }),
+        }
+    }
 }
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 4)]
-async fn main() -> Result<()> {
-    // Parse command line arguments
-    let args = Args::parse();
+async fn main() -> Result<ExitCode> {
+    // Parse command line arguments and the config file
+    let matches = Args::command().get_matches();
+    let mut args = Args::load(&matches)?;
+
+    // One-shot commands print their own output, without the probe logs
+    if let Some(command) = args.command.take() {
+        return cli::run(command, &args.database_path).await;
+    }
+    args.validate().context("Invalid configuration")?;
 
     // Initialize logger
-    let mut config = AppConfig::default();
-    let _ = init_logger(&config.logging, false);
+    let _ = init_logger(&args.logging_config(), false);
 
     info!("Starting neo-uptime-node v{}", env!("CARGO_PKG_VERSION"));
-    info!("Backend URL: {}", args.backend_base_url);
+    if let Some(config) = &args.config {
+        info!("Config file: {}", config.display());
+    }
+    match &args.peers_file {
+        Some(peers_file) => info!(
+            "Peers file: {}, reports: {}",
+            peers_file.display(),
+            args.report_file.display()
+        ),
+        None => info!("Backend URL: {}", args.backend_base_url.as_deref().unwrap_or_default()),
+    }
     info!("Region: {:?}", args.region);
     info!("Peer fetch interval: {}s", args.peer_fetch_interval);
     info!("Status report interval: {}s", args.status_report_interval);
+    info!("Probe mode: {:?}", args.probe_mode);
+    if !args.include_tags.is_empty() || !args.exclude_tags.is_empty() {
+        info!(
+            "Tag filter: include {:?}, exclude {:?}",
+            args.include_tags
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            args.exclude_tags
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    // Intervals, tag filters and the minimum node version follow the config file while running
+    let settings = match args.config.clone() {
+        Some(path) => config::watch_config_file(
+            path,
+            CONFIG_POLL_INTERVAL,
+            args.runtime_settings(),
+            move || {
+                let args = Args::load(&matches)?;
+                args.validate()?;
+                Ok(args.runtime_settings())
+            },
+        ),
+        None => watch::channel(args.runtime_settings()).1,
+    };
 
     // Create database connection for local caching
     let db = Db::new(&args.database_path).await?;
     info!("Database initialized at: {}", args.database_path);
 
+    // Start periodic rollup and cleanup of health history
+    let cleanup_manager = CleanupManager::new(
+        db.clone(),
+        CleanupConfig {
+            health_record_retention_days: args.health_record_retention_days,
+            max_health_records_per_node: args.max_health_records_per_node,
+            cleanup_interval_seconds: args.cleanup_interval,
+            auto_cleanup_enabled: true,
+            rollup_enabled: true,
+            rollup_retention_days: args.rollup_retention_days,
+        },
+    );
+    cleanup_manager
+        .start_auto_cleanup()
+        .await
+        .context("Failed to start database cleanup")?;
+    info!(
+        "Database cleanup started: raw records kept {} days, rollups kept {} days",
+        args.health_record_retention_days, args.rollup_retention_days
+    );
+
     // Create health checker
-    let health_checker = Arc::new(HealthChecker::new(db.clone()));
+    let health_checker = Arc::new(
+        HealthChecker::new(db.clone())
+            .with_state_machine_config(StateMachineConfig {
+                failure_threshold: args.state_failure_threshold,
+                recovery_threshold: args.state_recovery_threshold,
+                min_dwell: chrono::Duration::seconds(args.state_min_dwell),
+            })
+            .with_check_interval_config(CheckIntervalConfig {
+                base: Duration::from_secs(args.health_check_interval.max(1)),
+                max: Duration::from_secs(args.health_check_max_interval),
+                confirm: Duration::from_secs(args.health_check_confirm_interval.max(1)),
+                backoff_after: chrono::Duration::seconds(args.health_check_backoff_after),
+                ..Default::default()
+            })
+            .with_quality_window(args.quality_window)
+            .with_probe_mode(args.probe_mode)
+            .with_handshake_ping_duration(Duration::from_secs(args.handshake_ping_duration))
+            .with_cert_check_config(CertCheckConfig {
+                interval: Duration::from_secs(args.cert_check_interval),
+                expiry_warning: Duration::from_secs(args.cert_expiry_warning_days * 86400),
+            }),
+    );
+
+    // Start webhook notifier for node state changes
+    if !args.webhook_urls.is_empty() {
+        let notifier = Notifier::new(
+            db.clone(),
+            NotifierConfig {
+                webhook_urls: args.webhook_urls.clone(),
+                secret: args.webhook_secret.clone(),
+                mute_windows: args.webhook_mute.clone(),
+                batch_window: Duration::from_secs(args.webhook_batch_window),
+                digest_threshold: args.webhook_digest_threshold,
+                notify_degraded: args.webhook_notify_degraded,
+                ..Default::default()
+            },
+        )?;
+        notifier.start(health_checker.subscribe_state_changes());
+        info!(
+            "Webhook notifier started for {} URLs",
+            args.webhook_urls.len()
+        );
+    }
 
     // Load existing health records from database
     health_checker
@@ -108,7 +626,7 @@ async fn main() -> Result<()> {
 
     // Start health checker manager
     let health_checker_manager = HealthCheckerManager::new(health_checker.clone(), db.clone())
-        .with_monitor_interval(Duration::from_secs(args.health_check_interval));
+        .with_settings(settings.clone());
 
     health_checker_manager
         .start_monitoring()
@@ -116,96 +634,268 @@ async fn main() -> Result<()> {
         .context("Failed to start health checker manager")?;
     info!("Health checker manager started");
 
-    // Create backend client
-    let backend_client = Arc::new(
-        BackendClient::new(
-            args.backend_base_url.clone(),
-            Some(args.api_key.clone()),
-        )
-        .context("Failed to create backend client")?,
-    );
+    // Start local status server (/healthz, /nodes, /metrics)
+    let status_server_handle = StatusServer::bind(
+        SocketAddr::new(args.server_host, args.server_port),
+        db.clone(),
+        health_checker.clone(),
+        settings.clone(),
+    )
+    .await?
+    .start();
 
-    // Test backend connection
-    backend_client
-        .test_connection()
+    // Create backend: a local peers file, or the HTTP backend
+    let backend: Arc<dyn ProbeBackend> = match &args.peers_file {
+        Some(peers_file) => Arc::new(FileBackend::new(peers_file, &args.report_file)),
+        None => Arc::new(
+            BackendClient::new(
+                args.backend_base_url.clone().unwrap_or_default(),
+                args.api_key.clone(),
+            )
+            .context("Failed to create backend client")?
+            .with_registration(
+                db.clone(),
+                ProbeRegistration {
+                    name: args.probe_name.clone(),
+                    region: args.region.clone(),
+                },
+            ),
+        ),
+    };
+
+    // Register probe with the backend
+    backend
+        .register_probe()
         .await
-        .context("Failed to connect to backend")?;
+        .context("Failed to register probe with backend")?;
     info!("Backend connection successful");
 
     // Create peer metadata map for tracking backend peer information
     let peer_metadata: PeerMetadataMap = Arc::new(DashMap::new());
 
-    // Build distributed config
-    let distributed_config = DistributedConfig {
-        enabled: true,
-        backend_base_url: Some(args.backend_base_url),
-        api_key: Some(args.api_key),
-        region: args.region.clone(),
-        peer_fetch_interval: args.peer_fetch_interval,
-        status_report_interval: args.status_report_interval,
-    };
-
     // Start peer fetch task
-    let peer_fetch_handle = start_peer_fetch_task(
-        backend_client.clone(),
+    let mut peer_fetch_handle = start_peer_fetch_task(
+        backend.clone(),
         db.clone(),
         health_checker.clone(),
         peer_metadata.clone(),
-        distributed_config.clone(),
+        args.region.clone(),
+        settings.clone(),
+        NodeRetention {
+            grace_period: chrono::Duration::seconds(args.missing_node_grace_period),
+            purge_after: chrono::Duration::days(args.deactivated_node_retention_days),
+        },
+        ShardConfig {
+            replicas: args.shard_replicas,
+            stale_after: chrono::Duration::seconds(args.probe_stale_after),
+        },
     );
 
+    // Start outbox replay task for heartbeats that could not be delivered
+    let outbox = Arc::new(HeartbeatOutbox::new(
+        db.clone(),
+        OutboxConfig {
+            max_age: chrono::Duration::hours(args.outbox_max_age_hours),
+            max_entries: args.outbox_max_entries,
+            ..Default::default()
+        },
+    ));
+    let mut outbox_replay_handle = outbox.start_replay_task(backend.clone());
+
     // Start status report task
-    let status_report_handle = start_status_report_task(
-        backend_client.clone(),
+    let mut status_report_handle = start_status_report_task(
+        backend.clone(),
         db.clone(),
         health_checker.clone(),
         peer_metadata.clone(),
-        distributed_config,
+        outbox.clone(),
+        settings.clone(),
     );
 
+    // Start SLA report task
+    let mut sla_report_handle =
+        start_sla_report_task(backend.clone(), db.clone(), settings.clone());
+
     // Wait for shutdown signal
     tokio::select! {
-        _ = tokio::signal::ctrl_c() => {
+        _ = shutdown_signal() => {
             info!("Received shutdown signal");
         }
-        _ = peer_fetch_handle => {
+        _ = &mut peer_fetch_handle => {
             error!("Peer fetch task completed unexpectedly");
         }
-        _ = status_report_handle => {
+        _ = &mut status_report_handle => {
             error!("Status report task completed unexpectedly");
         }
+        _ = &mut outbox_replay_handle => {
+            error!("Outbox replay task completed unexpectedly");
+        }
+        _ = &mut sla_report_handle => {
+            error!("SLA report task completed unexpectedly");
+        }
+        res = status_server_handle => {
+            error!("Status server completed unexpectedly: {:?}", res);
+        }
     }
 
     info!("Shutting down gracefully...");
-    Ok(())
+    for handle in [
+        &peer_fetch_handle,
+        &status_report_handle,
+        &outbox_replay_handle,
+        &sla_report_handle,
+    ] {
+        handle.abort();
+    }
+    cleanup_manager.stop_auto_cleanup();
+
+    // A second signal skips whatever is left of the shutdown
+    let shutdown_timeout = Duration::from_secs(args.shutdown_timeout);
+    let min_node_version = settings.borrow().min_node_version.clone();
+    let graceful = tokio::time::timeout(
+        shutdown_timeout,
+        shutdown(
+            &health_checker_manager,
+            &health_checker,
+            backend.as_ref(),
+            &db,
+            &peer_metadata,
+            &outbox,
+            min_node_version.as_ref(),
+        ),
+    );
+    tokio::select! {
+        res = graceful => match res {
+            Ok(()) => info!("Shutdown complete"),
+            Err(_) => warn!("Shutdown did not complete within {:?}, exiting", shutdown_timeout),
+        },
+        _ = shutdown_signal() => {
+            warn!("Received another shutdown signal, exiting immediately");
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Resolves on Ctrl-C, or on SIGTERM as sent by container runtimes
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+                return;
+            }
+            Err(e) => warn!("Failed to listen for SIGTERM: {}", e),
+        }
+    }
+
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        error!("Failed to listen for Ctrl-C: {}", e);
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Stop probing, hand the final state to the backend and leave no instances or WAL behind
+async fn shutdown(
+    manager: &HealthCheckerManager,
+    health_checker: &Arc<HealthChecker>,
+    backend: &dyn ProbeBackend,
+    db: &Db,
+    peer_metadata: &PeerMetadataMap,
+    outbox: &HeartbeatOutbox,
+    min_node_version: Option<&NodeVersion>,
+) {
+    manager.stop_monitoring();
+    let stopped = health_checker.stop_checks();
+    info!("Stopped health checks of {} nodes", stopped);
+
+    // Heartbeats the backend does not take are kept in the outbox for the next start
+    report_current_heartbeats(
+        backend,
+        db,
+        health_checker,
+        peer_metadata,
+        outbox,
+        min_node_version,
+    )
+    .await;
+
+    match backend.report_offline("shutdown").await {
+        Ok(()) => info!("Reported probe offline to backend"),
+        Err(e) => warn!("Failed to report probe offline to backend: {}", e),
+    }
+
+    match health_checker.delete_all_instances() {
+        Ok(count) => info!("Deleted {} network instances", count),
+        Err(e) => error!("Failed to delete network instances: {}", e),
+    }
+
+    match db.checkpoint().await {
+        Ok(()) => info!("Database checkpointed"),
+        Err(e) => error!("Failed to checkpoint database: {}", e),
+    }
 }
 
 /// Start periodic peer fetching from backend
 fn start_peer_fetch_task(
-    backend_client: Arc<BackendClient>,
+    backend: Arc<dyn ProbeBackend>,
     db: Db,
     health_checker: Arc<HealthChecker>,
     peer_metadata: PeerMetadataMap,
-    config: DistributedConfig,
+    region: Option<String>,
+    settings: watch::Receiver<RuntimeSettings>,
+    retention: NodeRetention,
+    shard_config: ShardConfig,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
-        let mut ticker = interval(Duration::from_secs(config.peer_fetch_interval));
+        let mut ticker = ReloadableInterval::new("Peer fetch", settings, |s| s.peer_fetch_interval);
         let mut consecutive_failures = 0;
         let max_failures = 5;
+        let mut last_assignment = None;
 
         loop {
             ticker.tick().await;
 
             debug!("Fetching peers from backend...");
 
-            match backend_client.fetch_peers(config.region.as_deref()).await {
+            let fetch_started = Instant::now();
+            let fetch_result = backend.fetch_peers(region.as_deref()).await;
+            metrics::global().record_backend_fetch(fetch_started.elapsed(), fetch_result.is_ok());
+
+            match fetch_result {
                 Ok(peers) => {
                     info!("Fetched {} peers from backend", peers.len());
                     consecutive_failures = 0;
 
+                    // Keep only the peers this probe is responsible for within its region
+                    let (peers, unowned) =
+                        shard_peers(
+                            backend.as_ref(),
+                            region.as_deref(),
+                            &shard_config,
+                            &mut last_assignment,
+                            peers,
+                        )
+                        .await;
+
                     // Sync peers with local database
                     if let Err(e) = sync_peers_to_db(&db, &health_checker, &peer_metadata, peers).await {
                         error!("Failed to sync peers to database: {}", e);
+                    } else if let Err(e) =
+                        release_unowned_nodes(&db, &health_checker, &peer_metadata, unowned).await
+                    {
+                        error!("Failed to release nodes assigned to other probes: {}", e);
+                    } else if let Err(e) =
+                        reconcile_missing_nodes(&db, &health_checker, &peer_metadata, retention)
+                            .await
+                    {
+                        error!("Failed to reconcile nodes missing from backend: {}", e);
                     }
                 }
                 Err(e) => {
@@ -229,133 +919,348 @@ fn start_peer_fetch_task(
     })
 }
 
-/// Start periodic status reporting to backend (per-peer reporting)
+/// Start periodic status reporting to backend (batched, per-peer results)
 fn start_status_report_task(
-    backend_client: Arc<BackendClient>,
+    backend: Arc<dyn ProbeBackend>,
     db: Db,
     health_checker: Arc<HealthChecker>,
     peer_metadata: PeerMetadataMap,
-    config: DistributedConfig,
+    outbox: Arc<HeartbeatOutbox>,
+    settings: watch::Receiver<RuntimeSettings>,
 ) -> tokio::task::JoinHandle<()> {
-    let version = env!("CARGO_PKG_VERSION").to_string();
-    let region = config.region.clone();
-
     tokio::spawn(async move {
-        let mut ticker = interval(Duration::from_secs(config.status_report_interval));
+        let mut ticker = ReloadableInterval::new("Status report", settings.clone(), |s| {
+            s.status_report_interval
+        });
 
         loop {
             ticker.tick().await;
 
-            debug!("Collecting and reporting peer statuses...");
-
-            // Get all node statuses
-            let all_statuses = health_checker.get_all_nodes_health_status();
-            
-            debug!("Found {} peers to report", all_statuses.len());
-
-            // Report each peer individually (Mode A)
-            for (node_id, health_status, error_info) in all_statuses {
-                // Get RTT for this peer (in microseconds from health checker)
-                let rtt_us = health_checker
-                    .get_node_memory_record(node_id)
-                    .and_then(|r| r.get_last_response_time());
-                
-                // Convert RTT from microseconds to milliseconds
-                let latency_ms = rtt_us.map(|us| (us / 1000) as i32).unwrap_or(0);
-
-                // Determine status
-                let status = match health_status {
-                    db::HealthStatus::Healthy => "online",
-                    _ => "offline",
-                };
+            let min_node_version = settings.borrow().min_node_version.clone();
+            report_current_heartbeats(
+                backend.as_ref(),
+                &db,
+                &health_checker,
+                &peer_metadata,
+                &outbox,
+                min_node_version.as_ref(),
+            )
+            .await;
+        }
+    })
+}
 
-                // Get node details from database to retrieve backend peer ID
-                let node_details = match NodeOperations::get_node_by_id(&db, node_id).await {
-                    Ok(Some(node)) => node,
-                    Ok(None) => {
-                        warn!("Node {} not found in database, skipping report", node_id);
-                        continue;
-                    }
-                    Err(e) => {
-                        error!("Failed to get node {} from database: {}", node_id, e);
-                        continue;
-                    }
-                };
+/// Report the current state of every node, queueing heartbeats that could not be delivered
+async fn report_current_heartbeats(
+    backend: &dyn ProbeBackend,
+    db: &Db,
+    health_checker: &Arc<HealthChecker>,
+    peer_metadata: &PeerMetadataMap,
+    outbox: &HeartbeatOutbox,
+    min_node_version: Option<&NodeVersion>,
+) {
+    debug!("Collecting and reporting peer statuses...");
 
-                // Extract backend peer ID from description
-                // Format: "Auto-added from backend (ID: 123)"
-                let backend_peer_id = if let Some(id_str) = node_details.description
-                    .strip_prefix("Auto-added from backend (ID: ")
-                    .and_then(|s| s.strip_suffix(")")) {
-                    match id_str.parse::<i32>() {
-                        Ok(id) => id,
-                        Err(_) => {
-                            warn!("Failed to parse backend peer ID from description: {}", node_details.description);
-                            continue;
-                        }
-                    }
-                } else {
-                    warn!("Node {} does not have a valid backend peer ID in description: {}", 
-                          node_id, node_details.description);
+    let items = collect_heartbeats(db, health_checker, peer_metadata, min_node_version).await;
+    if items.is_empty() {
+        debug!("No peers to report");
+        return;
+    }
+
+    // Live heartbeats go out even while older ones wait for replay: every heartbeat
+    // carries its checked_at, so the backend orders them regardless of arrival
+    debug!("Reporting {} peers", items.len());
+
+    match backend.report_heartbeats(&items).await {
+        Ok(outcomes) => {
+            let mut retry_items = Vec::new();
+            let mut rejected = 0;
+            for (item, outcome) in items.iter().zip(outcomes.iter()) {
+                let Some(error) = &outcome.error else {
                     continue;
                 };
+                if outcome.is_rejected() {
+                    warn!(
+                        "Backend rejected heartbeat for backend peer {}, dropping it: {}",
+                        outcome.node_id, error
+                    );
+                    rejected += 1;
+                } else {
+                    warn!(
+                        "Failed to report heartbeat for backend peer {}: {}",
+                        outcome.node_id, error
+                    );
+                    retry_items.push(item.clone());
+                }
+            }
+            let failed = retry_items.len() + rejected;
+            let succeeded = outcomes.iter().filter(|o| o.is_success()).count();
+            debug!(
+                "Reported heartbeats: {} succeeded, {} rejected, {} to retry",
+                succeeded,
+                rejected,
+                retry_items.len()
+            );
+            metrics::global().record_reports(succeeded as u64, failed as u64);
+            metrics::global().record_dropped_heartbeats("rejected", rejected as u64);
 
-                // Get peer count from metadata or use 0 as default
-                // In a real scenario, this would come from the actual peer connection count
-                let peer_count = peer_metadata.get(&node_id)
-                    .and_then(|p| p.peer)
-                    .unwrap_or(0);
+            if !retry_items.is_empty() {
+                if let Err(e) = outbox.store(&retry_items, "not delivered to backend").await {
+                    error!("Failed to store heartbeats in outbox: {}", e);
+                }
+            }
+        }
+        Err(e) => {
+            error!(
+                "Failed to report {} heartbeats, storing for replay: {}",
+                items.len(),
+                e
+            );
+            metrics::global().record_reports(0, items.len() as u64);
+            if let Err(e) = outbox.store(&items, &e.to_string()).await {
+                error!("Failed to store heartbeats in outbox: {}", e);
+            }
+        }
+    }
+}
 
-                debug!(
-                    "Reporting peer {} (backend ID {}): status={}, latency={}ms, peer_count={}",
-                    node_details.name, backend_peer_id, status, latency_ms, peer_count
-                );
+/// Start periodic SLA reporting to backend, computed from the hourly rollups
+fn start_sla_report_task(
+    backend: Arc<dyn ProbeBackend>,
+    db: Db,
+    settings: watch::Receiver<RuntimeSettings>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = ReloadableInterval::new("SLA report", settings, |s| s.sla_report_interval);
+        if ticker.period().is_zero() {
+            info!("SLA reporting disabled");
+        }
 
-                // Report to backend with retry logic
-                let mut retry_count = 0;
-                let max_retries = 3;
-                
-                loop {
-                    match backend_client
-                        .report_status(backend_peer_id, status, latency_ms, peer_count)
-                        .await
-                    {
-                        Ok(_) => {
-                            debug!("Successfully reported heartbeat for peer {} (backend ID {})", 
-                                   node_details.name, backend_peer_id);
-                            break;
-                        }
-                        Err(e) => {
-                            retry_count += 1;
-                            if e.to_string().contains("401") || e.to_string().contains("Unauthorized") {
-                                error!("Authentication failed for peer {}: {}. Skipping retry.", 
-                                       node_details.name, e);
-                                break;
-                            }
-                            
-                            if retry_count >= max_retries {
-                                error!(
-                                    "Failed to report heartbeat for peer {} after {} attempts: {}",
-                                    node_details.name, max_retries, e
-                                );
-                                break;
-                            }
-                            
-                            warn!(
-                                "Failed to report heartbeat for peer {} (attempt {}/{}): {}. Retrying...",
-                                node_details.name, retry_count, max_retries, e
-                            );
-                            
-                            // Simple backoff
-                            tokio::time::sleep(Duration::from_secs(2_u64.pow(retry_count as u32))).await;
-                        }
-                    }
+        loop {
+            ticker.tick().await;
+
+            let nodes = match NodeOperations::get_monitored_nodes(&db).await {
+                Ok(nodes) => nodes
+                    .into_iter()
+                    .filter(|node| node.backend_peer_id.is_some())
+                    .collect::<Vec<_>>(),
+                Err(e) => {
+                    error!("Failed to get monitored nodes for SLA report: {}", e);
+                    continue;
                 }
+            };
+
+            let items = match sla::compute_nodes_sla(&db, &nodes, chrono::Utc::now()).await {
+                // The backend knows nodes by their backend peer ID
+                Ok(slas) => slas
+                    .into_iter()
+                    .zip(&nodes)
+                    .filter_map(|(sla, node)| {
+                        Some(sla::NodeSla {
+                            node_id: node.backend_peer_id?,
+                            ..sla
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+                Err(e) => {
+                    error!("Failed to compute node SLA: {}", e);
+                    continue;
+                }
+            };
+
+            match backend.report_sla(&items).await {
+                Ok(()) => debug!("Reported SLA of {} nodes", items.len()),
+                Err(e) => warn!("Failed to report SLA of {} nodes: {}", items.len(), e),
             }
         }
     })
 }
 
+/// Build one heartbeat per monitored node that is bound to a backend peer
+async fn collect_heartbeats(
+    db: &Db,
+    health_checker: &Arc<HealthChecker>,
+    peer_metadata: &PeerMetadataMap,
+    min_node_version: Option<&NodeVersion>,
+) -> Vec<HeartbeatItem> {
+    let all_states = health_checker.get_all_nodes_state();
+    let mut items = Vec::with_capacity(all_states.len());
+
+    // Last per-protocol listener checks, reported alongside the primary status
+    let mut listeners_map = ListenerOperations::get_listeners_map(db)
+        .await
+        .unwrap_or_else(|e| {
+            error!("Failed to get node listeners from database: {}", e);
+            Default::default()
+        });
+    let mut certificates_map = CertificateOperations::get_certificates_map(db)
+        .await
+        .unwrap_or_else(|e| {
+            error!("Failed to get node certificates from database: {}", e);
+            Default::default()
+        });
+
+    for (node_id, node_state) in all_states {
+        let mem_record = health_checker.get_node_memory_record(node_id);
+
+        // Get RTT for this peer (in microseconds from health checker)
+        let rtt_us = mem_record.as_ref().and_then(|r| r.get_last_response_time());
+
+        // Convert RTT from microseconds to milliseconds
+        let latency_ms = rtt_us.map(|us| us / 1000).unwrap_or(0);
+
+        // Determine status from the derived state so a single failed check does not flap
+        let status = match node_state {
+            NodeState::Up | NodeState::Degraded => "online",
+            NodeState::Down => "offline",
+            NodeState::Unknown => {
+                debug!("Node {} has no state yet, skipping report", node_id);
+                continue;
+            }
+        };
+
+        // Get node details from database to retrieve backend peer ID
+        let node_details = match NodeOperations::get_node_by_id(db, node_id).await {
+            Ok(Some(node)) => node,
+            Ok(None) => {
+                warn!("Node {} not found in database, skipping report", node_id);
+                continue;
+            }
+            Err(e) => {
+                error!("Failed to get node {} from database: {}", node_id, e);
+                continue;
+            }
+        };
+
+        let Some(backend_peer_id) = node_details.backend_peer_id else {
+            debug!(
+                "Node {} ({}) is not bound to a backend peer, skipping report",
+                node_id, node_details.name
+            );
+            continue;
+        };
+
+        // Prefer the last measured foreign peer count, which is kept when a later check
+        // fails. Nodes never measured report 0 while their last check failed, and fall back
+        // to the backend metadata otherwise (not checked yet or probed by handshake only)
+        let load = mem_record.as_ref().and_then(|r| r.get_last_load().cloned());
+        let measured = mem_record
+            .as_ref()
+            .and_then(|r| r.get_last_foreign_peer_count());
+        let last_check_failed = mem_record
+            .as_ref()
+            .is_some_and(|r| *r.get_current_health_status() != HealthStatus::Healthy);
+        let peer_count = match measured {
+            Some(count) => count as i32,
+            None if last_check_failed => 0,
+            None => peer_metadata
+                .get(&node_id)
+                .and_then(|p| p.peer)
+                .unwrap_or(0),
+        };
+
+        debug!(
+            "Collected peer {} (backend ID {}): status={}, latency={}ms, peer_count={}",
+            node_details.name, backend_peer_id, status, latency_ms, peer_count
+        );
+
+        items.push(HeartbeatItem {
+            node_id: backend_peer_id,
+            status: status.to_string(),
+            peer: peer_count,
+            latency_ms,
+            checked_at: mem_record.as_ref().map(|r| r.get_last_check_time()),
+            state: Some(node_state.to_string()),
+            quality: mem_record
+                .as_ref()
+                .and_then(|r| heartbeat_quality(&r.get_link_quality())),
+            error_kind: mem_record.as_ref().and_then(|r| r.get_last_error_kind()),
+            listeners: listener_heartbeats(listeners_map.remove(&node_id).unwrap_or_default()),
+            load,
+            certificates: certificate_heartbeats(
+                certificates_map.remove(&node_id).unwrap_or_default(),
+            ),
+            version_outdated: node_version::is_outdated(&node_details.version, min_node_version),
+            version: Some(node_details.version).filter(|v| !v.is_empty()),
+            features: mem_record
+                .as_ref()
+                .map(|r| r.get_last_features().to_vec())
+                .unwrap_or_default(),
+        });
+    }
+
+    items
+}
+
+/// Convert the last listener checks to heartbeat entries, skipping listeners not checked yet
+fn listener_heartbeats(listeners: Vec<node_listeners::Model>) -> Vec<ListenerHeartbeat> {
+    listeners
+        .into_iter()
+        .filter_map(|listener| {
+            let status = match HealthStatus::from(listener.last_status?) {
+                HealthStatus::Healthy => "online",
+                _ => "offline",
+            };
+            Some(ListenerHeartbeat {
+                url: listener.url,
+                protocol: listener.protocol,
+                status: status.to_string(),
+                latency_ms: listener.last_response_time.map(|us| us / 1000),
+                error_kind: listener.last_error_kind.as_deref().map(ProbeErrorKind::from),
+            })
+        })
+        .collect()
+}
+
+/// Convert the last certificate checks to heartbeat entries
+fn certificate_heartbeats(
+    certificates: Vec<node_certificates::Model>,
+) -> Vec<CertificateHeartbeat> {
+    certificates
+        .into_iter()
+        .map(|cert| CertificateHeartbeat {
+            url: cert.url,
+            status: CertStatus::from(cert.status.as_str()),
+            subject: cert.subject,
+            issuer: cert.issuer,
+            not_after: cert.not_after.map(|at| at.to_utc()),
+            hostname_match: cert.hostname_match,
+            trusted: cert.trusted,
+            error: cert.last_error_message,
+        })
+        .collect()
+}
+
+/// Convert windowed link quality (microseconds) to the heartbeat format (milliseconds)
+fn heartbeat_quality(quality: &LinkQuality) -> Option<HeartbeatQuality> {
+    // No successful checks in the window yet
+    quality.loss_rate?;
+
+    let us_to_ms = |us: f64| us / 1000.0;
+    Some(HeartbeatQuality {
+        loss_rate: quality.loss_rate,
+        jitter_ms: quality.jitter_us.map(us_to_ms),
+        p50_latency_ms: quality.p50_latency.map(|us| us_to_ms(us as f64)),
+        p95_latency_ms: quality.p95_latency.map(|us| us_to_ms(us as f64)),
+    })
+}
+
+/// Store the listener URLs reported by the backend so every protocol gets probed
+async fn sync_node_listeners(db: &Db, node_id: i32, backend_peer: &BackendPeer) {
+    if let Err(e) =
+        ListenerOperations::set_node_listeners(db, node_id, backend_peer.listeners.clone()).await
+    {
+        warn!("Failed to sync listeners of node {}: {}", node_id, e);
+    }
+}
+
+/// Store the backend tags of a node, used by the tag filter of the health checker manager
+async fn sync_node_tags(db: &Db, node_id: i32, backend_peer: &BackendPeer) {
+    if let Err(e) = NodeOperations::set_node_tags(db, node_id, backend_peer.tags.clone()).await {
+        warn!("Failed to sync tags of node {}: {}", node_id, e);
+    }
+}
+
 /// Sync fetched peers to local database and health checker
 async fn sync_peers_to_db(
     db: &Db,
@@ -363,58 +1268,69 @@ async fn sync_peers_to_db(
     peer_metadata: &PeerMetadataMap,
     backend_peers: Vec<BackendPeer>,
 ) -> Result<()> {
-    // Get current nodes from database
-    let current_nodes = NodeOperations::get_all_nodes(db)
+    // Get current backend-sourced nodes from database, keyed by backend peer ID
+    let current_node_map = NodeOperations::get_backend_nodes_map(db)
         .await
         .context("Failed to get current nodes")?;
 
-    // Create a map keyed by backend node ID for easier lookup
-    let mut current_node_map: HashMap<i32, shared_nodes::Model> = HashMap::new();
-    for node in current_nodes {
-        // Extract backend peer ID from description
-        if let Some(id_str) = node.description
-            .strip_prefix("Auto-added from backend (ID: ")
-            .and_then(|s| s.strip_suffix(")")) {
-            if let Ok(backend_id) = id_str.parse::<i32>() {
-                current_node_map.insert(backend_id, node);
-            }
-        }
-    }
-
     // Add or update peers from backend
+    let mut synced = Vec::new();
     for backend_peer in backend_peers {
         if let Some(existing_node) = current_node_map.get(&backend_peer.id) {
             // Node already exists - store/update peer metadata
+            synced.push(existing_node.id);
             peer_metadata.insert(existing_node.id, backend_peer.clone());
-            
+            health_checker
+                .set_node_check_interval(existing_node.id, backend_peer.requested_check_interval());
+            sync_node_listeners(db, existing_node.id, &backend_peer).await;
+            sync_node_tags(db, existing_node.id, &backend_peer).await;
+
             // Check if network_secret needs to be updated
-            let backend_secret = backend_peer.network_secret.clone().unwrap_or_else(|| String::new());
+            let backend_secret = backend_peer.network_secret.clone().unwrap_or_default();
             let needs_update = existing_node.network_secret != backend_secret;
-            
-            // Update description to include backend ID if needed
-            let expected_desc = format!("Auto-added from backend (ID: {})", backend_peer.id);
-            let desc_needs_update = existing_node.description != expected_desc;
-            
-            if needs_update || desc_needs_update {
-                debug!("Updating peer {}: network_secret={}, description={}", 
-                       backend_peer.name, needs_update, desc_needs_update);
-                if let Ok(Some(node)) = NodeOperations::get_node_by_id(db, existing_node.id).await {
-                    let mut active_model = node.into_active_model();
-                    if needs_update {
-                        active_model.network_secret = Set(backend_secret);
-                    }
-                    if desc_needs_update {
-                        active_model.description = Set(expected_desc);
-                    }
-                    if let Err(e) = active_model.update(db.orm_db()).await {
-                        warn!("Failed to update node: {}", e);
-                    } else if needs_update {
-                        // Trigger health checker to reload configuration for this node
-                        info!("Network secret updated for node {}, triggering reload", backend_peer.name);
-                        if let Err(e) = health_checker.try_update_node(existing_node.id).await {
-                            error!("Failed to reload health checker for node {}: {}", existing_node.id, e);
-                        }
-                    }
+            let reactivate =
+                existing_node.deactivated_at.is_some() || existing_node.released_at.is_some();
+            if !needs_update && !reactivate {
+                continue;
+            }
+
+            let mut active_model = existing_node.clone().into_active_model();
+            active_model.updated_at = Set(chrono::Utc::now().fixed_offset());
+            if reactivate {
+                // Back in the peer list or assigned back to this probe, the health checker
+                // manager picks it up again
+                if existing_node.released_at.is_some() {
+                    info!(
+                        "Peer {} is assigned to this probe again, resuming monitoring",
+                        backend_peer.name
+                    );
+                } else {
+                    info!(
+                        "Peer {} is back in the backend list, reactivating",
+                        backend_peer.name
+                    );
+                }
+                active_model.deactivated_at = Set(None);
+                active_model.released_at = Set(None);
+            }
+            if needs_update {
+                debug!("Updating network_secret of peer {}", backend_peer.name);
+                active_model.network_secret = Set(backend_secret);
+            }
+
+            if let Err(e) = active_model.update(db.orm_db()).await {
+                warn!("Failed to update node: {}", e);
+            } else if needs_update {
+                // Trigger health checker to reload configuration for this node
+                info!(
+                    "Network secret updated for node {}, triggering reload",
+                    backend_peer.name
+                );
+                if let Err(e) = health_checker.try_update_node(existing_node.id).await {
+                    error!(
+                        "Failed to reload health checker for node {}: {}",
+                        existing_node.id, e
+                    );
                 }
             }
         } else {
@@ -443,33 +1359,37 @@ async fn sync_peers_to_db(
                 name: backend_peer.name.clone(),
                 host,
                 port,
-                protocol: backend_peer.protocol.clone().unwrap_or_else(|| String::from("tcp")),
-                description: Some(format!(
-                    "Auto-added from backend (ID: {})",
-                    backend_peer.id
-                )),
+                protocol: backend_peer
+                    .protocol
+                    .clone()
+                    .unwrap_or_else(|| String::from("tcp")),
+                description: Some(format!("Auto-added from backend (ID: {})", backend_peer.id)),
                 max_connections: 100,
                 allow_relay: backend_peer.allow_relay.unwrap_or(true),
-                network_name: backend_peer.network_name.clone().unwrap_or_else(|| String::from("default")),
+                network_name: backend_peer
+                    .network_name
+                    .clone()
+                    .unwrap_or_else(|| String::from("default")),
                 network_secret: backend_peer.network_secret.clone(),
                 qq_number: None,
                 wechat: None,
                 mail: None,
             };
 
-            match NodeOperations::create_node(db, create_req).await {
+            // Bound to the backend peer ID and approved in the same insert, so a failure
+            // never leaves a node that the next sync cannot find
+            match NodeOperations::create_backend_node(db, create_req, backend_peer.id).await {
                 Ok(node) => {
-                    // Auto-approve nodes from backend
-                    let mut active_model = node.clone().into_active_model();
-                    active_model.is_approved = Set(true);
-
-                    if let Err(e) = active_model.update(db.orm_db()).await {
-                        warn!("Failed to approve new node: {}", e);
-                    } else {
-                        info!("Successfully added and approved peer: {}", backend_peer.name);
-                        // Store peer metadata with the new node ID
-                        peer_metadata.insert(node.id, backend_peer.clone());
-                    }
+                    info!(
+                        "Successfully added and approved peer: {}",
+                        backend_peer.name
+                    );
+                    // Store peer metadata with the new node ID
+                    peer_metadata.insert(node.id, backend_peer.clone());
+                    health_checker
+                        .set_node_check_interval(node.id, backend_peer.requested_check_interval());
+                    sync_node_listeners(db, node.id, &backend_peer).await;
+                    sync_node_tags(db, node.id, &backend_peer).await;
                 }
                 Err(e) => {
                     error!("Failed to create node {}: {}", backend_peer.name, e);
@@ -478,8 +1398,244 @@ async fn sync_peers_to_db(
         }
     }
 
-    // Note: We don't remove nodes that are no longer in backend for safety
-    // This preserves historical data and prevents accidental data loss
+    // Only the sync time of nodes still in the list is written, in one statement
+    NodeOperations::mark_synced(db, synced, chrono::Utc::now())
+        .await
+        .context("Failed to record node sync time")?;
+
+    // Nodes no longer in the backend list are handled by `reconcile_missing_nodes`,
+    // only after a grace period so a partial or failed fetch does not drop them
+
+    Ok(())
+}
+
+/// Split fetched peers into those this probe monitors and the backend IDs of the rest
+///
+/// Every peer is kept when the probe is not registered or the backend has no probe list.
+/// When fetching the probe list fails, the last assignment is kept so a transient backend
+/// error does not make every probe monitor every node; without one every peer is kept,
+/// so a backend problem never leaves nodes unmonitored.
+async fn shard_peers(
+    backend: &dyn ProbeBackend,
+    region: Option<&str>,
+    shard_config: &ShardConfig,
+    last_assignment: &mut Option<ShardAssignment>,
+    peers: Vec<BackendPeer>,
+) -> (Vec<BackendPeer>, Vec<i32>) {
+    let Some(probe_id) = backend.probe_id() else {
+        return (peers, Vec::new());
+    };
+    let assignment = match backend.fetch_probes(region).await {
+        Ok(Some(probes)) => last_assignment.insert(ShardAssignment::new(
+            &probe_id,
+            &probes,
+            shard_config,
+            chrono::Utc::now(),
+        )),
+        Ok(None) => {
+            *last_assignment = None;
+            return (peers, Vec::new());
+        }
+        Err(e) => match last_assignment {
+            Some(assignment) => {
+                warn!(
+                    "Failed to fetch probe list, keeping the last assignment: {}",
+                    e
+                );
+                assignment
+            }
+            None => {
+                warn!("Failed to fetch probe list, monitoring every node: {}", e);
+                return (peers, Vec::new());
+            }
+        },
+    };
+
+    let (mut primary, mut backup) = (0, 0);
+    let mut unowned = Vec::new();
+    let owned = peers
+        .into_iter()
+        .filter(|peer| match assignment.role(peer.id) {
+            Some(ShardRole::Primary) => {
+                primary += 1;
+                true
+            }
+            Some(ShardRole::Backup) => {
+                backup += 1;
+                true
+            }
+            None => {
+                unowned.push(peer.id);
+                false
+            }
+        })
+        .collect::<Vec<_>>();
+
+    info!(
+        "Probe {} of {} in region {:?}: primary for {} peers, backup for {}, {} assigned elsewhere",
+        probe_id,
+        assignment.probe_count,
+        region,
+        primary,
+        backup,
+        unowned.len()
+    );
+    (owned, unowned)
+}
+
+/// Stop monitoring nodes that are now assigned to other probes, their history is kept
+/// (they are not purged like deactivated nodes) so monitoring resumes seamlessly if they
+/// are assigned back
+async fn release_unowned_nodes(
+    db: &Db,
+    health_checker: &Arc<HealthChecker>,
+    peer_metadata: &PeerMetadataMap,
+    unowned: Vec<i32>,
+) -> Result<()> {
+    let released = NodeOperations::release_backend_peers(db, unowned, chrono::Utc::now())
+        .await
+        .context("Failed to release reassigned nodes")?;
+    for node in released {
+        info!(
+            "Peer {} (backend ID {:?}) is assigned to other probes, no longer monitored",
+            node.name, node.backend_peer_id
+        );
+        peer_metadata.remove(&node.id);
+        if let Err(e) = health_checker.forget_node(node.id).await {
+            error!("Failed to stop health check for node {}: {}", node.id, e);
+        }
+    }
+    Ok(())
+}
+
+/// Stop monitoring backend nodes that have been missing from the peer list for longer
+/// than the grace period, and delete nodes that stayed deactivated past retention
+async fn reconcile_missing_nodes(
+    db: &Db,
+    health_checker: &Arc<HealthChecker>,
+    peer_metadata: &PeerMetadataMap,
+    retention: NodeRetention,
+) -> Result<()> {
+    let now = chrono::Utc::now();
+
+    let deactivated =
+        NodeOperations::deactivate_missing_backend_nodes(db, now - retention.grace_period)
+            .await
+            .context("Failed to deactivate missing nodes")?;
+    for node in deactivated {
+        info!(
+            "Peer {} (backend ID {:?}) missing from backend for over {}s, deactivated",
+            node.name,
+            node.backend_peer_id,
+            retention.grace_period.num_seconds()
+        );
+        peer_metadata.remove(&node.id);
+        if let Err(e) = health_checker.forget_node(node.id).await {
+            error!("Failed to stop health check for node {}: {}", node.id, e);
+        }
+    }
+
+    let purged = NodeOperations::purge_deactivated_nodes(db, now - retention.purge_after)
+        .await
+        .context("Failed to purge deactivated nodes")?;
+    if !purged.is_empty() {
+        info!(
+            "Deleted {} nodes deactivated for over {} days: {:?}",
+            purged.len(),
+            retention.purge_after.num_days(),
+            purged
+        );
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse `argv` only, ignoring the process environment so the result does not
+    /// depend on variables such as `REGION` or `API_KEY` being set
+    fn parse_args(argv: &[&str]) -> (Args, ArgMatches) {
+        let matches = Args::command()
+            .mut_args(|arg| arg.env(None))
+            .try_get_matches_from(argv)
+            .unwrap();
+        (Args::from_arg_matches(&matches).unwrap(), matches)
+    }
+
+    #[test]
+    fn test_config_file_precedence() {
+        let file = ConfigFile::parse(
+            r#"
+            [backend]
+            base_url = "https://backend.example.com"
+            api_key = "from-file"
+            region = "file-region"
+            probe_name = "file-probe"
+
+            [intervals]
+            peer_fetch = 120
+            status_report = 45
+
+            [state]
+            min_dwell = 10
+            "#,
+        )
+        .unwrap();
+
+        let (mut args, matches) = parse_args(&[
+            "neo-uptime-node",
+            "--region",
+            "cli-region",
+            "--probe-name",
+            "cli-probe",
+            "--status-report-interval",
+            "30",
+        ]);
+        args.apply_config_file(file, &matches);
+
+        // Options given explicitly win, even when they equal the default; the file only
+        // replaces defaults. Environment values have the same precedence, clap reports
+        // them as explicit too
+        assert_eq!(args.region.as_deref(), Some("cli-region"));
+        assert_eq!(args.probe_name.as_deref(), Some("cli-probe"));
+        assert_eq!(args.status_report_interval, 30);
+        assert_eq!(args.peer_fetch_interval, 120);
+        assert_eq!(args.state_min_dwell, 10);
+        assert_eq!(
+            args.backend_base_url.as_deref(),
+            Some("https://backend.example.com")
+        );
+        assert_eq!(args.api_key.as_deref(), Some("from-file"));
+        assert_eq!(args.health_check_interval, 5);
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_settings() {
+        let (args, _) = parse_args(&["neo-uptime-node", "--peers-file", "peers.json"]);
+        args.validate().unwrap();
+
+        let invalid: [fn(&mut Args); 8] = [
+            |args| args.shard_replicas = 0,
+            |args| args.outbox_max_entries = 0,
+            |args| args.outbox_max_age_hours = 0,
+            |args| args.outbox_max_age_hours = -1,
+            |args| args.probe_stale_after = 0,
+            |args| args.missing_node_grace_period = -1,
+            |args| args.state_min_dwell = -1,
+            |args| args.health_check_backoff_after = -1,
+        ];
+        for change in invalid {
+            let (mut args, _) = parse_args(&["neo-uptime-node", "--peers-file", "peers.json"]);
+            change(&mut args);
+            assert!(args.validate().is_err(), "{:?}", args);
+        }
+
+        // Zero is a valid grace period and dwell time
+        let (mut args, _) = parse_args(&["neo-uptime-node", "--peers-file", "peers.json"]);
+        args.missing_node_grace_period = 0;
+        args.state_min_dwell = 0;
+        args.validate().unwrap();
+    }
+}
@@ -0,0 +1,285 @@
+use std::{
+    fmt::Write as _,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        LazyLock,
+    },
+    time::Duration,
+};
+
+use dashmap::DashMap;
+
+use crate::db::NodeState;
+
+/// Upper bounds (seconds) of the backend fetch latency histogram buckets
+const FETCH_DURATION_BUCKETS: [f64; 9] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+static METRICS: LazyLock<ProbeMetrics> = LazyLock::new(ProbeMetrics::default);
+
+/// 进程级探测指标
+pub fn global() -> &'static ProbeMetrics {
+    &METRICS
+}
+
+/// 探测节点自身的运行指标，以 Prometheus 文本格式导出
+#[derive(Debug, Default)]
+pub struct ProbeMetrics {
+    checks_total: AtomicU64,
+    check_failures: DashMap<String, AtomicU64>,
+    reports_success: AtomicU64,
+    reports_failure: AtomicU64,
+    heartbeats_dropped: DashMap<&'static str, AtomicU64>,
+    fetch_failures: AtomicU64,
+    fetch_duration: Histogram,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [AtomicU64; FETCH_DURATION_BUCKETS.len()],
+    sum_micros: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        for (bound, bucket) in FETCH_DURATION_BUCKETS.iter().zip(self.buckets.iter()) {
+            if secs <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl ProbeMetrics {
+    /// 记录一次健康检查，失败时按原因计数
+    pub fn record_check(&self, failure_reason: Option<&str>) {
+        self.checks_total.fetch_add(1, Ordering::Relaxed);
+        if let Some(reason) = failure_reason {
+            self.check_failures
+                .entry(reason.to_string())
+                .or_default()
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 记录一轮心跳上报的结果
+    pub fn record_reports(&self, succeeded: u64, failed: u64) {
+        self.reports_success.fetch_add(succeeded, Ordering::Relaxed);
+        self.reports_failure.fetch_add(failed, Ordering::Relaxed);
+    }
+
+    /// 记录不再投递而丢弃的心跳，按原因计数
+    pub fn record_dropped_heartbeats(&self, reason: &'static str, count: u64) {
+        if count == 0 {
+            return;
+        }
+        self.heartbeats_dropped
+            .entry(reason)
+            .or_default()
+            .fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// 记录一次从后端获取节点列表的耗时
+    pub fn record_backend_fetch(&self, duration: Duration, success: bool) {
+        self.fetch_duration.observe(duration);
+        if !success {
+            self.fetch_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 以 Prometheus 文本格式导出，`node_states` 为当前各节点的派生状态
+    pub fn render(&self, node_states: &[NodeState]) -> String {
+        let mut out = String::new();
+
+        write_header(
+            &mut out,
+            "neo_uptime_checks_total",
+            "counter",
+            "Health checks run",
+        );
+        let _ = writeln!(
+            out,
+            "neo_uptime_checks_total {}",
+            self.checks_total.load(Ordering::Relaxed)
+        );
+
+        write_header(
+            &mut out,
+            "neo_uptime_check_failures_total",
+            "counter",
+            "Failed health checks by reason",
+        );
+        let mut failures: Vec<(String, u64)> = self
+            .check_failures
+            .iter()
+            .map(|e| (e.key().clone(), e.value().load(Ordering::Relaxed)))
+            .collect();
+        failures.sort();
+        for (reason, count) in failures {
+            let _ = writeln!(
+                out,
+                "neo_uptime_check_failures_total{{reason=\"{}\"}} {}",
+                escape_label(&reason),
+                count
+            );
+        }
+
+        write_header(
+            &mut out,
+            "neo_uptime_heartbeat_reports_total",
+            "counter",
+            "Heartbeats reported to the backend by result",
+        );
+        let _ = writeln!(
+            out,
+            "neo_uptime_heartbeat_reports_total{{result=\"success\"}} {}",
+            self.reports_success.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            out,
+            "neo_uptime_heartbeat_reports_total{{result=\"failure\"}} {}",
+            self.reports_failure.load(Ordering::Relaxed)
+        );
+
+        write_header(
+            &mut out,
+            "neo_uptime_heartbeats_dropped_total",
+            "counter",
+            "Heartbeats dropped without being delivered, by reason",
+        );
+        let mut dropped: Vec<(&str, u64)> = self
+            .heartbeats_dropped
+            .iter()
+            .map(|e| (*e.key(), e.value().load(Ordering::Relaxed)))
+            .collect();
+        dropped.sort();
+        for (reason, count) in dropped {
+            let _ = writeln!(
+                out,
+                "neo_uptime_heartbeats_dropped_total{{reason=\"{}\"}} {}",
+                reason, count
+            );
+        }
+
+        write_header(
+            &mut out,
+            "neo_uptime_backend_fetch_failures_total",
+            "counter",
+            "Failed peer list fetches from the backend",
+        );
+        let _ = writeln!(
+            out,
+            "neo_uptime_backend_fetch_failures_total {}",
+            self.fetch_failures.load(Ordering::Relaxed)
+        );
+
+        write_header(
+            &mut out,
+            "neo_uptime_backend_fetch_duration_seconds",
+            "histogram",
+            "Latency of peer list fetches from the backend",
+        );
+        for (bound, bucket) in FETCH_DURATION_BUCKETS
+            .iter()
+            .zip(self.fetch_duration.buckets.iter())
+        {
+            let _ = writeln!(
+                out,
+                "neo_uptime_backend_fetch_duration_seconds_bucket{{le=\"{}\"}} {}",
+                bound,
+                bucket.load(Ordering::Relaxed)
+            );
+        }
+        let count = self.fetch_duration.count.load(Ordering::Relaxed);
+        let _ = writeln!(
+            out,
+            "neo_uptime_backend_fetch_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            count
+        );
+        let _ = writeln!(
+            out,
+            "neo_uptime_backend_fetch_duration_seconds_sum {}",
+            self.fetch_duration.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+        );
+        let _ = writeln!(
+            out,
+            "neo_uptime_backend_fetch_duration_seconds_count {}",
+            count
+        );
+
+        write_header(
+            &mut out,
+            "neo_uptime_monitored_nodes",
+            "gauge",
+            "Monitored nodes by current state",
+        );
+        let mut by_state: Vec<(String, u64)> = Vec::new();
+        for state in node_states {
+            let state = state.to_string();
+            match by_state.iter_mut().find(|(s, _)| *s == state) {
+                Some((_, count)) => *count += 1,
+                None => by_state.push((state, 1)),
+            }
+        }
+        by_state.sort();
+        for (state, count) in by_state {
+            let _ = writeln!(
+                out,
+                "neo_uptime_monitored_nodes{{state=\"{}\"}} {}",
+                state, count
+            );
+        }
+
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let metrics = ProbeMetrics::default();
+        metrics.record_check(None);
+        metrics.record_check(Some("destination_offline"));
+        metrics.record_check(Some("destination_offline"));
+        metrics.record_reports(3, 1);
+        metrics.record_dropped_heartbeats("rejected", 2);
+        metrics.record_backend_fetch(Duration::from_millis(200), true);
+        metrics.record_backend_fetch(Duration::from_secs(60), false);
+
+        let text = metrics.render(&[NodeState::Up, NodeState::Up, NodeState::Degraded]);
+
+        assert!(text.contains("neo_uptime_checks_total 3\n"));
+        assert!(
+            text.contains("neo_uptime_check_failures_total{reason=\"destination_offline\"} 2\n")
+        );
+        assert!(text.contains("neo_uptime_heartbeat_reports_total{result=\"success\"} 3\n"));
+        assert!(text.contains("neo_uptime_heartbeat_reports_total{result=\"failure\"} 1\n"));
+        assert!(text.contains("neo_uptime_heartbeats_dropped_total{reason=\"rejected\"} 2\n"));
+        assert!(text.contains("neo_uptime_backend_fetch_failures_total 1\n"));
+        assert!(text.contains("neo_uptime_backend_fetch_duration_seconds_bucket{le=\"0.1\"} 0\n"));
+        assert!(text.contains("neo_uptime_backend_fetch_duration_seconds_bucket{le=\"0.25\"} 1\n"));
+        assert!(text.contains("neo_uptime_backend_fetch_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.contains("neo_uptime_backend_fetch_duration_seconds_count 2\n"));
+        assert!(text.contains("neo_uptime_monitored_nodes{state=\"up\"} 2\n"));
+        assert!(text.contains("neo_uptime_monitored_nodes{state=\"degraded\"} 1\n"));
+    }
+}
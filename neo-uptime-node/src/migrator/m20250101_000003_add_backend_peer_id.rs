@@ -0,0 +1,108 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum SharedNodes {
+    Table,
+    BackendPeerId,
+    Source,
+    LastSyncedAt,
+}
+
+/// 旧版本写入 description 的后端 ID 格式前缀：`Auto-added from backend (ID: N)`
+const LEGACY_DESCRIPTION_PREFIX: &str = "Auto-added from backend (ID: ";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 的 ALTER TABLE 每次只能添加一列
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SharedNodes::Table)
+                    .add_column(integer_null(SharedNodes::BackendPeerId))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SharedNodes::Table)
+                    .add_column(string(SharedNodes::Source).default("manual"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SharedNodes::Table)
+                    .add_column(timestamp_with_time_zone_null(SharedNodes::LastSyncedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        // 索引：BackendPeerId
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_shared_nodes_backend_peer_id")
+                    .table(SharedNodes::Table)
+                    .col(SharedNodes::BackendPeerId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // 从旧的 description 格式回填后端 ID
+        let prefix_len = LEGACY_DESCRIPTION_PREFIX.chars().count();
+        let backfill = format!(
+            "UPDATE shared_nodes \
+             SET backend_peer_id = CAST(substr(description, {start}, length(description) - {prefix_len} - 1) AS INTEGER), \
+                 source = 'backend' \
+             WHERE backend_peer_id IS NULL \
+               AND description LIKE '{prefix}%)' \
+               AND substr(description, {start}, length(description) - {prefix_len} - 1) GLOB '[0-9]*' \
+               AND substr(description, {start}, length(description) - {prefix_len} - 1) NOT GLOB '*[^0-9]*'",
+            start = prefix_len + 1,
+            prefix_len = prefix_len,
+            prefix = LEGACY_DESCRIPTION_PREFIX,
+        );
+        manager
+            .get_connection()
+            .execute_unprepared(&backfill)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_shared_nodes_backend_peer_id")
+                    .table(SharedNodes::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        for col in [
+            SharedNodes::LastSyncedAt,
+            SharedNodes::Source,
+            SharedNodes::BackendPeerId,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(SharedNodes::Table)
+                        .drop_column(col)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum HeartbeatOutbox {
+    Table,
+    Id,
+    BackendPeerId,
+    Status,
+    Peer,
+    LatencyMs,
+    CheckedAt,
+    Attempts,
+    LastError,
+    CreatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 heartbeat_outbox 表：后端不可用时暂存未送达的心跳
+        manager
+            .create_table(
+                Table::create()
+                    .table(HeartbeatOutbox::Table)
+                    .if_not_exists()
+                    .col(pk_auto(HeartbeatOutbox::Id).not_null())
+                    .col(integer(HeartbeatOutbox::BackendPeerId).not_null())
+                    .col(string(HeartbeatOutbox::Status).not_null())
+                    .col(integer(HeartbeatOutbox::Peer).default(0))
+                    .col(integer(HeartbeatOutbox::LatencyMs).default(0))
+                    .col(timestamp_with_time_zone(HeartbeatOutbox::CheckedAt).not_null())
+                    .col(integer(HeartbeatOutbox::Attempts).default(0))
+                    .col(text_null(HeartbeatOutbox::LastError))
+                    .col(
+                        timestamp_with_time_zone(HeartbeatOutbox::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 索引：按检查时间顺序重放
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_heartbeat_outbox_checked_at")
+                    .table(HeartbeatOutbox::Table)
+                    .col(HeartbeatOutbox::CheckedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_heartbeat_outbox_checked_at")
+                    .table(HeartbeatOutbox::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(HeartbeatOutbox::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,109 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum HealthRollups {
+    Table,
+    Id,
+    NodeId,
+    BucketStart,
+    CheckCount,
+    SuccessCount,
+    MinLatency,
+    AvgLatency,
+    P95Latency,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum SharedNodes {
+    Table,
+    Id,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 health_rollups 表：按小时汇总的健康记录
+        manager
+            .create_table(
+                Table::create()
+                    .table(HealthRollups::Table)
+                    .if_not_exists()
+                    .col(pk_auto(HealthRollups::Id).not_null())
+                    .col(integer(HealthRollups::NodeId).not_null())
+                    .col(timestamp_with_time_zone(HealthRollups::BucketStart).not_null())
+                    .col(integer(HealthRollups::CheckCount).default(0))
+                    .col(integer(HealthRollups::SuccessCount).default(0))
+                    .col(integer_null(HealthRollups::MinLatency))
+                    .col(double_null(HealthRollups::AvgLatency))
+                    .col(integer_null(HealthRollups::P95Latency))
+                    .col(
+                        timestamp_with_time_zone(HealthRollups::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_health_rollups_node")
+                            .from(HealthRollups::Table, HealthRollups::NodeId)
+                            .to(SharedNodes::Table, SharedNodes::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 唯一索引：每个节点每小时一条
+        manager
+            .create_index(
+                Index::create()
+                    .name("uniq_health_rollups_node_bucket")
+                    .table(HealthRollups::Table)
+                    .col(HealthRollups::NodeId)
+                    .col(HealthRollups::BucketStart)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // 索引：BucketStart
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_health_rollups_bucket_start")
+                    .table(HealthRollups::Table)
+                    .col(HealthRollups::BucketStart)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("uniq_health_rollups_node_bucket")
+                    .table(HealthRollups::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_health_rollups_bucket_start")
+                    .table(HealthRollups::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(HealthRollups::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,108 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum NodeStateEvents {
+    Table,
+    Id,
+    NodeId,
+    FromState,
+    ToState,
+    Reason,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum SharedNodes {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum HeartbeatOutbox {
+    Table,
+    State,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 node_state_events 表：节点派生状态的转换记录
+        manager
+            .create_table(
+                Table::create()
+                    .table(NodeStateEvents::Table)
+                    .if_not_exists()
+                    .col(pk_auto(NodeStateEvents::Id).not_null())
+                    .col(integer(NodeStateEvents::NodeId).not_null())
+                    .col(string(NodeStateEvents::FromState).not_null())
+                    .col(string(NodeStateEvents::ToState).not_null())
+                    .col(text_null(NodeStateEvents::Reason))
+                    .col(
+                        timestamp_with_time_zone(NodeStateEvents::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_node_state_events_node")
+                            .from(NodeStateEvents::Table, NodeStateEvents::NodeId)
+                            .to(SharedNodes::Table, SharedNodes::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 索引：NodeId + CreatedAt
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_node_state_events_node_created")
+                    .table(NodeStateEvents::Table)
+                    .col(NodeStateEvents::NodeId)
+                    .col(NodeStateEvents::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // 暂存的心跳同样记录派生状态，重放时一并上报
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .add_column(string_null(HeartbeatOutbox::State))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .drop_column(HeartbeatOutbox::State)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_node_state_events_node_created")
+                    .table(NodeStateEvents::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(NodeStateEvents::Table).to_owned())
+            .await
+    }
+}
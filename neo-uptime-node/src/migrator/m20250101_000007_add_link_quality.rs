@@ -0,0 +1,102 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum HealthRecords {
+    Table,
+    LossRate,
+    JitterUs,
+    P50Latency,
+    P95Latency,
+}
+
+#[derive(DeriveIden)]
+enum HeartbeatOutbox {
+    Table,
+    Quality,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 的 ALTER TABLE 每次只能添加一列
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HealthRecords::Table)
+                    .add_column(double_null(HealthRecords::LossRate))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HealthRecords::Table)
+                    .add_column(double_null(HealthRecords::JitterUs))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HealthRecords::Table)
+                    .add_column(integer_null(HealthRecords::P50Latency))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HealthRecords::Table)
+                    .add_column(integer_null(HealthRecords::P95Latency))
+                    .to_owned(),
+            )
+            .await?;
+
+        // 暂存的心跳同样保留链路质量（JSON）
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .add_column(text_null(HeartbeatOutbox::Quality))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .drop_column(HeartbeatOutbox::Quality)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [
+            HealthRecords::LossRate,
+            HealthRecords::JitterUs,
+            HealthRecords::P50Latency,
+            HealthRecords::P95Latency,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(HealthRecords::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum HealthRecords {
+    Table,
+    ErrorKind,
+}
+
+#[derive(DeriveIden)]
+enum HeartbeatOutbox {
+    Table,
+    ErrorKind,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HealthRecords::Table)
+                    .add_column(string_null(HealthRecords::ErrorKind))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .add_column(string_null(HeartbeatOutbox::ErrorKind))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .drop_column(HeartbeatOutbox::ErrorKind)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HealthRecords::Table)
+                    .drop_column(HealthRecords::ErrorKind)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum NodeListeners {
+    Table,
+    Id,
+    NodeId,
+    Url,
+    Protocol,
+    LastStatus,
+    LastResponseTime,
+    LastErrorMessage,
+    LastErrorKind,
+    LastCheckedAt,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum SharedNodes {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum HeartbeatOutbox {
+    Table,
+    Listeners,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 node_listeners 表：节点的各个监听地址及其最近一次检查结果
+        manager
+            .create_table(
+                Table::create()
+                    .table(NodeListeners::Table)
+                    .if_not_exists()
+                    .col(pk_auto(NodeListeners::Id).not_null())
+                    .col(integer(NodeListeners::NodeId).not_null())
+                    .col(string(NodeListeners::Url).not_null())
+                    .col(string(NodeListeners::Protocol).not_null())
+                    .col(string_null(NodeListeners::LastStatus))
+                    .col(integer_null(NodeListeners::LastResponseTime))
+                    .col(text_null(NodeListeners::LastErrorMessage))
+                    .col(string_null(NodeListeners::LastErrorKind))
+                    .col(timestamp_with_time_zone_null(NodeListeners::LastCheckedAt))
+                    .col(
+                        timestamp_with_time_zone(NodeListeners::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_node_listeners_node")
+                            .from(NodeListeners::Table, NodeListeners::NodeId)
+                            .to(SharedNodes::Table, SharedNodes::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 唯一索引：同一节点的监听地址不重复
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_node_listeners_node_url")
+                    .table(NodeListeners::Table)
+                    .col(NodeListeners::NodeId)
+                    .col(NodeListeners::Url)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // 暂存的心跳同样保留各协议的检查结果（JSON）
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .add_column(text_null(HeartbeatOutbox::Listeners))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .drop_column(HeartbeatOutbox::Listeners)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_node_listeners_node_url")
+                    .table(NodeListeners::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(NodeListeners::Table).to_owned())
+            .await
+    }
+}
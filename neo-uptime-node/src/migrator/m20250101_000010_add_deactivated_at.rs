@@ -0,0 +1,36 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum SharedNodes {
+    Table,
+    DeactivatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 后端列表中消失超过宽限期的节点停止监控的时间，NULL 表示仍在监控
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SharedNodes::Table)
+                    .add_column(timestamp_with_time_zone_null(SharedNodes::DeactivatedAt))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SharedNodes::Table)
+                    .drop_column(SharedNodes::DeactivatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,71 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum ProbeIdentity {
+    Table,
+    Id,
+    BackendUrl,
+    ProbeId,
+    Name,
+    Region,
+    Secret,
+    CreatedAt,
+    RegisteredAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 probe_identity 表：探测节点在各个后端注册得到的身份和签名密钥
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProbeIdentity::Table)
+                    .if_not_exists()
+                    .col(pk_auto(ProbeIdentity::Id).not_null())
+                    .col(string(ProbeIdentity::BackendUrl).not_null())
+                    .col(string(ProbeIdentity::ProbeId).not_null())
+                    .col(string(ProbeIdentity::Name).not_null())
+                    .col(string_null(ProbeIdentity::Region))
+                    .col(string(ProbeIdentity::Secret).not_null())
+                    .col(
+                        timestamp_with_time_zone(ProbeIdentity::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone(ProbeIdentity::RegisteredAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // 唯一索引：每个后端只保留一个身份
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_probe_identity_backend_url")
+                    .table(ProbeIdentity::Table)
+                    .col(ProbeIdentity::BackendUrl)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_probe_identity_backend_url")
+                    .table(ProbeIdentity::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ProbeIdentity::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,40 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum HeartbeatOutbox {
+    Table,
+    Load,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 暂存心跳中节点实测的外部网络负载（JSON）
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .add_column(text_null(HeartbeatOutbox::Load))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .drop_column(HeartbeatOutbox::Load)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum NodeCertificates {
+    Table,
+    Id,
+    NodeId,
+    Url,
+    Status,
+    Subject,
+    Issuer,
+    NotBefore,
+    NotAfter,
+    SelfSigned,
+    HostnameMatch,
+    Trusted,
+    LastErrorMessage,
+    CheckedAt,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum SharedNodes {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum HeartbeatOutbox {
+    Table,
+    Certificates,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 node_certificates 表：节点 wss / quic 地址出示的证书及其最近一次检查结果
+        manager
+            .create_table(
+                Table::create()
+                    .table(NodeCertificates::Table)
+                    .if_not_exists()
+                    .col(pk_auto(NodeCertificates::Id).not_null())
+                    .col(integer(NodeCertificates::NodeId).not_null())
+                    .col(string(NodeCertificates::Url).not_null())
+                    .col(string(NodeCertificates::Status).not_null())
+                    .col(text_null(NodeCertificates::Subject))
+                    .col(text_null(NodeCertificates::Issuer))
+                    .col(timestamp_with_time_zone_null(NodeCertificates::NotBefore))
+                    .col(timestamp_with_time_zone_null(NodeCertificates::NotAfter))
+                    .col(boolean_null(NodeCertificates::SelfSigned))
+                    .col(boolean_null(NodeCertificates::HostnameMatch))
+                    .col(boolean_null(NodeCertificates::Trusted))
+                    .col(text_null(NodeCertificates::LastErrorMessage))
+                    .col(timestamp_with_time_zone(NodeCertificates::CheckedAt).not_null())
+                    .col(
+                        timestamp_with_time_zone(NodeCertificates::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_node_certificates_node")
+                            .from(NodeCertificates::Table, NodeCertificates::NodeId)
+                            .to(SharedNodes::Table, SharedNodes::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 唯一索引：同一节点的每个地址只保留最近一次检查结果
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_node_certificates_node_url")
+                    .table(NodeCertificates::Table)
+                    .col(NodeCertificates::NodeId)
+                    .col(NodeCertificates::Url)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // 暂存的心跳同样保留证书检查结果（JSON）
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .add_column(text_null(HeartbeatOutbox::Certificates))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .drop_column(HeartbeatOutbox::Certificates)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_node_certificates_node_url")
+                    .table(NodeCertificates::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(NodeCertificates::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,128 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum NodeVersions {
+    Table,
+    Id,
+    NodeId,
+    Version,
+    FirstSeenAt,
+    LastSeenAt,
+}
+
+#[derive(DeriveIden)]
+enum SharedNodes {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum HeartbeatOutbox {
+    Table,
+    Version,
+    VersionOutdated,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 node_versions 表：节点每次连续运行同一版本的时间段
+        manager
+            .create_table(
+                Table::create()
+                    .table(NodeVersions::Table)
+                    .if_not_exists()
+                    .col(pk_auto(NodeVersions::Id).not_null())
+                    .col(integer(NodeVersions::NodeId).not_null())
+                    .col(string(NodeVersions::Version).not_null())
+                    .col(timestamp_with_time_zone(NodeVersions::FirstSeenAt).not_null())
+                    .col(timestamp_with_time_zone(NodeVersions::LastSeenAt).not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_node_versions_node")
+                            .from(NodeVersions::Table, NodeVersions::NodeId)
+                            .to(SharedNodes::Table, SharedNodes::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 索引：NodeId + FirstSeenAt（按时间查询节点的版本历史）
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_node_versions_node_first_seen")
+                    .table(NodeVersions::Table)
+                    .col(NodeVersions::NodeId)
+                    .col(NodeVersions::FirstSeenAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // 以节点当前的版本作为历史的起点
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "INSERT INTO node_versions (node_id, version, first_seen_at, last_seen_at) \
+                 SELECT id, version, updated_at, updated_at FROM shared_nodes WHERE version <> ''",
+            )
+            .await?;
+
+        // 暂存的心跳同样保留节点版本和是否低于最低版本
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .add_column(string_null(HeartbeatOutbox::Version))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .add_column(boolean(HeartbeatOutbox::VersionOutdated).default(false))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .drop_column(HeartbeatOutbox::VersionOutdated)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartbeatOutbox::Table)
+                    .drop_column(HeartbeatOutbox::Version)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_node_versions_node_first_seen")
+                    .table(NodeVersions::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(NodeVersions::Table).to_owned())
+            .await
+    }
+}
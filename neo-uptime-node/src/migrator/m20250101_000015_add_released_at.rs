@@ -0,0 +1,36 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum SharedNodes {
+    Table,
+    ReleasedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 节点分配给其他探测节点、本探测节点停止监控的时间，NULL 表示由本探测节点监控
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SharedNodes::Table)
+                    .add_column(timestamp_with_time_zone_null(SharedNodes::ReleasedAt))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SharedNodes::Table)
+                    .drop_column(SharedNodes::ReleasedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -2,6 +2,20 @@ use sea_orm_migration::prelude::*;
 
 mod m20250101_000001_create_tables;
 mod m20250101_000002_create_node_tags;
+mod m20250101_000003_add_backend_peer_id;
+mod m20250101_000004_create_heartbeat_outbox;
+mod m20250101_000005_create_health_rollups;
+mod m20250101_000006_create_node_state_events;
+mod m20250101_000007_add_link_quality;
+mod m20250101_000008_add_error_kind;
+mod m20250101_000009_create_node_listeners;
+mod m20250101_000010_add_deactivated_at;
+mod m20250101_000011_create_probe_identity;
+mod m20250101_000012_add_heartbeat_load;
+mod m20250101_000013_create_node_certificates;
+mod m20250101_000014_create_node_versions;
+mod m20250101_000015_add_released_at;
+mod m20250101_000016_add_heartbeat_features;
 
 pub struct Migrator;
 
@@ -11,6 +25,72 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20250101_000001_create_tables::Migration),
             Box::new(m20250101_000002_create_node_tags::Migration),
+            Box::new(m20250101_000003_add_backend_peer_id::Migration),
+            Box::new(m20250101_000004_create_heartbeat_outbox::Migration),
+            Box::new(m20250101_000005_create_health_rollups::Migration),
+            Box::new(m20250101_000006_create_node_state_events::Migration),
+            Box::new(m20250101_000007_add_link_quality::Migration),
+            Box::new(m20250101_000008_add_error_kind::Migration),
+            Box::new(m20250101_000009_create_node_listeners::Migration),
+            Box::new(m20250101_000010_add_deactivated_at::Migration),
+            Box::new(m20250101_000011_create_probe_identity::Migration),
+            Box::new(m20250101_000012_add_heartbeat_load::Migration),
+            Box::new(m20250101_000013_create_node_certificates::Migration),
+            Box::new(m20250101_000014_create_node_versions::Migration),
+            Box::new(m20250101_000015_add_released_at::Migration),
+            Box::new(m20250101_000016_add_heartbeat_features::Migration),
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::SqlxSqliteConnector;
+
+    #[tokio::test]
+    async fn test_backfill_backend_peer_id_from_description() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        let db = SqlxSqliteConnector::from_sqlx_sqlite_pool(pool);
+
+        // 先只执行到旧版本的表结构
+        Migrator::up(&db, Some(2)).await.unwrap();
+        db.execute_unprepared(
+            "INSERT INTO shared_nodes (name, host, port, protocol, version, network_name, network_secret, description, qq_number, wechat, mail) VALUES \
+             ('a', 'a.example.com', 11010, 'tcp', '', 'n', '', 'Auto-added from backend (ID: 17)', '', '', ''), \
+             ('b', 'b.example.com', 11010, 'tcp', '', 'n', '', 'Auto-added from backend (ID: abc)', '', '', ''), \
+             ('c', 'c.example.com', 11010, 'tcp', '', 'n', '', 'manually added', '', '', '')",
+        )
+        .await
+        .unwrap();
+
+        Migrator::up(&db, None).await.unwrap();
+
+        let rows = db
+            .query_all(sea_orm::Statement::from_string(
+                sea_orm::DatabaseBackend::Sqlite,
+                "SELECT name, backend_peer_id, source FROM shared_nodes ORDER BY name".to_string(),
+            ))
+            .await
+            .unwrap();
+        let rows: Vec<(String, Option<i32>, String)> = rows
+            .iter()
+            .map(|r| {
+                (
+                    r.try_get("", "name").unwrap(),
+                    r.try_get("", "backend_peer_id").unwrap(),
+                    r.try_get("", "source").unwrap(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                ("a".to_string(), Some(17), "backend".to_string()),
+                ("b".to_string(), None, "manual".to_string()),
+                ("c".to_string(), None, "manual".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+/// 共享节点实测的外部网络负载
+///
+/// 计数来自探测实例的路由信息：`GetForeignNetworkSummary` 给出节点承载的网络数和节点总数，
+/// `ListGlobalForeignNetwork` 给出每个外部网络的节点列表。只上报数量，不上报外部网络名称。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeLoad {
+    /// 节点承载的外部网络数
+    pub foreign_network_count: u32,
+    /// 所有外部网络中的节点总数
+    pub foreign_peer_count: u32,
+    /// 每个外部网络的节点数，从多到少排列；拿不到网络列表时为空
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub peers_per_network: Vec<u32>,
+    /// 节点声明尽量不转发数据
+    #[serde(default)]
+    pub avoid_relay_data: bool,
+    /// 节点不转发 KCP 流量
+    #[serde(default)]
+    pub no_relay_kcp: bool,
+}
+
+impl NodeLoad {
+    /// 由路由摘要中的 (网络数, 节点数) 和每个外部网络的节点数计算负载
+    ///
+    /// 两者来自不同的 RPC，可能不是同一时刻的数据；拿到网络列表时以列表为准，否则使用摘要。
+    pub fn measure(
+        summary: Option<(u32, u32)>,
+        peers_per_network: Option<Vec<u32>>,
+        avoid_relay_data: bool,
+        no_relay_kcp: bool,
+    ) -> Self {
+        let (foreign_network_count, foreign_peer_count, peers) = match peers_per_network {
+            Some(mut counts) => {
+                counts.sort_unstable_by(|a, b| b.cmp(a));
+                (counts.len() as u32, counts.iter().sum(), counts)
+            }
+            None => {
+                let (networks, peers) = summary.unwrap_or_default();
+                (networks, peers, Vec::new())
+            }
+        };
+
+        Self {
+            foreign_network_count,
+            foreign_peer_count,
+            peers_per_network: peers,
+            avoid_relay_data,
+            no_relay_kcp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_node_load() {
+        let load = NodeLoad::measure(Some((2, 5)), Some(vec![1, 4, 2]), true, false);
+        assert_eq!(load.foreign_network_count, 3);
+        assert_eq!(load.foreign_peer_count, 7);
+        assert_eq!(load.peers_per_network, vec![4, 2, 1]);
+        assert!(load.avoid_relay_data);
+
+        // 网络列表不可用时使用路由摘要
+        let load = NodeLoad::measure(Some((2, 5)), None, false, false);
+        assert_eq!(load.foreign_network_count, 2);
+        assert_eq!(load.foreign_peer_count, 5);
+        assert!(load.peers_per_network.is_empty());
+
+        let json = serde_json::to_value(&load).unwrap();
+        assert!(json.get("peers_per_network").is_none());
+        assert_eq!(serde_json::from_value::<NodeLoad>(json).unwrap(), load);
+
+        assert_eq!(
+            NodeLoad::measure(None, None, false, false),
+            NodeLoad::default()
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use chrono::{DateTime, Utc};
+
+use crate::db::NodeState;
+
+/// 节点状态机配置
+#[derive(Debug, Clone)]
+pub struct StateMachineConfig {
+    /// 连续失败多少次后判定为离线
+    pub failure_threshold: u32,
+    /// 连续成功多少次后从降级/离线恢复为在线
+    pub recovery_threshold: u32,
+    /// 进入一个状态后至少停留的时间
+    pub min_dwell: chrono::Duration,
+}
+
+impl Default for StateMachineConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            recovery_threshold: 2,
+            min_dwell: chrono::Duration::seconds(30),
+        }
+    }
+}
+
+/// 一次检查的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckOutcome {
+    /// 主地址和所有监听地址均可用
+    Healthy,
+    /// 主地址可用，但有监听地址检查失败
+    Impaired,
+    /// 主地址不可用
+    Unhealthy,
+}
+
+/// 一次状态转换
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateTransition {
+    pub from: NodeState,
+    pub to: NodeState,
+    pub at: DateTime<Utc>,
+}
+
+/// 某个节点的状态变化，由 `HealthChecker` 广播给订阅者
+#[derive(Debug, Clone)]
+pub struct NodeStateChange {
+    pub node_id: i32,
+    pub transition: StateTransition,
+    /// 触发转换的最后一次检查错误
+    pub reason: Option<String>,
+}
+
+/// 单个节点的滞后状态机
+///
+/// 单次检查失败只会让节点进入 `Degraded`，连续失败达到阈值才进入 `Down`；
+/// 恢复同样需要连续成功。主地址可用但有监听地址失败时节点处于 `Degraded`。
+/// 每个状态至少停留 `min_dwell`，避免频繁抖动。
+#[derive(Debug, Clone)]
+pub struct NodeStateMachine {
+    state: NodeState,
+    since: DateTime<Utc>,
+    consecutive_failures: u32,
+    consecutive_successes: u32,
+    /// 最近一次检查中主地址可用但有监听地址失败
+    impaired: bool,
+}
+
+impl NodeStateMachine {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self::restore(NodeState::Unknown, now)
+    }
+
+    /// 从持久化的最近一次转换恢复状态
+    pub fn restore(state: NodeState, since: DateTime<Utc>) -> Self {
+        Self {
+            state,
+            since,
+            consecutive_failures: 0,
+            consecutive_successes: 0,
+            impaired: false,
+        }
+    }
+
+    /// 当前状态
+    pub fn state(&self) -> NodeState {
+        self.state
+    }
+
+    /// 进入当前状态的时间
+    pub fn since(&self) -> DateTime<Utc> {
+        self.since
+    }
+
+    /// 最近的检查结果与当前状态不一致，状态转换还在等待更多检查结果或最短停留时间
+    pub fn is_pending(&self) -> bool {
+        match self.state {
+            NodeState::Unknown => false,
+            NodeState::Up => self.consecutive_failures > 0 || self.impaired,
+            NodeState::Degraded => {
+                self.consecutive_failures > 0 || (self.consecutive_successes > 0 && !self.impaired)
+            }
+            NodeState::Down => self.consecutive_successes > 0,
+        }
+    }
+
+    /// 记录一次检查结果，发生状态转换时返回转换信息
+    ///
+    /// 主地址可用的检查（包括有监听地址失败的）都计入连续成功，用于从离线恢复。
+    pub fn observe(
+        &mut self,
+        outcome: CheckOutcome,
+        now: DateTime<Utc>,
+        config: &StateMachineConfig,
+    ) -> Option<StateTransition> {
+        if outcome == CheckOutcome::Unhealthy {
+            self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+            self.consecutive_successes = 0;
+        } else {
+            self.consecutive_successes = self.consecutive_successes.saturating_add(1);
+            self.consecutive_failures = 0;
+        }
+        self.impaired = outcome == CheckOutcome::Impaired;
+
+        let target = self.target_state(config);
+        if target == self.state {
+            return None;
+        }
+
+        // Unknown 没有需要保护的历史状态，可以立即离开
+        if self.state != NodeState::Unknown && now - self.since < config.min_dwell {
+            return None;
+        }
+
+        let transition = StateTransition {
+            from: self.state,
+            to: target,
+            at: now,
+        };
+        self.state = target;
+        self.since = now;
+        Some(transition)
+    }
+
+    fn target_state(&self, config: &StateMachineConfig) -> NodeState {
+        if self.consecutive_failures >= config.failure_threshold.max(1) {
+            return NodeState::Down;
+        }
+
+        if self.consecutive_failures > 0 {
+            return match self.state {
+                NodeState::Down => NodeState::Down,
+                _ => NodeState::Degraded,
+            };
+        }
+
+        let recovered = match self.state {
+            NodeState::Unknown | NodeState::Up => true,
+            NodeState::Degraded | NodeState::Down => {
+                self.consecutive_successes >= config.recovery_threshold.max(1)
+            }
+        };
+        if !recovered {
+            self.state
+        } else if self.impaired {
+            NodeState::Degraded
+        } else {
+            NodeState::Up
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(min_dwell_secs: i64) -> StateMachineConfig {
+        StateMachineConfig {
+            failure_threshold: 3,
+            recovery_threshold: 2,
+            min_dwell: chrono::Duration::seconds(min_dwell_secs),
+        }
+    }
+
+    #[test]
+    fn test_single_failure_does_not_flap_to_down() {
+        let cfg = config(0);
+        let start = Utc::now();
+        let mut sm = NodeStateMachine::new(start);
+
+        let t = sm.observe(CheckOutcome::Healthy, start, &cfg).unwrap();
+        assert_eq!((t.from, t.to), (NodeState::Unknown, NodeState::Up));
+
+        // 单次失败只降级
+        let t = sm.observe(CheckOutcome::Unhealthy, start, &cfg).unwrap();
+        assert_eq!(t.to, NodeState::Degraded);
+
+        // 一次成功不足以恢复，两次才恢复
+        assert!(sm.observe(CheckOutcome::Healthy, start, &cfg).is_none());
+        let t = sm.observe(CheckOutcome::Healthy, start, &cfg).unwrap();
+        assert_eq!((t.from, t.to), (NodeState::Degraded, NodeState::Up));
+    }
+
+    #[test]
+    fn test_consecutive_failures_reach_down() {
+        let cfg = config(0);
+        let start = Utc::now();
+        let mut sm = NodeStateMachine::restore(NodeState::Up, start);
+
+        assert_eq!(
+            sm.observe(CheckOutcome::Unhealthy, start, &cfg).unwrap().to,
+            NodeState::Degraded
+        );
+        assert!(sm.observe(CheckOutcome::Unhealthy, start, &cfg).is_none());
+        let t = sm.observe(CheckOutcome::Unhealthy, start, &cfg).unwrap();
+        assert_eq!((t.from, t.to), (NodeState::Degraded, NodeState::Down));
+
+        // 离线后的单次成功不会立即恢复，之后的失败也不会回到降级
+        assert!(sm.observe(CheckOutcome::Healthy, start, &cfg).is_none());
+        assert!(sm.observe(CheckOutcome::Unhealthy, start, &cfg).is_none());
+        assert_eq!(sm.state(), NodeState::Down);
+    }
+
+    #[test]
+    fn test_min_dwell_delays_transition() {
+        let cfg = config(30);
+        let start = Utc::now();
+        let mut sm = NodeStateMachine::restore(NodeState::Up, start);
+
+        // 停留时间不足，保持在线
+        for i in 1..=5 {
+            assert!(sm
+                .observe(
+                    CheckOutcome::Unhealthy,
+                    start + chrono::Duration::seconds(i * 5),
+                    &cfg
+                )
+                .is_none());
+        }
+        assert_eq!(sm.state(), NodeState::Up);
+
+        // 停留时间满足后直接按当前连续失败次数转换
+        let at = start + chrono::Duration::seconds(30);
+        let t = sm.observe(CheckOutcome::Unhealthy, at, &cfg).unwrap();
+        assert_eq!((t.from, t.to), (NodeState::Up, NodeState::Down));
+        assert_eq!(sm.since(), at);
+    }
+
+    #[test]
+    fn test_failing_listener_degrades_reachable_node() {
+        let cfg = config(0);
+        let start = Utc::now();
+        let mut sm = NodeStateMachine::restore(NodeState::Up, start);
+
+        // 主地址可用但监听地址失败，只降级不会离线
+        let t = sm.observe(CheckOutcome::Impaired, start, &cfg).unwrap();
+        assert_eq!((t.from, t.to), (NodeState::Up, NodeState::Degraded));
+        for _ in 0..5 {
+            assert!(sm.observe(CheckOutcome::Impaired, start, &cfg).is_none());
+        }
+        assert!(!sm.is_pending());
+
+        // 监听地址恢复后回到在线
+        let t = sm.observe(CheckOutcome::Healthy, start, &cfg).unwrap();
+        assert_eq!((t.from, t.to), (NodeState::Degraded, NodeState::Up));
+
+        // 离线节点的主地址恢复后，监听地址仍失败时恢复为降级
+        let mut sm = NodeStateMachine::restore(NodeState::Down, start);
+        assert!(sm.observe(CheckOutcome::Impaired, start, &cfg).is_none());
+        let t = sm.observe(CheckOutcome::Impaired, start, &cfg).unwrap();
+        assert_eq!((t.from, t.to), (NodeState::Down, NodeState::Degraded));
+    }
+}
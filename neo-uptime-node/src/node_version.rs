@@ -0,0 +1,150 @@
+use std::{cmp::Ordering, collections::HashMap, str::FromStr};
+
+use serde::Serialize;
+
+/// 节点版本号，只比较开头的数字部分，如 `2.4.5-4f3f3b2a` 按 `2.4.5` 比较
+///
+/// EasyTier 在版本号后附加提交哈希，不能按 semver 的预发布版本处理。
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NodeVersion {
+    /// 去掉末尾的 0，使 `2.4` 与 `2.4.0` 相等
+    parts: Vec<u64>,
+}
+
+impl FromStr for NodeVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let numeric = trimmed
+            .strip_prefix(['v', 'V'])
+            .unwrap_or(trimmed)
+            .split(|c: char| !c.is_ascii_digit() && c != '.')
+            .next()
+            .unwrap_or_default();
+        let mut parts = numeric
+            .split('.')
+            .map(|part| part.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| format!("invalid version '{}', expected x.y.z", s))?;
+        while parts.last() == Some(&0) {
+            parts.pop();
+        }
+        Ok(Self { parts })
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for NodeVersion {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <String as serde::Deserialize>::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl std::fmt::Display for NodeVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = self.parts.clone();
+        parts.resize(parts.len().max(3), 0);
+        let parts = parts.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        write!(f, "{}", parts.join("."))
+    }
+}
+
+/// 节点上报的版本是否低于要求的最低版本，未设置最低版本或无法解析的版本不算过旧
+pub fn is_outdated(version: &str, minimum: Option<&NodeVersion>) -> bool {
+    let Some(minimum) = minimum else {
+        return false;
+    };
+    version
+        .parse::<NodeVersion>()
+        .is_ok_and(|version| version < *minimum)
+}
+
+/// 运行某个版本的节点数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VersionCount {
+    pub version: String,
+    pub nodes: usize,
+    /// 低于最低版本
+    pub outdated: bool,
+}
+
+/// 按版本统计节点数，从新到旧排列，无法解析的版本排在最后；空版本（尚未检查到）不计入
+pub fn version_distribution<'a>(
+    versions: impl IntoIterator<Item = &'a str>,
+    minimum: Option<&NodeVersion>,
+) -> Vec<VersionCount> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for version in versions.into_iter().filter(|v| !v.is_empty()) {
+        *counts.entry(version).or_default() += 1;
+    }
+
+    let mut distribution = counts
+        .into_iter()
+        .map(|(version, nodes)| VersionCount {
+            version: version.to_string(),
+            nodes,
+            outdated: is_outdated(version, minimum),
+        })
+        .collect::<Vec<_>>();
+    distribution.sort_by(|a, b| {
+        let parsed = |v: &str| v.parse::<NodeVersion>().ok();
+        match (parsed(&a.version), parsed(&b.version)) {
+            (Some(x), Some(y)) => y.cmp(&x),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+        .then_with(|| a.version.cmp(&b.version))
+    });
+    distribution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(s: &str) -> NodeVersion {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_compare_versions() {
+        assert_eq!(version("2.4.5-4f3f3b2a"), version("2.4.5"));
+        assert_eq!(version("v2.4"), version("2.4.0"));
+        assert!(version("2.10.0") > version("2.9.1"));
+        assert!(version("2.4.5~rc1") < version("2.4.6"));
+        assert_eq!(version("2.4").to_string(), "2.4.0");
+        assert!("".parse::<NodeVersion>().is_err());
+        assert!("unknown".parse::<NodeVersion>().is_err());
+
+        let minimum = version("2.4.0");
+        assert!(is_outdated("2.3.9-abcdef", Some(&minimum)));
+        assert!(!is_outdated("2.4.0-abcdef", Some(&minimum)));
+        assert!(!is_outdated("2.3.9", None));
+        assert!(!is_outdated("garbage", Some(&minimum)));
+    }
+
+    #[test]
+    fn test_version_distribution() {
+        let minimum = version("2.4.0");
+        let distribution = version_distribution(
+            ["2.3.0-aa", "2.4.5-bb", "", "2.4.5-bb", "dev", "2.10.0-cc"],
+            Some(&minimum),
+        );
+        let summary = distribution
+            .iter()
+            .map(|c| (c.version.as_str(), c.nodes, c.outdated))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("2.10.0-cc", 1, false),
+                ("2.4.5-bb", 2, false),
+                ("2.3.0-aa", 1, true),
+                ("dev", 1, false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,419 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use anyhow::Context as _;
+use chrono::{DateTime, NaiveTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use tokio::sync::broadcast;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    db::{operations::NodeOperations, Db, NodeState},
+    node_state::NodeStateChange,
+};
+
+const SIGNATURE_HEADER: &str = "x-neo-uptime-signature";
+const TIMESTAMP_HEADER: &str = "x-neo-uptime-timestamp";
+
+// Retry backoff bounds for a single webhook delivery
+const DELIVERY_MIN_BACKOFF: Duration = Duration::from_secs(1);
+const DELIVERY_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// 节点每日静默时段（UTC），结束时间早于开始时间表示跨越午夜
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MuteWindow {
+    pub node_id: i32,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl MuteWindow {
+    /// 给定时间是否处于静默时段内
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let t = at.time();
+        if self.start <= self.end {
+            t >= self.start && t < self.end
+        } else {
+            t >= self.start || t < self.end
+        }
+    }
+}
+
+impl FromStr for MuteWindow {
+    type Err = anyhow::Error;
+
+    /// 格式：`<node_id>@<HH:MM>-<HH:MM>`，例如 `12@01:00-03:30`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (node_id, range) = s.trim().split_once('@').with_context(|| {
+            format!(
+                "Invalid mute window '{}', expected <node_id>@<HH:MM>-<HH:MM>",
+                s
+            )
+        })?;
+        let (start, end) = range
+            .split_once('-')
+            .with_context(|| format!("Invalid mute window range '{}'", range))?;
+
+        Ok(Self {
+            node_id: node_id
+                .parse()
+                .with_context(|| format!("Invalid node id in mute window '{}'", s))?,
+            start: NaiveTime::parse_from_str(start, "%H:%M")
+                .with_context(|| format!("Invalid start time in mute window '{}'", s))?,
+            end: NaiveTime::parse_from_str(end, "%H:%M")
+                .with_context(|| format!("Invalid end time in mute window '{}'", s))?,
+        })
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for MuteWindow {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <String as serde::Deserialize>::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Webhook 通知配置
+#[derive(Debug, Clone)]
+pub struct NotifierConfig {
+    /// 接收通知的 Webhook 地址
+    pub webhook_urls: Vec<String>,
+    /// HMAC-SHA256 签名密钥，未设置时不签名
+    pub secret: Option<String>,
+    /// 节点静默时段
+    pub mute_windows: Vec<MuteWindow>,
+    /// 聚合窗口：窗口内的状态变化合并处理
+    pub batch_window: Duration,
+    /// 窗口内变化超过该数量时合并为一条摘要
+    pub digest_threshold: usize,
+    /// 单次投递的最大尝试次数
+    pub max_attempts: u32,
+    /// 是否通知 Up 与 Degraded 之间的转换
+    pub notify_degraded: bool,
+}
+
+impl Default for NotifierConfig {
+    fn default() -> Self {
+        Self {
+            webhook_urls: Vec::new(),
+            secret: None,
+            mute_windows: Vec::new(),
+            batch_window: Duration::from_secs(10),
+            digest_threshold: 5,
+            max_attempts: 5,
+            notify_degraded: false,
+        }
+    }
+}
+
+/// 单个节点状态变化的通知内容
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeStateNotice {
+    pub node_id: i32,
+    pub node_name: Option<String>,
+    pub backend_peer_id: Option<i32>,
+    pub address: Option<String>,
+    pub from: String,
+    pub to: String,
+    pub reason: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// Webhook 请求体
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WebhookPayload {
+    NodeStateChanged {
+        event: NodeStateNotice,
+    },
+    NodeStateDigest {
+        count: usize,
+        events: Vec<NodeStateNotice>,
+    },
+}
+
+/// 节点状态变化的 Webhook 通知器
+pub struct Notifier {
+    db: Db,
+    config: NotifierConfig,
+    client: reqwest::Client,
+}
+
+impl Notifier {
+    pub fn new(db: Db, config: NotifierConfig) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to create webhook HTTP client")?;
+
+        Ok(Self { db, config, client })
+    }
+
+    /// 启动通知任务：在聚合窗口内收集状态变化，数量过多时合并为摘要
+    pub fn start(
+        self,
+        mut changes: broadcast::Receiver<NodeStateChange>,
+    ) -> tokio::task::JoinHandle<()> {
+        let notifier = Arc::new(self);
+        tokio::spawn(async move {
+            while let Some(first) = notifier.next_notifiable(&mut changes).await {
+                let mut pending = vec![first];
+                let deadline = tokio::time::Instant::now() + notifier.config.batch_window;
+
+                loop {
+                    tokio::select! {
+                        change = notifier.next_notifiable(&mut changes) => match change {
+                            Some(change) => pending.push(change),
+                            None => break,
+                        },
+                        _ = tokio::time::sleep_until(deadline) => break,
+                    }
+                }
+
+                let notices = notifier.build_notices(pending).await;
+                for payload in build_payloads(notices, notifier.config.digest_threshold) {
+                    let notifier = Arc::clone(&notifier);
+                    tokio::spawn(async move { notifier.deliver(&payload).await });
+                }
+            }
+
+            info!("State change channel closed, webhook notifier stopped");
+        })
+    }
+
+    /// 等待下一条需要通知的状态变化，通道关闭时返回 None
+    async fn next_notifiable(
+        &self,
+        changes: &mut broadcast::Receiver<NodeStateChange>,
+    ) -> Option<NodeStateChange> {
+        loop {
+            match changes.recv().await {
+                Ok(change) if self.should_notify(&change) => return Some(change),
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Webhook notifier lagged, {} state changes dropped", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    fn should_notify(&self, change: &NodeStateChange) -> bool {
+        let transition = &change.transition;
+
+        // 启动后的首次判定不是真正的变化
+        if transition.from == NodeState::Unknown {
+            return false;
+        }
+
+        if let Some(window) = self
+            .config
+            .mute_windows
+            .iter()
+            .find(|w| w.node_id == change.node_id && w.contains(transition.at))
+        {
+            debug!(
+                "Node {} is muted ({}-{}), skipping notification for {} -> {}",
+                change.node_id, window.start, window.end, transition.from, transition.to
+            );
+            return false;
+        }
+
+        transition.to == NodeState::Down
+            || transition.from == NodeState::Down
+            || self.config.notify_degraded
+    }
+
+    async fn build_notices(&self, changes: Vec<NodeStateChange>) -> Vec<NodeStateNotice> {
+        let mut notices = Vec::with_capacity(changes.len());
+        for change in changes {
+            let node = match NodeOperations::get_node_by_id(&self.db, change.node_id).await {
+                Ok(node) => node,
+                Err(e) => {
+                    error!(
+                        "Failed to get node {} for notification: {}",
+                        change.node_id, e
+                    );
+                    None
+                }
+            };
+
+            notices.push(NodeStateNotice {
+                node_id: change.node_id,
+                node_name: node.as_ref().map(|n| n.name.clone()),
+                backend_peer_id: node.as_ref().and_then(|n| n.backend_peer_id),
+                address: node
+                    .as_ref()
+                    .map(|n| format!("{}://{}:{}", n.protocol, n.host, n.port)),
+                from: change.transition.from.to_string(),
+                to: change.transition.to.to_string(),
+                reason: change.reason,
+                at: change.transition.at,
+            });
+        }
+        notices
+    }
+
+    /// 向所有 Webhook 地址投递
+    async fn deliver(&self, payload: &WebhookPayload) {
+        let body = match serde_json::to_vec(payload) {
+            Ok(body) => body,
+            Err(e) => {
+                error!("Failed to serialize webhook payload: {}", e);
+                return;
+            }
+        };
+
+        for url in &self.config.webhook_urls {
+            self.deliver_to(url, &body).await;
+        }
+    }
+
+    /// 投递到单个地址，网络错误、429 和 5xx 时指数退避重试
+    async fn deliver_to(&self, url: &str, body: &[u8]) {
+        let mut backoff = DELIVERY_MIN_BACKOFF;
+        let max_attempts = self.config.max_attempts.max(1);
+
+        for attempt in 1..=max_attempts {
+            let retryable = match self.post(url, body).await {
+                Ok(status) if status.is_success() => {
+                    debug!("Delivered webhook to {}", url);
+                    return;
+                }
+                Ok(status) => {
+                    warn!(
+                        "Webhook {} returned {} (attempt {}/{})",
+                        url, status, attempt, max_attempts
+                    );
+                    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to deliver webhook to {} (attempt {}/{}): {}",
+                        url, attempt, max_attempts, e
+                    );
+                    true
+                }
+            };
+
+            if !retryable {
+                break;
+            }
+            if attempt < max_attempts {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(DELIVERY_MAX_BACKOFF);
+            }
+        }
+
+        error!("Giving up webhook delivery to {}", url);
+    }
+
+    async fn post(&self, url: &str, body: &[u8]) -> anyhow::Result<reqwest::StatusCode> {
+        let mut request = self
+            .client
+            .post(url)
+            .header("content-type", "application/json")
+            .header("user-agent", "neo-uptime-node")
+            .body(body.to_vec());
+
+        if let Some(secret) = &self.config.secret {
+            let timestamp = Utc::now().timestamp();
+            request = request
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(SIGNATURE_HEADER, sign_payload(secret, timestamp, body));
+        }
+
+        let response = request.send().await.context("Failed to send webhook")?;
+        Ok(response.status())
+    }
+}
+
+/// 计算签名：HMAC-SHA256(secret, "{timestamp}.{body}")
+pub fn sign_payload(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// 变化数量不超过阈值时逐条通知，否则合并为一条摘要
+fn build_payloads(notices: Vec<NodeStateNotice>, digest_threshold: usize) -> Vec<WebhookPayload> {
+    if notices.len() > digest_threshold {
+        vec![WebhookPayload::NodeStateDigest {
+            count: notices.len(),
+            events: notices,
+        }]
+    } else {
+        notices
+            .into_iter()
+            .map(|event| WebhookPayload::NodeStateChanged { event })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notice(node_id: i32) -> NodeStateNotice {
+        NodeStateNotice {
+            node_id,
+            node_name: Some(format!("node-{}", node_id)),
+            backend_peer_id: Some(node_id + 100),
+            address: None,
+            from: "up".to_string(),
+            to: "down".to_string(),
+            reason: None,
+            at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_sign_payload() {
+        assert_eq!(
+            sign_payload("secret", 1_700_000_000, br#"{"a":1}"#),
+            "sha256=49f24e537407743fa4a0242bb63b94b9a47ee99cbbe071ccd8a22550ae411686"
+        );
+    }
+
+    #[test]
+    fn test_mute_window() {
+        let window: MuteWindow = "12@23:00-02:00".parse().unwrap();
+        assert_eq!(window.node_id, 12);
+
+        let at = |h: u32, m: u32| {
+            Utc::now()
+                .date_naive()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+                .and_utc()
+        };
+        assert!(window.contains(at(23, 30)));
+        assert!(window.contains(at(1, 59)));
+        assert!(!window.contains(at(2, 0)));
+        assert!(!window.contains(at(12, 0)));
+
+        assert!("12@25:00-02:00".parse::<MuteWindow>().is_err());
+        assert!("01:00-02:00".parse::<MuteWindow>().is_err());
+    }
+
+    #[test]
+    fn test_build_payloads_digest() {
+        let payloads = build_payloads(vec![notice(1), notice(2)], 2);
+        assert_eq!(payloads.len(), 2);
+        let json = serde_json::to_value(&payloads[0]).unwrap();
+        assert_eq!(json["type"], "node_state_changed");
+        assert_eq!(json["event"]["node_id"], 1);
+
+        let payloads = build_payloads((1..=3).map(notice).collect(), 2);
+        assert_eq!(payloads.len(), 1);
+        let json = serde_json::to_value(&payloads[0]).unwrap();
+        assert_eq!(json["type"], "node_state_digest");
+        assert_eq!(json["count"], 3);
+        assert_eq!(json["events"].as_array().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,296 @@
+use std::{sync::Arc, time::Duration};
+
+use sea_orm::Set;
+use tokio::sync::Notify;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    backend_client::HeartbeatItem,
+    db::{entity::heartbeat_outbox, operations::OutboxOperations, Db, ProbeErrorKind},
+    metrics,
+    probe_backend::ProbeBackend,
+};
+
+// Replay backoff bounds while the backend is unreachable
+const REPLAY_MIN_BACKOFF: Duration = Duration::from_secs(5);
+const REPLAY_MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// 离线心跳暂存配置
+#[derive(Debug, Clone)]
+pub struct OutboxConfig {
+    /// 心跳最长保留时间，超过后丢弃
+    pub max_age: chrono::Duration,
+    /// 最多暂存的心跳数量，超过后丢弃最旧的
+    pub max_entries: u64,
+    /// 每次重放的批大小
+    pub replay_batch_size: u64,
+    /// 单条心跳的最大投递次数
+    pub max_attempts: i32,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            max_age: chrono::Duration::hours(24),
+            max_entries: 50_000,
+            replay_batch_size: 200,
+            max_attempts: 10,
+        }
+    }
+}
+
+/// 一轮重放的结果
+#[derive(Debug, Default)]
+struct ReplayResult {
+    delivered: u64,
+    /// 暂时失败、留待下一轮重放的心跳
+    retrying: u64,
+}
+
+/// SQLite 持久化的心跳发件箱，后端恢复后按检查时间顺序重放
+pub struct HeartbeatOutbox {
+    db: Db,
+    config: OutboxConfig,
+    notify: Notify,
+}
+
+impl HeartbeatOutbox {
+    pub fn new(db: Db, config: OutboxConfig) -> Self {
+        Self {
+            db,
+            config,
+            notify: Notify::new(),
+        }
+    }
+
+    /// 是否有待重放的心跳
+    pub async fn is_empty(&self) -> anyhow::Result<bool> {
+        Ok(OutboxOperations::count(&self.db).await? == 0)
+    }
+
+    /// 暂存未送达的心跳，保留原始检查时间
+    pub async fn store(&self, items: &[HeartbeatItem], error: &str) -> anyhow::Result<()> {
+        let now = chrono::Utc::now();
+        let entries = items
+            .iter()
+            .map(|item| heartbeat_outbox::ActiveModel {
+                backend_peer_id: Set(item.node_id),
+                status: Set(item.status.clone()),
+                peer: Set(item.peer),
+                latency_ms: Set(item.latency_ms),
+                checked_at: Set(item.checked_at.unwrap_or(now).fixed_offset()),
+                attempts: Set(0),
+                last_error: Set(Some(error.to_string())),
+                created_at: Set(now.fixed_offset()),
+                state: Set(item.state.clone()),
+                quality: Set(item
+                    .quality
+                    .as_ref()
+                    .and_then(|q| serde_json::to_string(q).ok())),
+                error_kind: Set(item.error_kind.map(|kind| kind.to_string())),
+                listeners: Set((!item.listeners.is_empty())
+                    .then(|| serde_json::to_string(&item.listeners).ok())
+                    .flatten()),
+                load: Set(item
+                    .load
+                    .as_ref()
+                    .and_then(|l| serde_json::to_string(l).ok())),
+                certificates: Set((!item.certificates.is_empty())
+                    .then(|| serde_json::to_string(&item.certificates).ok())
+                    .flatten()),
+                version: Set(item.version.clone()),
+                version_outdated: Set(item.version_outdated),
+                features: Set((!item.features.is_empty())
+                    .then(|| serde_json::to_string(&item.features).ok())
+                    .flatten()),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        let trimmed = OutboxOperations::enqueue(&self.db, entries, self.config.max_entries).await?;
+        metrics::global().record_dropped_heartbeats("outbox_full", trimmed);
+        if trimmed > 0 {
+            warn!(
+                "Heartbeat outbox is full, dropped {} oldest heartbeats (max {})",
+                trimmed, self.config.max_entries
+            );
+        }
+        debug!("Stored {} undelivered heartbeats in outbox", items.len());
+
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    /// 启动重放任务：后端连接恢复后按顺序投递暂存的心跳
+    pub fn start_replay_task(
+        self: &Arc<Self>,
+        backend: Arc<dyn ProbeBackend>,
+    ) -> tokio::task::JoinHandle<()> {
+        let outbox = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut backoff = REPLAY_MIN_BACKOFF;
+            let mut idle = true;
+
+            loop {
+                if idle {
+                    // 发件箱为空时等待新的暂存，或定期检查一次
+                    tokio::select! {
+                        _ = outbox.notify.notified() => {}
+                        _ = tokio::time::sleep(REPLAY_MAX_BACKOFF) => {}
+                    }
+                } else {
+                    tokio::time::sleep(backoff).await;
+                }
+
+                match OutboxOperations::prune_older_than(&outbox.db, outbox.config.max_age).await {
+                    Ok(pruned) if pruned > 0 => {
+                        metrics::global().record_dropped_heartbeats("max_age", pruned);
+                        warn!("Dropped {} heartbeats older than {:?} from outbox", pruned, outbox.config.max_age);
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!("Failed to prune heartbeat outbox: {}", e),
+                }
+
+                match outbox.is_empty().await {
+                    Ok(true) => {
+                        idle = true;
+                        backoff = REPLAY_MIN_BACKOFF;
+                        continue;
+                    }
+                    Ok(false) => idle = false,
+                    Err(e) => {
+                        error!("Failed to read heartbeat outbox: {}", e);
+                        idle = false;
+                        continue;
+                    }
+                }
+
+                if let Err(e) = backend.test_connection().await {
+                    debug!("Backend still unreachable, delaying outbox replay: {}", e);
+                    backoff = (backoff * 2).min(REPLAY_MAX_BACKOFF);
+                    continue;
+                }
+
+                match outbox.replay(backend.as_ref()).await {
+                    Ok(result) if result.retrying > 0 => {
+                        warn!(
+                            "Replayed {} heartbeats from outbox, {} failed and will be retried",
+                            result.delivered, result.retrying
+                        );
+                        backoff = (backoff * 2).min(REPLAY_MAX_BACKOFF);
+                    }
+                    Ok(result) => {
+                        info!("Replayed {} heartbeats from outbox", result.delivered);
+                        backoff = REPLAY_MIN_BACKOFF;
+                    }
+                    Err(e) => {
+                        warn!("Outbox replay interrupted: {}", e);
+                        backoff = (backoff * 2).min(REPLAY_MAX_BACKOFF);
+                    }
+                }
+            }
+        })
+    }
+
+    /// 按检查时间顺序遍历一遍发件箱并投递
+    ///
+    /// 后端拒收的心跳直接丢弃；暂时失败的心跳留在原处等待下一轮，不阻塞排在后面的心跳。
+    async fn replay(&self, backend: &dyn ProbeBackend) -> anyhow::Result<ReplayResult> {
+        let mut result = ReplayResult::default();
+        let mut last: Option<heartbeat_outbox::Model> = None;
+
+        loop {
+            let batch = OutboxOperations::fetch_after(
+                &self.db,
+                last.as_ref(),
+                self.config.replay_batch_size,
+            )
+            .await?;
+            let Some(tail) = batch.last() else {
+                return Ok(result);
+            };
+            last = Some(tail.clone());
+
+            let items = batch
+                .iter()
+                .map(|entry| HeartbeatItem {
+                    node_id: entry.backend_peer_id,
+                    status: entry.status.clone(),
+                    peer: entry.peer,
+                    latency_ms: entry.latency_ms,
+                    checked_at: Some(entry.checked_at.to_utc()),
+                    state: entry.state.clone(),
+                    quality: entry
+                        .quality
+                        .as_deref()
+                        .and_then(|q| serde_json::from_str(q).ok()),
+                    error_kind: entry.error_kind.as_deref().map(ProbeErrorKind::from),
+                    listeners: entry
+                        .listeners
+                        .as_deref()
+                        .and_then(|l| serde_json::from_str(l).ok())
+                        .unwrap_or_default(),
+                    load: entry
+                        .load
+                        .as_deref()
+                        .and_then(|l| serde_json::from_str(l).ok()),
+                    certificates: entry
+                        .certificates
+                        .as_deref()
+                        .and_then(|c| serde_json::from_str(c).ok())
+                        .unwrap_or_default(),
+                    version: entry.version.clone(),
+                    version_outdated: entry.version_outdated,
+                    features: entry
+                        .features
+                        .as_deref()
+                        .and_then(|f| serde_json::from_str(f).ok())
+                        .unwrap_or_default(),
+                })
+                .collect::<Vec<_>>();
+
+            let outcomes = backend.report_heartbeats(&items).await?;
+
+            let mut finished_ids = Vec::new();
+            let (mut rejected, mut exhausted) = (0, 0);
+            for (entry, outcome) in batch.into_iter().zip(outcomes) {
+                if outcome.is_success() {
+                    finished_ids.push(entry.id);
+                    result.delivered += 1;
+                    continue;
+                }
+                let rejected_for_good = outcome.is_rejected();
+                let error = outcome.error.unwrap_or_default();
+                if rejected_for_good {
+                    warn!(
+                        "Backend rejected replayed heartbeat for backend peer {}, dropping it: {}",
+                        entry.backend_peer_id, error
+                    );
+                    finished_ids.push(entry.id);
+                    rejected += 1;
+                } else if OutboxOperations::record_failure(
+                    &self.db,
+                    entry,
+                    error,
+                    self.config.max_attempts,
+                )
+                .await?
+                {
+                    exhausted += 1;
+                } else {
+                    result.retrying += 1;
+                }
+            }
+
+            OutboxOperations::delete_by_ids(&self.db, finished_ids).await?;
+            metrics::global().record_dropped_heartbeats("rejected", rejected);
+            metrics::global().record_dropped_heartbeats("max_attempts", exhausted);
+            if exhausted > 0 {
+                warn!(
+                    "Dropped {} heartbeats after {} delivery attempts",
+                    exhausted, self.config.max_attempts
+                );
+            }
+        }
+    }
+}
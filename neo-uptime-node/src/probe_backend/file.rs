@@ -0,0 +1,185 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt as _;
+use tracing::debug;
+
+use super::ProbeBackend;
+use crate::backend_client::{BackendPeer, HeartbeatItem, HeartbeatOutcome};
+
+/// 节点文件内容：JSON 可以直接是节点数组，TOML 使用 `[[peers]]` 表数组
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PeersFile {
+    List(Vec<BackendPeer>),
+    Table {
+        #[serde(default)]
+        peers: Vec<BackendPeer>,
+    },
+}
+
+impl PeersFile {
+    fn into_peers(self) -> Vec<BackendPeer> {
+        match self {
+            PeersFile::List(peers) | PeersFile::Table { peers } => peers,
+        }
+    }
+}
+
+/// 基于本地文件的后端：从 JSON/TOML 文件读取节点，心跳以 JSON Lines 追加写入报告文件
+///
+/// 每次获取节点时重新读取文件，修改文件即可增删监控节点。
+pub struct FileBackend {
+    peers_path: PathBuf,
+    report_path: PathBuf,
+}
+
+impl FileBackend {
+    pub fn new(peers_path: impl Into<PathBuf>, report_path: impl Into<PathBuf>) -> Self {
+        Self {
+            peers_path: peers_path.into(),
+            report_path: report_path.into(),
+        }
+    }
+
+    fn parse_peers(path: &Path, content: &str) -> anyhow::Result<Vec<BackendPeer>> {
+        let is_toml = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+        let file: PeersFile = if is_toml {
+            toml::from_str(content).context("invalid TOML peers file")?
+        } else {
+            serde_json::from_str(content).context("invalid JSON peers file")?
+        };
+        Ok(file.into_peers())
+    }
+}
+
+#[async_trait]
+impl ProbeBackend for FileBackend {
+    async fn register_probe(&self) -> anyhow::Result<()> {
+        let peers = self.fetch_peers(None).await?;
+        debug!(
+            "Using peers file {} with {} peers, reports go to {}",
+            self.peers_path.display(),
+            peers.len(),
+            self.report_path.display()
+        );
+        Ok(())
+    }
+
+    async fn test_connection(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn fetch_peers(&self, _region: Option<&str>) -> anyhow::Result<Vec<BackendPeer>> {
+        let content = tokio::fs::read_to_string(&self.peers_path)
+            .await
+            .with_context(|| format!("failed to read {}", self.peers_path.display()))?;
+        Self::parse_peers(&self.peers_path, &content)
+            .with_context(|| format!("failed to parse {}", self.peers_path.display()))
+    }
+
+    async fn report_heartbeats(
+        &self,
+        items: &[HeartbeatItem],
+    ) -> anyhow::Result<Vec<HeartbeatOutcome>> {
+        let mut lines = String::new();
+        for item in items {
+            lines.push_str(&serde_json::to_string(item)?);
+            lines.push('\n');
+        }
+
+        if let Some(dir) = self
+            .report_path
+            .parent()
+            .filter(|d| !d.as_os_str().is_empty())
+        {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.report_path)
+            .await
+            .with_context(|| format!("failed to open {}", self.report_path.display()))?;
+        file.write_all(lines.as_bytes()).await?;
+        file.flush().await?;
+
+        Ok(items
+            .iter()
+            .map(|item| HeartbeatOutcome {
+                node_id: item.node_id,
+                error: None,
+                rejected: false,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend_client::tests::heartbeat_item;
+
+    #[tokio::test]
+    async fn test_file_backend_round_trip() {
+        let dir =
+            std::env::temp_dir().join(format!("neo-uptime-file-backend-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+
+        let json_path = dir.join("peers.json");
+        tokio::fs::write(
+            &json_path,
+            r#"[{ "id": 1, "name": "a", "public_ip": "a.example.com:11010", "protocol": "tcp" }]"#,
+        )
+        .await
+        .unwrap();
+        let toml_path = dir.join("peers.toml");
+        tokio::fs::write(
+            &toml_path,
+            "[[peers]]\nid = 2\nname = \"b\"\npublic_ip = \"b.example.com\"\nlisteners = [\"udp://b.example.com:11010\"]\n",
+        )
+        .await
+        .unwrap();
+
+        let report_path = dir.join("reports").join("heartbeats.jsonl");
+        let backend = FileBackend::new(&json_path, &report_path);
+        backend.register_probe().await.unwrap();
+        let peers = backend.fetch_peers(None).await.unwrap();
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].public_ip.as_deref(), Some("a.example.com:11010"));
+
+        let peers = FileBackend::new(&toml_path, &report_path)
+            .fetch_peers(None)
+            .await
+            .unwrap();
+        assert_eq!(peers[0].id, 2);
+        assert_eq!(peers[0].listeners, vec!["udp://b.example.com:11010"]);
+
+        // 心跳逐行追加写入
+        let item = HeartbeatItem {
+            latency_ms: 12,
+            state: Some("up".to_string()),
+            ..heartbeat_item(1)
+        };
+        for _ in 0..2 {
+            let outcomes = backend
+                .report_heartbeats(std::slice::from_ref(&item))
+                .await
+                .unwrap();
+            assert!(outcomes.iter().all(|o| o.is_success()));
+        }
+        let report = tokio::fs::read_to_string(&report_path).await.unwrap();
+        let reported = report
+            .lines()
+            .map(|line| serde_json::from_str::<HeartbeatItem>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(reported.len(), 2);
+        assert_eq!(reported[0].latency_ms, 12);
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
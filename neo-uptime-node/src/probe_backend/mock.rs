@@ -0,0 +1,120 @@
+//! 测试用的进程内 HTTP 后端，实现探测节点用到的后端接口
+
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use tokio::task::JoinHandle;
+
+use crate::backend_client::{BackendPeer, HeartbeatItem};
+
+#[derive(Default)]
+struct MockState {
+    peers: Vec<BackendPeer>,
+    heartbeats: Vec<HeartbeatItem>,
+}
+
+type SharedState = Arc<Mutex<MockState>>;
+
+#[derive(Deserialize)]
+struct BatchHeartbeats {
+    heartbeats: Vec<HeartbeatItem>,
+}
+
+/// 监听本地随机端口的模拟后端，drop 时停止服务
+pub struct MockBackend {
+    base_url: String,
+    state: SharedState,
+    server: JoinHandle<()>,
+}
+
+impl MockBackend {
+    /// 启动模拟后端，`peers` 同时作为公开状态和私有信息返回
+    pub async fn start(peers: Vec<BackendPeer>) -> Self {
+        let state = Arc::new(Mutex::new(MockState {
+            peers,
+            heartbeats: Vec::new(),
+        }));
+        let app = Router::new()
+            .route("/node-status", get(node_status))
+            .route("/nodes/private-info", get(private_info_list))
+            .route("/nodes/:id/private-info", get(private_info))
+            .route("/nodes/heartbeats", post(heartbeats))
+            .with_state(state.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        Self {
+            base_url,
+            state,
+            server,
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// 迄今收到的全部心跳，按接收顺序
+    pub fn heartbeats(&self) -> Vec<HeartbeatItem> {
+        self.state.lock().unwrap().heartbeats.clone()
+    }
+}
+
+impl Drop for MockBackend {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn node_status(State(state): State<SharedState>) -> impl IntoResponse {
+    let statuses = state
+        .lock()
+        .unwrap()
+        .peers
+        .iter()
+        .map(|peer| serde_json::json!({ "node_id": peer.id, "status": peer.status }))
+        .collect::<Vec<_>>();
+    Json(statuses)
+}
+
+async fn private_info_list(State(state): State<SharedState>) -> impl IntoResponse {
+    Json(state.lock().unwrap().peers.clone())
+}
+
+async fn private_info(State(state): State<SharedState>, Path(id): Path<i32>) -> impl IntoResponse {
+    let peer = state
+        .lock()
+        .unwrap()
+        .peers
+        .iter()
+        .find(|peer| peer.id == id)
+        .cloned();
+    match peer {
+        Some(peer) => Json(peer).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn heartbeats(
+    State(state): State<SharedState>,
+    Json(request): Json<BatchHeartbeats>,
+) -> impl IntoResponse {
+    let results = request
+        .heartbeats
+        .iter()
+        .map(|item| serde_json::json!({ "node_id": item.node_id, "success": true }))
+        .collect::<Vec<_>>();
+    state.lock().unwrap().heartbeats.extend(request.heartbeats);
+    Json(serde_json::json!({ "success": true, "results": results }))
+}
@@ -0,0 +1,56 @@
+//! 探测节点与后端之间的适配层
+//!
+//! `BackendClient` 是对接 HTTP 后端的实现；`FileBackend` 从本地文件读取节点、把心跳写入磁盘，
+//! 便于离线运行和调试。
+
+mod file;
+#[cfg(test)]
+pub mod mock;
+
+use async_trait::async_trait;
+
+use crate::{
+    backend_client::{BackendPeer, HeartbeatItem, HeartbeatOutcome, ProbeInfo},
+    sla::NodeSla,
+};
+
+pub use file::FileBackend;
+
+/// 探测节点使用的后端能力：注册、获取待监控节点、上报心跳
+#[async_trait]
+pub trait ProbeBackend: Send + Sync {
+    /// 启动时向后端登记探测节点，失败时探测节点不会启动
+    async fn register_probe(&self) -> anyhow::Result<()>;
+
+    /// 检查后端当前是否可达，用于决定何时重放暂存的心跳
+    async fn test_connection(&self) -> anyhow::Result<()>;
+
+    /// 获取需要监控的节点列表
+    async fn fetch_peers(&self, region: Option<&str>) -> anyhow::Result<Vec<BackendPeer>>;
+
+    /// 上报心跳，按输入顺序返回每条心跳的结果；整体失败（如后端不可达）时返回错误
+    async fn report_heartbeats(
+        &self,
+        items: &[HeartbeatItem],
+    ) -> anyhow::Result<Vec<HeartbeatOutcome>>;
+
+    /// 后端分配给本探测节点的ID，未注册时为 `None`
+    fn probe_id(&self) -> Option<String> {
+        None
+    }
+
+    /// 获取同一区域内的探测节点，用于分配被监控节点；后端不支持时返回 `None`，即监控全部节点
+    async fn fetch_probes(&self, _region: Option<&str>) -> anyhow::Result<Option<Vec<ProbeInfo>>> {
+        Ok(None)
+    }
+
+    /// 上报节点的 SLA，`node_id` 为后端节点ID；后端不支持时忽略
+    async fn report_sla(&self, _items: &[NodeSla]) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// 退出前通知后端探测节点下线，以便其负责的节点尽快由其他探测节点接管；后端不支持时忽略
+    async fn report_offline(&self, _reason: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
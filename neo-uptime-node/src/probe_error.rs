@@ -0,0 +1,175 @@
+use std::fmt;
+
+use crate::db::ProbeErrorKind;
+
+// Error text fragments (lowercase) emitted by EasyTier connectors, checked in order
+const ERROR_PATTERNS: &[(ProbeErrorKind, &[&str])] = &[
+    (
+        ProbeErrorKind::DnsResolutionFailed,
+        &[
+            "dns lookup",
+            "failed to lookup address",
+            "name or service not known",
+            "no record found",
+            "norecordsfound",
+            "cannot get ip from url",
+        ],
+    ),
+    (
+        ProbeErrorKind::HandshakeRejected,
+        &[
+            "network secret",
+            "secret digest",
+            "secretkeyerror",
+            "waitresperror",
+            "handshake",
+            "peer id conflict",
+        ],
+    ),
+    (
+        ProbeErrorKind::ConnectRefused,
+        &["connectionrefused", "connection refused"],
+    ),
+    (
+        ProbeErrorKind::NoRoute,
+        &[
+            "hostunreachable",
+            "networkunreachable",
+            "no route to host",
+            "network is unreachable",
+        ],
+    ),
+    (
+        ProbeErrorKind::ConnectTimeout,
+        &["elapsed", "timeout", "timed out", "timedout"],
+    ),
+];
+
+/// 带分类的检查失败错误
+#[derive(Debug)]
+pub struct ProbeError {
+    pub kind: ProbeErrorKind,
+    message: String,
+}
+
+impl ProbeError {
+    pub fn new(kind: ProbeErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ProbeError {}
+
+/// 获取检查错误的分类，未分类的错误视为 `Unknown`
+pub fn error_kind(err: &anyhow::Error) -> ProbeErrorKind {
+    err.downcast_ref::<ProbeError>()
+        .map(|e| e.kind)
+        .unwrap_or(ProbeErrorKind::Unknown)
+}
+
+/// 根据 EasyTier 连接错误文本判断失败原因
+pub fn classify_connect_error(err: &str) -> ProbeErrorKind {
+    let err = err.to_lowercase();
+    ERROR_PATTERNS
+        .iter()
+        .find(|(_, patterns)| patterns.iter().any(|p| err.contains(p)))
+        .map(|(kind, _)| *kind)
+        .unwrap_or(ProbeErrorKind::Unknown)
+}
+
+/// 从实例事件（JSON，按时间倒序）中取出最近一次连接错误
+///
+/// 最近一次连接建立之后的错误才有意义，更早的错误已经过时。
+pub fn latest_connect_error(events: &[String]) -> Option<String> {
+    for event in events {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(event) else {
+            continue;
+        };
+        let event = &value["event"];
+        if event.get("PeerConnAdded").is_some() || event.get("PeerAdded").is_some() {
+            return None;
+        }
+        // ConnectError(dst, ip version, error message)
+        if let Some(err) = event["ConnectError"][2].as_str() {
+            return Some(err.to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_connect_error() {
+        let cases = [
+            (
+                "AnyhowError(hickory dns lookup_ip failed, host: x.invalid, port: 11010)",
+                ProbeErrorKind::DnsResolutionFailed,
+            ),
+            (
+                "Err(TunnelError(IOError(Os { code: 111, kind: ConnectionRefused, message: \"Connection refused\" })))",
+                ProbeErrorKind::ConnectRefused,
+            ),
+            (
+                "Err(Timeout(Elapsed(())))",
+                ProbeErrorKind::ConnectTimeout,
+            ),
+            (
+                "Err(WaitRespError(\"invalid network secret digest\"))",
+                ProbeErrorKind::HandshakeRejected,
+            ),
+            (
+                "Err(WaitRespError(\"wait handshake timeout: Elapsed(())\"))",
+                ProbeErrorKind::HandshakeRejected,
+            ),
+            (
+                "Err(TunnelError(IOError(Os { code: 113, kind: HostUnreachable, message: \"No route to host\" })))",
+                ProbeErrorKind::NoRoute,
+            ),
+            ("something else", ProbeErrorKind::Unknown),
+        ];
+        for (err, kind) in cases {
+            assert_eq!(classify_connect_error(err), kind, "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_latest_connect_error() {
+        let connect_error = r#"{"time":"2025-01-01T00:00:02+00:00","event":{"ConnectError":["tcp://a:11010","Both","Err(Timeout(Elapsed(())))"]}}"#;
+        let connecting =
+            r#"{"time":"2025-01-01T00:00:01+00:00","event":{"Connecting":"tcp://a:11010"}}"#;
+        let peer_added = r#"{"time":"2025-01-01T00:00:00+00:00","event":{"PeerAdded":1}}"#;
+
+        let events = [connect_error, connecting, peer_added].map(String::from);
+        assert_eq!(
+            latest_connect_error(&events).as_deref(),
+            Some("Err(Timeout(Elapsed(())))")
+        );
+
+        // 连接建立之后没有新的错误
+        let events = [peer_added, connect_error].map(String::from);
+        assert_eq!(latest_connect_error(&events), None);
+    }
+
+    #[test]
+    fn test_error_kind_downcast() {
+        let err: anyhow::Error = ProbeError::new(ProbeErrorKind::NoRoute, "no route").into();
+        assert_eq!(error_kind(&err), ProbeErrorKind::NoRoute);
+        assert_eq!(err.to_string(), "no route");
+        assert_eq!(
+            error_kind(&anyhow::anyhow!("plain")),
+            ProbeErrorKind::Unknown
+        );
+    }
+}
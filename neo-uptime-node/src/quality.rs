@@ -0,0 +1,137 @@
+use std::collections::VecDeque;
+
+use crate::db::LinkQuality;
+
+/// 默认滑动窗口大小（检查次数）
+pub const DEFAULT_QUALITY_WINDOW: usize = 60;
+
+#[derive(Debug, Clone, Copy)]
+struct QualitySample {
+    /// 失败的检查没有 RTT
+    rtt_us: Option<u64>,
+    loss_rate: f64,
+}
+
+/// 最近若干次检查的链路质量滑动窗口
+///
+/// 成功检查的丢包率取 EasyTier 连接上报的 `loss_rate`，失败的检查按 100% 丢包计入；
+/// 抖动和延迟分位数只在成功检查的 RTT 上计算。
+#[derive(Debug, Clone, Default)]
+pub struct QualityWindow {
+    samples: VecDeque<QualitySample>,
+}
+
+impl QualityWindow {
+    /// 加入一次成功检查的结果，超出窗口大小时丢弃最旧的样本
+    pub fn push(&mut self, rtt_us: u64, loss_rate: f64, window_size: usize) {
+        self.push_sample(
+            QualitySample {
+                rtt_us: Some(rtt_us),
+                loss_rate: loss_rate.clamp(0.0, 1.0),
+            },
+            window_size,
+        );
+    }
+
+    /// 加入一次失败的检查，按全部丢包计算
+    pub fn push_failure(&mut self, window_size: usize) {
+        self.push_sample(
+            QualitySample {
+                rtt_us: None,
+                loss_rate: 1.0,
+            },
+            window_size,
+        );
+    }
+
+    fn push_sample(&mut self, sample: QualitySample, window_size: usize) {
+        self.samples.push_back(sample);
+        while self.samples.len() > window_size.max(1) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// 计算窗口内的丢包率、抖动和延迟分位数
+    pub fn stats(&self) -> LinkQuality {
+        let n = self.samples.len();
+        if n == 0 {
+            return LinkQuality::default();
+        }
+
+        let loss_rate = self.samples.iter().map(|s| s.loss_rate).sum::<f64>() / n as f64;
+
+        let mut rtts = self
+            .samples
+            .iter()
+            .filter_map(|s| s.rtt_us)
+            .collect::<Vec<_>>();
+        if rtts.is_empty() {
+            return LinkQuality {
+                loss_rate: Some(loss_rate),
+                ..LinkQuality::default()
+            };
+        }
+        rtts.sort_unstable();
+        let n = rtts.len();
+        let mean = rtts.iter().sum::<u64>() as f64 / n as f64;
+        let variance = rtts
+            .iter()
+            .map(|&rtt| (rtt as f64 - mean).powi(2))
+            .sum::<f64>()
+            / n as f64;
+
+        let percentile = |p: usize| rtts[(n * p).div_ceil(100).max(1) - 1] as i32;
+
+        LinkQuality {
+            loss_rate: Some(loss_rate),
+            jitter_us: Some(variance.sqrt()),
+            p50_latency: Some(percentile(50)),
+            p95_latency: Some(percentile(95)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quality_window_stats() {
+        let mut window = QualityWindow::default();
+        assert_eq!(window.stats(), LinkQuality::default());
+
+        for (rtt, loss) in [(100, 0.0), (200, 0.1), (300, 0.2), (400, 0.1)] {
+            window.push(rtt, loss, 4);
+        }
+        let stats = window.stats();
+        assert!((stats.loss_rate.unwrap() - 0.1).abs() < 1e-9);
+        assert!((stats.jitter_us.unwrap() - 125.0_f64.sqrt() * 10.0).abs() < 1e-9);
+        assert_eq!(stats.p50_latency, Some(200));
+        assert_eq!(stats.p95_latency, Some(400));
+
+        // 超出窗口后丢弃最旧的样本
+        window.push(1000, 0.0, 4);
+        let stats = window.stats();
+        assert_eq!(stats.p50_latency, Some(300));
+        assert_eq!(stats.p95_latency, Some(1000));
+    }
+
+    #[test]
+    fn test_failed_checks_count_as_loss() {
+        let mut window = QualityWindow::default();
+        window.push_failure(4);
+        let stats = window.stats();
+        assert_eq!(stats.loss_rate, Some(1.0));
+        assert_eq!(stats.p50_latency, None);
+        assert_eq!(stats.jitter_us, None);
+
+        // 失败的检查计入丢包率，但不影响延迟分位数
+        for (rtt, loss) in [(100, 0.0), (200, 0.2), (300, 0.2)] {
+            window.push(rtt, loss, 4);
+        }
+        let stats = window.stats();
+        assert!((stats.loss_rate.unwrap() - 0.35).abs() < 1e-9);
+        assert_eq!(stats.p50_latency, Some(200));
+        assert_eq!(stats.p95_latency, Some(300));
+    }
+}
@@ -0,0 +1,208 @@
+//! 同一区域的多个探测节点按一致性哈希分配被监控节点
+//!
+//! 每个探测节点用相同的探测节点列表和后端节点ID构建同样的哈希环，因此无需额外协调就能得出一致的分配：
+//! 节点在环上顺时针遇到的前 `replicas` 个不同探测节点负责它，第一个为主探测节点，其余为备份。
+//! 探测节点停止活动后从列表中剔除，它负责的节点自动转移到环上的下一个探测节点。
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::backend_client::ProbeInfo;
+
+// Ring positions per probe, evens out the share of each probe
+const VIRTUAL_NODES: u32 = 64;
+
+/// 分片配置
+#[derive(Debug, Clone)]
+pub struct ShardConfig {
+    /// 每个节点由几个探测节点负责（主 + 备份），0 表示不分片，监控全部节点
+    pub replicas: usize,
+    /// 探测节点超过该时长没有活动即视为离线，它负责的节点重新分配
+    pub stale_after: chrono::Duration,
+}
+
+impl Default for ShardConfig {
+    fn default() -> Self {
+        Self {
+            replicas: 2,
+            stale_after: chrono::Duration::minutes(3),
+        }
+    }
+}
+
+/// 本探测节点对某个被监控节点的角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardRole {
+    Primary,
+    Backup,
+}
+
+/// 探测节点的一致性哈希环
+#[derive(Debug, Clone)]
+pub struct HashRing {
+    ring: BTreeMap<u64, String>,
+}
+
+impl HashRing {
+    pub fn new<S: AsRef<str>>(probe_ids: impl IntoIterator<Item = S>) -> Self {
+        let mut ring = BTreeMap::new();
+        for probe_id in probe_ids {
+            let probe_id = probe_id.as_ref();
+            for vnode in 0..VIRTUAL_NODES {
+                ring.insert(
+                    hash(&format!("{}#{}", probe_id, vnode)),
+                    probe_id.to_string(),
+                );
+            }
+        }
+        Self { ring }
+    }
+
+    /// 负责该节点的探测节点，按主、备份的顺序
+    pub fn owners(&self, peer_id: i32, replicas: usize) -> Vec<&str> {
+        let start = hash(&format!("peer:{}", peer_id));
+        let mut owners: Vec<&str> = Vec::with_capacity(replicas);
+        for probe_id in self
+            .ring
+            .range(start..)
+            .chain(self.ring.range(..start))
+            .map(|(_, id)| id)
+        {
+            if owners.len() == replicas {
+                break;
+            }
+            if !owners.contains(&probe_id.as_str()) {
+                owners.push(probe_id);
+            }
+        }
+        owners
+    }
+}
+
+/// 哈希值取 SHA-256 的前 8 字节，保证不同版本、不同平台的探测节点算出相同的环
+fn hash(key: &str) -> u64 {
+    let digest = Sha256::digest(key.as_bytes());
+    u64::from_be_bytes(digest[..8].try_into().expect("digest is 32 bytes"))
+}
+
+/// 本探测节点在当前探测节点列表下的分配
+#[derive(Debug, Clone)]
+pub struct ShardAssignment {
+    ring: HashRing,
+    self_id: String,
+    replicas: usize,
+    /// 参与分配的在线探测节点数量（包括自己）
+    pub probe_count: usize,
+}
+
+impl ShardAssignment {
+    /// 用在线的探测节点构建分配；自己总是参与分配，即使后端还没有记录到自己的活动
+    pub fn new(
+        self_id: &str,
+        probes: &[ProbeInfo],
+        config: &ShardConfig,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let mut live = probes
+            .iter()
+            .filter(|probe| {
+                probe
+                    .last_seen_at
+                    .is_some_and(|seen| now - seen <= config.stale_after)
+            })
+            .map(|probe| probe.probe_id.as_str())
+            .chain(std::iter::once(self_id))
+            .collect::<Vec<_>>();
+        live.sort_unstable();
+        live.dedup();
+
+        Self {
+            ring: HashRing::new(&live),
+            self_id: self_id.to_string(),
+            replicas: config.replicas,
+            probe_count: live.len(),
+        }
+    }
+
+    /// 本探测节点对该节点的角色，不负责时为 `None`
+    pub fn role(&self, peer_id: i32) -> Option<ShardRole> {
+        match self
+            .ring
+            .owners(peer_id, self.replicas)
+            .iter()
+            .position(|owner| *owner == self.self_id)?
+        {
+            0 => Some(ShardRole::Primary),
+            _ => Some(ShardRole::Backup),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(id: &str, seen_secs_ago: i64, now: DateTime<Utc>) -> ProbeInfo {
+        ProbeInfo {
+            probe_id: id.to_string(),
+            region: Some("cn-hz".to_string()),
+            last_seen_at: Some(now - chrono::Duration::seconds(seen_secs_ago)),
+        }
+    }
+
+    #[test]
+    fn test_each_node_has_primary_and_backup() {
+        let now = Utc::now();
+        let probes = ["a", "b", "c"].map(|id| probe(id, 10, now));
+        let config = ShardConfig::default();
+        let assignments = ["a", "b", "c"].map(|id| ShardAssignment::new(id, &probes, &config, now));
+
+        let mut primaries = [0; 3];
+        for peer_id in 0..300 {
+            let roles = assignments.each_ref().map(|a| a.role(peer_id));
+            let owners = roles.iter().filter(|r| r.is_some()).count();
+            assert_eq!(owners, 2, "peer {}", peer_id);
+            let primary = roles.iter().position(|r| *r == Some(ShardRole::Primary));
+            primaries[primary.unwrap()] += 1;
+        }
+        // 虚拟节点让各探测节点分到的主节点数量大致均衡
+        assert!(primaries.iter().all(|count| *count > 50), "{:?}", primaries);
+    }
+
+    #[test]
+    fn test_stale_probe_nodes_are_reassigned() {
+        let now = Utc::now();
+        let config = ShardConfig::default();
+        let all = ["a", "b", "c"].map(|id| probe(id, 10, now));
+        // c 已经超过 stale_after 没有活动
+        let without_c = [
+            probe("a", 10, now),
+            probe("b", 10, now),
+            probe("c", 600, now),
+        ];
+
+        let before = ShardAssignment::new("a", &all, &config, now);
+        let after = ShardAssignment::new("a", &without_c, &config, now);
+        assert_eq!(after.probe_count, 2);
+
+        for peer_id in 0..100 {
+            // 只剩两个探测节点时，a 负责全部节点
+            assert!(after.role(peer_id).is_some());
+            // 原本由 a 负责的节点保持不变，重新分配只影响 c 负责的节点
+            if before.role(peer_id) == Some(ShardRole::Primary) {
+                assert_eq!(after.role(peer_id), Some(ShardRole::Primary));
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_probe_owns_everything() {
+        let now = Utc::now();
+        // 后端还没有记录到自己时，自己仍然参与分配
+        let assignment = ShardAssignment::new("a", &[], &ShardConfig::default(), now);
+        assert_eq!(assignment.probe_count, 1);
+        assert!((0..50).all(|peer_id| assignment.role(peer_id) == Some(ShardRole::Primary)));
+    }
+}
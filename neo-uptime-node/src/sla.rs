@@ -0,0 +1,206 @@
+//! 根据每小时汇总计算节点的可用性 SLA
+//!
+//! 只使用已结束的小时：可用率是窗口内成功检查的占比；没有一次成功检查的小时视为故障，
+//! 连续的故障小时合并为一次故障，用于计算平均故障间隔（MTBF）和最长故障时长。
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use sea_orm::DbErr;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{
+    entity::{health_rollups, shared_nodes},
+    operations::RollupOperations,
+    Db,
+};
+
+const HOUR_SECS: i64 = 3600;
+// Longest window, also the range MTBF and outages are computed over
+const LOOKBACK_HOURS: i64 = 24 * 30;
+
+/// 各时间窗口内的可用率（百分比），窗口内没有检查时为 `None`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Availability {
+    pub last_1h: Option<f64>,
+    pub last_24h: Option<f64>,
+    pub last_7d: Option<f64>,
+    pub last_30d: Option<f64>,
+}
+
+/// 节点最近 30 天的 SLA
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeSla {
+    pub node_id: i32,
+    pub availability: Availability,
+    /// 平均故障间隔（秒）：正常时长除以故障次数，没有故障时为 `None`
+    pub mtbf_secs: Option<i64>,
+    pub outage_count: u32,
+    /// 最长一次故障的时长（秒），按小时计
+    pub longest_outage_secs: i64,
+    pub computed_at: DateTime<Utc>,
+}
+
+impl NodeSla {
+    /// 用按时间升序排列的汇总记录计算，`now` 所在的小时尚未结束，不参与计算
+    pub fn from_rollups(
+        node_id: i32,
+        rollups: &[health_rollups::Model],
+        now: DateTime<Utc>,
+    ) -> Self {
+        let current_bucket = RollupOperations::bucket_start(now);
+        let since = |hours: i64| current_bucket - chrono::Duration::hours(hours);
+        let rollups = rollups
+            .iter()
+            .filter(|r| {
+                let bucket = r.bucket_start.to_utc();
+                bucket >= since(LOOKBACK_HOURS) && bucket < current_bucket
+            })
+            .collect::<Vec<_>>();
+
+        let availability_since = |hours: i64| {
+            let (checks, successes) = rollups
+                .iter()
+                .filter(|r| r.bucket_start.to_utc() >= since(hours))
+                .fold((0i64, 0i64), |(checks, successes), r| {
+                    (
+                        checks + r.check_count as i64,
+                        successes + r.success_count as i64,
+                    )
+                });
+            (checks > 0).then(|| successes as f64 * 100.0 / checks as f64)
+        };
+
+        let mut outage_count = 0;
+        let mut longest_outage_hours = 0;
+        let mut up_hours = 0;
+        let mut current_outage: Option<(DateTime<Utc>, i64)> = None;
+        for rollup in rollups.iter().filter(|r| r.check_count > 0) {
+            let bucket = rollup.bucket_start.to_utc();
+            if rollup.success_count > 0 {
+                up_hours += 1;
+                current_outage = None;
+                continue;
+            }
+
+            // 紧接上一个故障小时的算同一次故障，中间缺少汇总则视为新的故障
+            let hours = match current_outage {
+                Some((last, hours)) if bucket - last == chrono::Duration::hours(1) => hours + 1,
+                _ => {
+                    outage_count += 1;
+                    1
+                }
+            };
+            longest_outage_hours = longest_outage_hours.max(hours);
+            current_outage = Some((bucket, hours));
+        }
+
+        Self {
+            node_id,
+            availability: Availability {
+                last_1h: availability_since(1),
+                last_24h: availability_since(24),
+                last_7d: availability_since(24 * 7),
+                last_30d: availability_since(LOOKBACK_HOURS),
+            },
+            mtbf_secs: (outage_count > 0).then(|| up_hours * HOUR_SECS / outage_count as i64),
+            outage_count,
+            longest_outage_secs: longest_outage_hours * HOUR_SECS,
+            computed_at: now,
+        }
+    }
+}
+
+/// 计算所需汇总记录的起点
+fn lookback_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    RollupOperations::bucket_start(now) - chrono::Duration::hours(LOOKBACK_HOURS)
+}
+
+/// 计算单个节点的 SLA
+pub async fn compute_node_sla(db: &Db, node_id: i32, now: DateTime<Utc>) -> Result<NodeSla, DbErr> {
+    let rollups = RollupOperations::get_node_rollups(db, node_id, lookback_start(now)).await?;
+    Ok(NodeSla::from_rollups(node_id, &rollups, now))
+}
+
+/// 计算一组节点的 SLA，按输入顺序返回
+pub async fn compute_nodes_sla(
+    db: &Db,
+    nodes: &[shared_nodes::Model],
+    now: DateTime<Utc>,
+) -> Result<Vec<NodeSla>, DbErr> {
+    let mut rollups_map: HashMap<i32, Vec<health_rollups::Model>> = HashMap::new();
+    for rollup in RollupOperations::get_rollups_since(db, lookback_start(now)).await? {
+        rollups_map.entry(rollup.node_id).or_default().push(rollup);
+    }
+
+    Ok(nodes
+        .iter()
+        .map(|node| {
+            let rollups = rollups_map.remove(&node.id).unwrap_or_default();
+            NodeSla::from_rollups(node.id, &rollups, now)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rollup(
+        now: DateTime<Utc>,
+        hours_ago: i64,
+        checks: i32,
+        successes: i32,
+    ) -> health_rollups::Model {
+        let bucket = RollupOperations::bucket_start(now) - chrono::Duration::hours(hours_ago);
+        health_rollups::Model {
+            id: 0,
+            node_id: 1,
+            bucket_start: bucket.fixed_offset(),
+            check_count: checks,
+            success_count: successes,
+            min_latency: None,
+            avg_latency: None,
+            p95_latency: None,
+            created_at: now.fixed_offset(),
+        }
+    }
+
+    #[test]
+    fn test_sla_without_rollups() {
+        let sla = NodeSla::from_rollups(1, &[], Utc::now());
+        assert_eq!(sla.availability, Availability::default());
+        assert_eq!(sla.mtbf_secs, None);
+        assert_eq!(sla.outage_count, 0);
+        assert_eq!(sla.longest_outage_secs, 0);
+    }
+
+    #[test]
+    fn test_sla_from_rollups() {
+        let now = Utc::now();
+        let mut rollups = vec![
+            // 超出 30 天的汇总不参与计算
+            rollup(now, 24 * 31, 10, 0),
+            // 两小时的故障
+            rollup(now, 48, 10, 0),
+            rollup(now, 47, 10, 0),
+            rollup(now, 46, 10, 10),
+            // 中间缺少汇总，算两次故障
+            rollup(now, 10, 10, 0),
+            rollup(now, 8, 10, 0),
+            rollup(now, 2, 10, 5),
+            rollup(now, 1, 10, 10),
+            // 当前小时尚未结束
+            rollup(now, 0, 10, 0),
+        ];
+        rollups.sort_by_key(|r| r.bucket_start);
+
+        let sla = NodeSla::from_rollups(1, &rollups, now);
+        assert_eq!(sla.availability.last_1h, Some(100.0));
+        assert_eq!(sla.availability.last_24h, Some(1500.0 / 40.0));
+        assert_eq!(sla.availability.last_30d, Some(2500.0 / 70.0));
+        assert_eq!(sla.outage_count, 3);
+        assert_eq!(sla.longest_outage_secs, 2 * HOUR_SECS);
+        assert_eq!(sla.mtbf_secs, Some(3 * HOUR_SECS / 3));
+    }
+}
@@ -0,0 +1,374 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use anyhow::Context as _;
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use serde::Serialize;
+use tokio::{net::TcpListener, sync::watch};
+use tracing::info;
+
+use crate::{
+    config::RuntimeSettings,
+    db::{
+        entity::{node_certificates, node_listeners, node_versions},
+        operations::{
+            CertificateOperations, ListenerOperations, NodeOperations, VersionOperations,
+        },
+        CertStatus, Db, LinkQuality, NodeState, ProbeErrorKind,
+    },
+    health_checker::HealthChecker,
+    metrics,
+    node_load::NodeLoad,
+    node_version::{self, VersionCount},
+    sla::{self, NodeSla},
+};
+
+#[derive(Clone)]
+struct ServerState {
+    db: Db,
+    health_checker: Arc<HealthChecker>,
+    settings: watch::Receiver<RuntimeSettings>,
+}
+
+/// GET /healthz 响应
+#[derive(Debug, Serialize)]
+struct HealthzResponse {
+    status: &'static str,
+    version: &'static str,
+    monitored_nodes: usize,
+}
+
+/// GET /nodes 中单个节点的当前状态
+#[derive(Debug, Serialize)]
+pub struct NodeStatusView {
+    pub node_id: i32,
+    pub name: String,
+    pub backend_peer_id: Option<i32>,
+    pub protocol: String,
+    pub host: String,
+    pub port: i32,
+    /// 节点最近一次上报的 EasyTier 版本，尚未检查到时为空
+    pub version: Option<String>,
+    /// 版本低于设置的最低版本
+    pub version_outdated: bool,
+    pub status: String,
+    pub state: NodeState,
+    pub state_since: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_check_time: Option<chrono::DateTime<chrono::Utc>>,
+    /// 节点从后端列表中消失后被停用的时间，停用的节点不再检查
+    pub deactivated_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// 节点分配给其他探测节点、本探测节点停止检查的时间
+    pub released_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub latency_ms: Option<f64>,
+    pub last_error: Option<String>,
+    pub last_error_kind: Option<ProbeErrorKind>,
+    /// 滑动窗口内的丢包率、抖动（微秒）和延迟分位数（微秒）
+    pub quality: LinkQuality,
+    /// 最近一次检查实测的外部网络负载，握手探测或检查失败时为空
+    pub load: Option<NodeLoad>,
+    /// 各监听地址（协议）最近一次独立检查的结果
+    pub listeners: Vec<ListenerStatusView>,
+    /// wss / quic 地址最近一次证书检查的结果
+    pub certificates: Vec<CertificateStatusView>,
+}
+
+/// 节点单个监听地址的检查结果
+#[derive(Debug, Serialize)]
+pub struct ListenerStatusView {
+    pub url: String,
+    pub protocol: String,
+    pub status: Option<String>,
+    pub latency_ms: Option<f64>,
+    pub last_error: Option<String>,
+    pub last_error_kind: Option<ProbeErrorKind>,
+    pub last_checked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<node_listeners::Model> for ListenerStatusView {
+    fn from(listener: node_listeners::Model) -> Self {
+        Self {
+            url: listener.url,
+            protocol: listener.protocol,
+            status: listener.last_status,
+            latency_ms: listener.last_response_time.map(|us| us as f64 / 1000.0),
+            last_error: listener.last_error_message,
+            last_error_kind: listener
+                .last_error_kind
+                .as_deref()
+                .map(ProbeErrorKind::from),
+            last_checked_at: listener.last_checked_at.map(|at| at.to_utc()),
+        }
+    }
+}
+
+/// 节点单个 wss / quic 地址的证书检查结果
+#[derive(Debug, Serialize)]
+pub struct CertificateStatusView {
+    pub url: String,
+    pub status: CertStatus,
+    pub subject: Option<String>,
+    pub issuer: Option<String>,
+    pub not_before: Option<chrono::DateTime<chrono::Utc>>,
+    pub not_after: Option<chrono::DateTime<chrono::Utc>>,
+    pub self_signed: Option<bool>,
+    pub hostname_match: Option<bool>,
+    pub trusted: Option<bool>,
+    pub last_error: Option<String>,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<node_certificates::Model> for CertificateStatusView {
+    fn from(cert: node_certificates::Model) -> Self {
+        Self {
+            url: cert.url,
+            status: CertStatus::from(cert.status.as_str()),
+            subject: cert.subject,
+            issuer: cert.issuer,
+            not_before: cert.not_before.map(|at| at.to_utc()),
+            not_after: cert.not_after.map(|at| at.to_utc()),
+            self_signed: cert.self_signed,
+            hostname_match: cert.hostname_match,
+            trusted: cert.trusted,
+            last_error: cert.last_error_message,
+            checked_at: cert.checked_at.to_utc(),
+        }
+    }
+}
+
+/// 节点运行某个版本的时间段
+#[derive(Debug, Serialize)]
+pub struct NodeVersionView {
+    pub version: String,
+    pub first_seen_at: chrono::DateTime<chrono::Utc>,
+    pub last_seen_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<node_versions::Model> for NodeVersionView {
+    fn from(version: node_versions::Model) -> Self {
+        Self {
+            version: version.version,
+            first_seen_at: version.first_seen_at.to_utc(),
+            last_seen_at: version.last_seen_at.to_utc(),
+        }
+    }
+}
+
+/// GET /versions 响应：监控中节点的版本分布
+#[derive(Debug, Serialize)]
+pub struct VersionDistribution {
+    pub min_version: Option<String>,
+    pub versions: Vec<VersionCount>,
+    /// 尚未检查到版本的节点数
+    pub unknown: usize,
+}
+
+/// 探测节点本地状态服务：/healthz、/nodes、/versions、/sla、/metrics
+pub struct StatusServer {
+    listener: TcpListener,
+    state: ServerState,
+}
+
+impl StatusServer {
+    /// 绑定监听地址，端口被占用时在启动阶段直接报错
+    pub async fn bind(
+        addr: SocketAddr,
+        db: Db,
+        health_checker: Arc<HealthChecker>,
+        settings: watch::Receiver<RuntimeSettings>,
+    ) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind status server on {}", addr))?;
+
+        Ok(Self {
+            listener,
+            state: ServerState {
+                db,
+                health_checker,
+                settings,
+            },
+        })
+    }
+
+    /// 在后台运行状态服务
+    pub fn start(self) -> tokio::task::JoinHandle<anyhow::Result<()>> {
+        let app = Router::new()
+            .route("/healthz", get(healthz))
+            .route("/nodes", get(nodes))
+            .route("/nodes/:id/sla", get(node_sla))
+            .route("/nodes/:id/versions", get(node_version_history))
+            .route("/versions", get(versions))
+            .route("/sla", get(all_sla))
+            .route("/metrics", get(prometheus_metrics))
+            .with_state(self.state);
+
+        if let Ok(addr) = self.listener.local_addr() {
+            info!("Status server listening on http://{}", addr);
+        }
+
+        let listener = self.listener;
+        tokio::spawn(async move {
+            axum::serve(listener, app)
+                .await
+                .context("Status server stopped")
+        })
+    }
+}
+
+async fn healthz(State(state): State<ServerState>) -> Json<HealthzResponse> {
+    Json(HealthzResponse {
+        status: "ok",
+        version: env!("CARGO_PKG_VERSION"),
+        monitored_nodes: state.health_checker.get_all_nodes_health_status().len(),
+    })
+}
+
+async fn nodes(
+    State(state): State<ServerState>,
+) -> Result<Json<Vec<NodeStatusView>>, (StatusCode, String)> {
+    let db_nodes = NodeOperations::get_all_nodes(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut listeners_map = ListenerOperations::get_listeners_map(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut certificates_map = CertificateOperations::get_certificates_map(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let min_version = state.settings.borrow().min_node_version.clone();
+
+    let views = db_nodes
+        .into_iter()
+        .map(|node| {
+            let record = state.health_checker.get_node_memory_record(node.id);
+            NodeStatusView {
+                node_id: node.id,
+                name: node.name,
+                backend_peer_id: node.backend_peer_id,
+                protocol: node.protocol,
+                host: node.host,
+                port: node.port,
+                version_outdated: node_version::is_outdated(&node.version, min_version.as_ref()),
+                version: Some(node.version).filter(|v| !v.is_empty()),
+                status: record
+                    .as_ref()
+                    .map(|r| r.get_current_health_status().to_string())
+                    .unwrap_or_else(|| "unknown".to_string()),
+                state: record
+                    .as_ref()
+                    .map(|r| r.get_node_state())
+                    .unwrap_or(NodeState::Unknown),
+                state_since: record.as_ref().map(|r| r.get_node_state_since()),
+                last_check_time: record.as_ref().map(|r| r.get_last_check_time()),
+                deactivated_at: node.deactivated_at.map(|at| at.to_utc()),
+                released_at: node.released_at.map(|at| at.to_utc()),
+                latency_ms: record
+                    .as_ref()
+                    .and_then(|r| r.get_last_response_time())
+                    .map(|us| us as f64 / 1000.0),
+                last_error: record
+                    .as_ref()
+                    .and_then(|r| r.get_last_error_info().clone()),
+                last_error_kind: record.as_ref().and_then(|r| r.get_last_error_kind()),
+                load: record.as_ref().and_then(|r| r.get_last_load().cloned()),
+                quality: record.map(|r| r.get_link_quality()).unwrap_or_default(),
+                listeners: listeners_map
+                    .remove(&node.id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(ListenerStatusView::from)
+                    .collect(),
+                certificates: certificates_map
+                    .remove(&node.id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(CertificateStatusView::from)
+                    .collect(),
+            }
+        })
+        .collect();
+
+    Ok(Json(views))
+}
+
+async fn versions(
+    State(state): State<ServerState>,
+) -> Result<Json<VersionDistribution>, (StatusCode, String)> {
+    let db_nodes = NodeOperations::get_monitored_nodes(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let min_version = state.settings.borrow().min_node_version.clone();
+
+    Ok(Json(VersionDistribution {
+        min_version: min_version.as_ref().map(|v| v.to_string()),
+        versions: node_version::version_distribution(
+            db_nodes.iter().map(|node| node.version.as_str()),
+            min_version.as_ref(),
+        ),
+        unknown: db_nodes
+            .iter()
+            .filter(|node| node.version.is_empty())
+            .count(),
+    }))
+}
+
+async fn node_version_history(
+    State(state): State<ServerState>,
+    Path(node_id): Path<i32>,
+) -> Result<Json<Vec<NodeVersionView>>, (StatusCode, String)> {
+    NodeOperations::get_node_by_id(&state.db, node_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("node {} not found", node_id)))?;
+    let history = VersionOperations::get_node_versions(&state.db, node_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(
+        history.into_iter().map(NodeVersionView::from).collect(),
+    ))
+}
+
+async fn all_sla(
+    State(state): State<ServerState>,
+) -> Result<Json<Vec<NodeSla>>, (StatusCode, String)> {
+    let db_nodes = NodeOperations::get_all_nodes(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let slas = sla::compute_nodes_sla(&state.db, &db_nodes, chrono::Utc::now())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(slas))
+}
+
+async fn node_sla(
+    State(state): State<ServerState>,
+    Path(node_id): Path<i32>,
+) -> Result<Json<NodeSla>, (StatusCode, String)> {
+    NodeOperations::get_node_by_id(&state.db, node_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("node {} not found", node_id)))?;
+    let sla = sla::compute_node_sla(&state.db, node_id, chrono::Utc::now())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(sla))
+}
+
+async fn prometheus_metrics(State(state): State<ServerState>) -> impl IntoResponse {
+    let node_states = state
+        .health_checker
+        .get_all_nodes_state()
+        .into_iter()
+        .map(|(_, node_state)| node_state)
+        .collect::<Vec<_>>();
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::global().render(&node_states),
+    )
+}
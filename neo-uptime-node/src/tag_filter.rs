@@ -0,0 +1,194 @@
+use std::{collections::HashSet, str::FromStr};
+
+use sea_orm::DbErr;
+
+use crate::db::{operations::NodeOperations, Db};
+
+/// 标签表达式：用 `+` 连接的多个标签，节点须同时带有全部标签才匹配，如 `asia+relay`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagExpr {
+    tags: Vec<String>,
+}
+
+impl FromStr for TagExpr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tags = s
+            .split('+')
+            .map(|tag| tag.trim().to_string())
+            .collect::<Vec<_>>();
+        if tags.iter().any(|tag| tag.is_empty()) {
+            return Err(format!(
+                "invalid tag expression '{}', expected tag[+tag...]",
+                s
+            ));
+        }
+        Ok(Self { tags })
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for TagExpr {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <String as serde::Deserialize>::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl std::fmt::Display for TagExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.tags.join("+"))
+    }
+}
+
+/// 按标签选择要监控的节点
+///
+/// 设置了 `include` 时，节点须匹配其中任一表达式；匹配 `exclude` 中任一表达式的节点总是被排除。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagFilter {
+    pub include: Vec<TagExpr>,
+    pub exclude: Vec<TagExpr>,
+}
+
+impl TagFilter {
+    /// 未设置任何表达式时监控全部节点
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// 从 `node_ids` 中移除不匹配的节点，返回被移除的节点
+    pub async fn retain_matching(
+        &self,
+        db: &Db,
+        node_ids: &mut HashSet<i32>,
+    ) -> Result<HashSet<i32>, DbErr> {
+        let mut filtered_out = HashSet::new();
+        if self.is_empty() {
+            return Ok(filtered_out);
+        }
+
+        let included = if self.include.is_empty() {
+            None
+        } else {
+            Some(node_ids_matching_any(db, &self.include).await?)
+        };
+        let excluded = node_ids_matching_any(db, &self.exclude).await?;
+        node_ids.retain(|node_id| {
+            let matches = included.as_ref().is_none_or(|ids| ids.contains(node_id))
+                && !excluded.contains(node_id);
+            if !matches {
+                filtered_out.insert(*node_id);
+            }
+            matches
+        });
+        Ok(filtered_out)
+    }
+}
+
+/// 匹配任一表达式的节点：单个标签的表达式一次查出，多个标签的表达式取各标签节点的交集
+async fn node_ids_matching_any(db: &Db, exprs: &[TagExpr]) -> Result<HashSet<i32>, DbErr> {
+    let (single, multi): (Vec<_>, Vec<_>) = exprs.iter().partition(|expr| expr.tags.len() == 1);
+
+    let single_tags = single
+        .iter()
+        .map(|expr| expr.tags[0].clone())
+        .collect::<Vec<_>>();
+    let mut node_ids = NodeOperations::filter_node_ids_by_tags_any(db, &single_tags)
+        .await?
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+    for expr in multi {
+        let mut expr_ids: Option<HashSet<i32>> = None;
+        for tag in &expr.tags {
+            let tagged = NodeOperations::filter_node_ids_by_tag(db, tag)
+                .await?
+                .into_iter()
+                .collect::<HashSet<_>>();
+            expr_ids = Some(match expr_ids {
+                Some(ids) => ids.intersection(&tagged).copied().collect(),
+                None => tagged,
+            });
+        }
+        node_ids.extend(expr_ids.unwrap_or_default());
+    }
+    Ok(node_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::operations::tests::test_node;
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    fn exprs(exprs: &[&str]) -> Vec<TagExpr> {
+        exprs.iter().map(|e| e.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse_tag_expr() {
+        let expr: TagExpr = " asia + relay ".parse().unwrap();
+        assert_eq!(expr.to_string(), "asia+relay");
+        assert!("asia+".parse::<TagExpr>().is_err());
+        assert!("".parse::<TagExpr>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tag_filter() {
+        let db = Db::memory_db().await;
+        let mut nodes = Vec::new();
+        for (name, node_tags) in [
+            ("asia-relay", tags(&["asia", "relay", "cn"])),
+            ("europe", tags(&["europe"])),
+            ("asia", tags(&["asia"])),
+            ("untagged", Vec::new()),
+            ("europe-maintenance", tags(&["europe", "maintenance"])),
+        ] {
+            let node = NodeOperations::create_node(&db, test_node(name))
+                .await
+                .unwrap();
+            NodeOperations::set_node_tags(&db, node.id, node_tags)
+                .await
+                .unwrap();
+            nodes.push(node.id);
+        }
+        let [asia_relay, europe, asia, untagged, europe_maintenance] = nodes[..] else {
+            unreachable!()
+        };
+        let all = nodes.iter().copied().collect::<HashSet<_>>();
+
+        let mut kept = all.clone();
+        let filtered_out = TagFilter::default()
+            .retain_matching(&db, &mut kept)
+            .await
+            .unwrap();
+        assert_eq!(kept, all);
+        assert!(filtered_out.is_empty());
+
+        let filter = TagFilter {
+            include: exprs(&["asia+relay", "europe"]),
+            exclude: exprs(&["maintenance"]),
+        };
+        let mut kept = all.clone();
+        let filtered_out = filter.retain_matching(&db, &mut kept).await.unwrap();
+        assert_eq!(kept, HashSet::from([asia_relay, europe]));
+        // 表达式内的标签须全部匹配；设置了 include 时，没有标签的节点不监控
+        assert_eq!(
+            filtered_out,
+            HashSet::from([asia, untagged, europe_maintenance])
+        );
+
+        let exclude_only = TagFilter {
+            include: Vec::new(),
+            exclude: exprs(&["maintenance"]),
+        };
+        let mut kept = all.clone();
+        let filtered_out = exclude_only.retain_matching(&db, &mut kept).await.unwrap();
+        assert!(kept.contains(&untagged));
+        assert_eq!(filtered_out, HashSet::from([europe_maintenance]));
+    }
+}
@@ -0,0 +1,472 @@
+use std::{
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::Context as _;
+use chrono::{DateTime, Utc};
+use der::{
+    asn1::{AnyRef, GeneralizedTime, ObjectIdentifier, UtcTime},
+    Decode, Reader, SliceReader, Tag, TagNumber, Tagged,
+};
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::CryptoProvider,
+    pki_types::{CertificateDer, ServerName, UnixTime},
+    ClientConfig, DigitallySignedStruct, SignatureScheme,
+};
+use tokio::net::TcpStream;
+use tracing::debug;
+
+use crate::cert_check::CertificateInfo;
+
+// Upper bound for connecting and completing the TLS handshake
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 只记录服务端证书链、不做校验的验证器
+///
+/// 过期、自签名或域名不匹配的证书同样要拿到，校验在握手结束后单独进行；签名仍按正常流程验证。
+#[derive(Debug)]
+struct CaptureServerCert {
+    provider: Arc<CryptoProvider>,
+    chain: Mutex<Vec<CertificateDer<'static>>>,
+}
+
+impl CaptureServerCert {
+    fn take_chain(&self) -> Vec<CertificateDer<'static>> {
+        std::mem::take(&mut self.chain.lock().unwrap())
+    }
+}
+
+impl ServerCertVerifier for CaptureServerCert {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        *self.chain.lock().unwrap() = std::iter::once(end_entity)
+            .chain(intermediates)
+            .map(|cert| cert.clone().into_owned())
+            .collect();
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// 与 `url` 完成一次 TLS（wss）或 QUIC（quic）握手，取回节点出示的证书并校验
+pub async fn fetch_certificate(url: &str) -> anyhow::Result<CertificateInfo> {
+    let parsed = url::Url::parse(url).with_context(|| format!("invalid url {}", url))?;
+    let host = parsed
+        .host_str()
+        .with_context(|| format!("url {} has no host", url))?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = parsed
+        .port_or_known_default()
+        .with_context(|| format!("url {} has no port", url))?;
+
+    // 证书按域名或 IP 校验；握手时的 SNI 与 EasyTier 客户端一致，quic 和 IP 地址使用 localhost
+    let ip = host.parse::<IpAddr>().ok();
+    let server_name = match ip {
+        Some(ip) => ServerName::IpAddress(ip.into()),
+        None => ServerName::try_from(host.clone())
+            .with_context(|| format!("invalid host name {}", host))?,
+    };
+    let sni = if parsed.scheme() == "quic" || ip.is_some() {
+        ServerName::try_from("localhost")?
+    } else {
+        server_name.clone()
+    };
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier = Arc::new(CaptureServerCert {
+        provider: provider.clone(),
+        chain: Mutex::new(Vec::new()),
+    });
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(verifier.clone())
+        .with_no_client_auth();
+
+    let handshake = async {
+        match parsed.scheme() {
+            "wss" => tls_handshake(&host, port, config, sni).await,
+            "quic" => quic_handshake(&host, port, config, sni).await,
+            scheme => anyhow::bail!("{} does not use tls", scheme),
+        }
+    };
+    tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, handshake)
+        .await
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "tls handshake with {} timed out after {:?}",
+                url,
+                TLS_HANDSHAKE_TIMEOUT
+            )
+        })?
+        .with_context(|| format!("tls handshake with {} failed", url))?;
+
+    let chain = verifier.take_chain();
+    debug!("{} presented {} certificates", url, chain.len());
+    inspect_chain(&chain, &server_name)
+}
+
+async fn tls_handshake(
+    host: &str,
+    port: u16,
+    config: ClientConfig,
+    sni: ServerName<'static>,
+) -> anyhow::Result<()> {
+    let stream = TcpStream::connect((host, port)).await?;
+    let connector = tokio_rustls::TlsConnector::from(Arc::new(config));
+    connector.connect(sni, stream).await?;
+    Ok(())
+}
+
+async fn quic_handshake(
+    host: &str,
+    port: u16,
+    config: ClientConfig,
+    sni: ServerName<'static>,
+) -> anyhow::Result<()> {
+    let addr = tokio::net::lookup_host((host, port))
+        .await?
+        .next()
+        .with_context(|| format!("{} did not resolve to any address", host))?;
+    let bind_addr = if addr.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+
+    let mut endpoint = quinn::Endpoint::client(bind_addr.parse()?)?;
+    endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(
+        quinn::crypto::rustls::QuicClientConfig::try_from(config)?,
+    )));
+    let connection = endpoint.connect(addr, &sni.to_str())?.await?;
+    connection.close(0u32.into(), b"");
+    endpoint.wait_idle().await;
+    Ok(())
+}
+
+/// 解析终端证书，校验域名和证书链
+fn inspect_chain(
+    chain: &[CertificateDer<'_>],
+    server_name: &ServerName<'_>,
+) -> anyhow::Result<CertificateInfo> {
+    let end_entity = chain.first().context("no certificate presented")?;
+    let cert = webpki::EndEntityCert::try_from(end_entity)
+        .map_err(|e| anyhow::anyhow!("failed to parse certificate: {}", e))?;
+    let (not_before, not_after) = parse_validity(end_entity)
+        .map_err(|e| anyhow::anyhow!("failed to parse certificate validity: {}", e))?;
+
+    let hostname_match = cert.verify_is_valid_for_subject_name(server_name).is_ok();
+    // 在证书有效期中点校验证书链，过期单独判定，不影响是否受信任
+    let midpoint = (not_before.timestamp() + not_after.timestamp()) / 2;
+    let verify_at = UnixTime::since_unix_epoch(Duration::from_secs(midpoint.max(0) as u64));
+    let trusted = cert
+        .verify_for_usage(
+            webpki::ALL_VERIFICATION_ALGS,
+            webpki_roots::TLS_SERVER_ROOTS,
+            &chain[1..],
+            verify_at,
+            webpki::KeyUsage::server_auth(),
+            None,
+            None,
+        )
+        .is_ok();
+
+    Ok(CertificateInfo {
+        subject: format_name(cert.subject()),
+        issuer: format_name(cert.issuer()),
+        not_before,
+        not_after,
+        self_signed: cert.subject() == cert.issuer(),
+        hostname_match,
+        trusted,
+    })
+}
+
+/// 从证书的 TBSCertificate 中读取有效期（notBefore, notAfter）
+fn parse_validity(cert_der: &[u8]) -> der::Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let cert = AnyRef::from_der(cert_der)?;
+    let tbs = AnyRef::decode(&mut SliceReader::new(cert.value())?)?;
+
+    // version [0] 可选，validity 之前依次是 serialNumber、signature、issuer
+    let mut fields = SliceReader::new(tbs.value())?;
+    let version_tag = Tag::ContextSpecific {
+        constructed: true,
+        number: TagNumber::N0,
+    };
+    let skip = if AnyRef::decode(&mut fields)?.tag() == version_tag {
+        3
+    } else {
+        2
+    };
+    for _ in 0..skip {
+        AnyRef::decode(&mut fields)?;
+    }
+    let validity = AnyRef::decode(&mut fields)?;
+
+    let mut times = SliceReader::new(validity.value())?;
+    let not_before = decode_time(AnyRef::decode(&mut times)?)?;
+    let not_after = decode_time(AnyRef::decode(&mut times)?)?;
+    Ok((not_before, not_after))
+}
+
+/// X.509 的 Time 可以是 UTCTime 或 GeneralizedTime
+fn decode_time(time: AnyRef<'_>) -> der::Result<DateTime<Utc>> {
+    let since_epoch = match time.tag() {
+        Tag::UtcTime => UtcTime::try_from(time)?.to_unix_duration(),
+        _ => GeneralizedTime::try_from(time)?.to_unix_duration(),
+    };
+    DateTime::from_timestamp(since_epoch.as_secs() as i64, 0)
+        .ok_or_else(|| der::Error::from(der::ErrorKind::DateTime))
+}
+
+/// 将 webpki 给出的 Name（RDNSequence 的内容）格式化为 `CN=example.com, O=Example`
+fn format_name(rdns: &[u8]) -> String {
+    fn attributes(rdns: &[u8]) -> der::Result<Vec<String>> {
+        let mut parts = Vec::new();
+        let mut sets = SliceReader::new(rdns)?;
+        while !sets.is_finished() {
+            let set = AnyRef::decode(&mut sets)?;
+            let mut attrs = SliceReader::new(set.value())?;
+            while !attrs.is_finished() {
+                let attr = AnyRef::decode(&mut attrs)?;
+                let mut attr = SliceReader::new(attr.value())?;
+                let oid = ObjectIdentifier::decode(&mut attr)?;
+                let value = AnyRef::decode(&mut attr)?;
+                let name = match oid.to_string().as_str() {
+                    "2.5.4.3" => "CN".to_string(),
+                    "2.5.4.6" => "C".to_string(),
+                    "2.5.4.7" => "L".to_string(),
+                    "2.5.4.8" => "ST".to_string(),
+                    "2.5.4.10" => "O".to_string(),
+                    "2.5.4.11" => "OU".to_string(),
+                    other => other.to_string(),
+                };
+                parts.push(format!(
+                    "{}={}",
+                    name,
+                    String::from_utf8_lossy(value.value())
+                ));
+            }
+        }
+        Ok(parts)
+    }
+
+    attributes(rdns)
+        .map(|parts| parts.join(", "))
+        .unwrap_or_else(|_| "<unparseable>".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use der::Encode;
+    use rcgen::{date_time_ymd, Certificate, CertificateParams, DnType};
+
+    use super::*;
+    use crate::db::CertStatus;
+
+    fn params(
+        names: &[&str],
+        not_before: (i32, u8, u8),
+        not_after: (i32, u8, u8),
+    ) -> CertificateParams {
+        let mut params = CertificateParams::new(
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>(),
+        );
+        params.distinguished_name = rcgen::DistinguishedName::new();
+        params.distinguished_name.push(DnType::CommonName, names[0]);
+        params
+            .distinguished_name
+            .push(DnType::OrganizationName, "Neo Uptime");
+        params.not_before = date_time_ymd(not_before.0, not_before.1, not_before.2);
+        params.not_after = date_time_ymd(not_after.0, not_after.1, not_after.2);
+        params
+    }
+
+    fn self_signed(names: &[&str], not_before: (i32, u8, u8), not_after: (i32, u8, u8)) -> Vec<u8> {
+        Certificate::from_params(params(names, not_before, not_after))
+            .unwrap()
+            .serialize_der()
+            .unwrap()
+    }
+
+    fn utc(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        chrono::NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    /// 把 `content` 包装成一个 DER SEQUENCE
+    fn sequence(content: &[u8]) -> Vec<u8> {
+        let seq = AnyRef::new(Tag::Sequence, content).unwrap();
+        let mut buf = vec![0; usize::try_from(seq.encoded_len().unwrap()).unwrap()];
+        seq.encode_to_slice(&mut buf).unwrap();
+        buf
+    }
+
+    fn inspect(cert_der: Vec<u8>, host: &str) -> CertificateInfo {
+        let server_name = ServerName::try_from(host.to_string()).unwrap();
+        inspect_chain(&[CertificateDer::from(cert_der)], &server_name).unwrap()
+    }
+
+    #[test]
+    fn test_inspect_self_signed_certificate() {
+        let info = inspect(
+            self_signed(&["node.example.com"], (2020, 1, 1), (2999, 1, 1)),
+            "node.example.com",
+        );
+
+        assert_eq!(info.subject, "CN=node.example.com, O=Neo Uptime");
+        assert_eq!(info.issuer, info.subject);
+        assert_eq!(info.not_before, utc(2020, 1, 1));
+        assert_eq!(info.not_after, utc(2999, 1, 1));
+        assert!(info.self_signed);
+        assert!(info.hostname_match);
+        assert!(!info.trusted);
+        assert_eq!(
+            info.status(Utc::now(), Duration::from_secs(14 * 86400)),
+            CertStatus::SelfSigned
+        );
+    }
+
+    #[test]
+    fn test_inspect_expired_certificate() {
+        let info = inspect(
+            self_signed(&["node.example.com"], (2020, 1, 1), (2021, 1, 1)),
+            "node.example.com",
+        );
+
+        assert_eq!(info.not_before, utc(2020, 1, 1));
+        assert_eq!(info.not_after, utc(2021, 1, 1));
+        assert_eq!(
+            info.status(Utc::now(), Duration::from_secs(14 * 86400)),
+            CertStatus::Expired
+        );
+    }
+
+    #[test]
+    fn test_inspect_hostname_mismatch() {
+        // 由测试 CA 签发的证书，不是自签名，也不在受信任的根证书中
+        let mut ca_params = params(&["Test CA"], (2020, 1, 1), (2999, 1, 1));
+        ca_params.subject_alt_names.clear();
+        ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let ca = Certificate::from_params(ca_params).unwrap();
+        let leaf =
+            Certificate::from_params(params(&["node.example.com"], (2020, 1, 1), (2999, 1, 1)))
+                .unwrap()
+                .serialize_der_with_signer(&ca)
+                .unwrap();
+
+        let info = inspect(leaf.clone(), "other.example.com");
+        assert_eq!(info.issuer, "CN=Test CA, O=Neo Uptime");
+        assert!(!info.self_signed);
+        assert!(!info.hostname_match);
+        assert!(!info.trusted);
+        assert_eq!(
+            info.status(Utc::now(), Duration::from_secs(14 * 86400)),
+            CertStatus::HostnameMismatch
+        );
+
+        assert!(inspect(leaf, "node.example.com").hostname_match);
+    }
+
+    #[test]
+    fn test_parse_validity_without_version() {
+        let cert_der = self_signed(&["node.example.com"], (2020, 1, 1), (2021, 1, 1));
+
+        // 去掉 TBSCertificate 中的 version [0]，得到 v1 证书的结构（签名不再有效，不影响解析）
+        let cert = AnyRef::from_der(&cert_der).unwrap();
+        let mut outer = SliceReader::new(cert.value()).unwrap();
+        let tbs = AnyRef::decode(&mut outer).unwrap();
+        let mut fields = SliceReader::new(tbs.value()).unwrap();
+        assert_eq!(
+            AnyRef::decode(&mut fields).unwrap().tag(),
+            Tag::ContextSpecific {
+                constructed: true,
+                number: TagNumber::N0,
+            }
+        );
+        let v1_fields = fields.read_slice(fields.remaining_len()).unwrap();
+        let v1_tbs = sequence(v1_fields);
+        let rest = outer.read_slice(outer.remaining_len()).unwrap();
+        let v1_cert = sequence(&[v1_tbs.as_slice(), rest].concat());
+
+        assert_eq!(
+            parse_validity(&v1_cert).unwrap(),
+            (utc(2020, 1, 1), utc(2021, 1, 1))
+        );
+        assert_eq!(
+            parse_validity(&v1_cert).unwrap(),
+            parse_validity(&cert_der).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_format_name() {
+        let mut params = params(&["node.example.com"], (2020, 1, 1), (2999, 1, 1));
+        params.distinguished_name.push(DnType::CountryName, "CN");
+        let cert_der = Certificate::from_params(params)
+            .unwrap()
+            .serialize_der()
+            .unwrap();
+        let cert_der = CertificateDer::from(cert_der);
+        let cert = webpki::EndEntityCert::try_from(&cert_der).unwrap();
+
+        assert_eq!(
+            format_name(cert.subject()),
+            "CN=node.example.com, O=Neo Uptime, C=CN"
+        );
+        assert_eq!(format_name(&[0x31, 0x05, 0x30]), "<unparseable>");
+        assert_eq!(format_name(&[]), "");
+    }
+}